@@ -0,0 +1,79 @@
+use crate::{Client, ClientTrait, PeerId};
+use std::collections::HashSet;
+#[derive(Copy, Debug, Clone, Hash, PartialEq, Eq)]
+pub enum AccessRule {
+    Peer(PeerId),
+}
+impl From<PeerId> for AccessRule {
+    fn from(value: PeerId) -> Self {
+        Self::Peer(value)
+    }
+}
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Access {
+    denied: HashSet<AccessRule>,
+    allowed: Option<HashSet<AccessRule>>,
+}
+impl Access {
+    pub(crate) fn deny(&mut self, rule: AccessRule) {
+        self.denied.insert(rule);
+    }
+    pub(crate) fn allow_only(&mut self, rules: Vec<AccessRule>) {
+        self.allowed = Some(rules.into_iter().collect());
+    }
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default()
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.denied.is_empty() && self.allowed.is_none()
+    }
+    pub(crate) fn permits(&self, peer: PeerId) -> bool {
+        let rule = AccessRule::Peer(peer);
+        !self.denied.contains(&rule)
+            && self
+                .allowed
+                .as_ref()
+                .is_none_or(|allowed| allowed.contains(&rule))
+    }
+}
+impl Client {
+    pub fn deny(&mut self, rule: impl Into<AccessRule>) {
+        self.access.deny(rule.into());
+        self.enforce_access()
+    }
+    pub fn allow_only(&mut self, rules: Vec<AccessRule>) {
+        self.access.allow_only(rules);
+        self.enforce_access()
+    }
+    pub fn clear_access(&mut self) {
+        self.access.clear();
+        self.enforce_access()
+    }
+    pub(crate) fn enforce_access(&mut self) {
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &mut self.steam_client {
+            steam.access = self.access.clone();
+        }
+        if !self.is_host() {
+            return;
+        }
+        for peer in self.peers() {
+            if !self.access.permits(peer) {
+                let _ = self.kick(peer, "denied");
+            }
+        }
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_access() {
+    let mut access = Access::default();
+    assert!(access.is_empty() && access.permits(PeerId(3)));
+    access.allow_only(vec![PeerId(1).into(), PeerId(2).into()]);
+    access.deny(PeerId(2).into());
+    assert!(access.permits(PeerId(1)));
+    assert!(!access.permits(PeerId(2)));
+    assert!(!access.permits(PeerId(3)));
+    access.clear();
+    assert!(access.permits(PeerId(2)));
+}
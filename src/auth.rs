@@ -0,0 +1,148 @@
+use crate::{Client, ClientTrait, MsgType, NetError, NetEvent, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashMap;
+use steamworks::networking_types::NetworkingIdentity;
+use steamworks::{AuthTicket, CallbackResult, SteamId};
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthResult {
+    Valid { owner: PeerId },
+    Rejected(String),
+}
+#[derive(Default)]
+pub(crate) struct Auth {
+    tickets: Vec<AuthTicket>,
+    pending: HashMap<SteamId, PeerId>,
+    verified: HashMap<PeerId, SteamId>,
+}
+impl Client {
+    pub fn request_auth(&mut self, peer: PeerId) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Err(NetError::Unsupported);
+        }
+        self.send_packet(peer, encode(&MsgType::AuthRequest), Reliability::Reliable)
+    }
+    pub fn is_authenticated(&self, peer: PeerId) -> bool {
+        self.auth.verified.contains_key(&peer)
+    }
+    pub fn peer_steam_id(&self, peer: PeerId) -> Option<SteamId> {
+        self.auth.verified.get(&peer).copied()
+    }
+    pub(crate) fn receive_auth_request(&mut self, src: PeerId) {
+        let Some(steam) = &self.steam_client else {
+            return;
+        };
+        if src != self.host_id() {
+            return;
+        }
+        let user = steam.steamworks.user();
+        let (ticket, data) = user.authentication_session_ticket(NetworkingIdentity::new());
+        self.auth.tickets.push(ticket);
+        let packet = encode(&MsgType::AuthTicket {
+            steam_id: user.steam_id().raw(),
+            ticket: data,
+        });
+        let _ = self.send_packet(src, packet, Reliability::Reliable);
+    }
+    pub(crate) fn receive_auth_ticket(&mut self, src: PeerId, steam_id: u64, ticket: Vec<u8>) {
+        if !self.is_host() {
+            return;
+        }
+        let steam_id = SteamId::from_raw(steam_id);
+        if !self.is_ip() && !self.is_bridged(src) && PeerId::from(steam_id) != src {
+            return self.auth_result(src, AuthResult::Rejected("steam id mismatch".to_owned()));
+        }
+        let Some(steam) = &self.steam_client else {
+            return self.auth_result(src, AuthResult::Rejected("steam unavailable".to_owned()));
+        };
+        let user = steam.steamworks.user();
+        match user.begin_authentication_session(steam_id, &ticket) {
+            Ok(()) => {
+                self.auth.pending.insert(steam_id, src);
+            }
+            Err(e) => self.auth_result(src, AuthResult::Rejected(e.to_string())),
+        }
+    }
+    fn auth_result(&mut self, peer: PeerId, result: AuthResult) {
+        let rejected = matches!(result, AuthResult::Rejected(_));
+        self.net_events
+            .push_back(NetEvent::AuthResult { peer, result });
+        if rejected && self.config.require_steam_auth {
+            let _ = self.kick(peer, "steam authentication failed");
+        }
+    }
+    pub(crate) fn pump_auth(&mut self) {
+        let is_ip = self.is_ip();
+        let Some(steam) = &mut self.steam_client else {
+            return;
+        };
+        if is_ip && !self.auth.pending.is_empty() {
+            let auth = &mut steam.auth;
+            steam.steamworks.clone().process_callbacks(|callback| {
+                if let CallbackResult::ValidateAuthTicketResponse(response) = callback {
+                    auth.push_back(response)
+                }
+            });
+        }
+        while let Some(response) = self
+            .steam_client
+            .as_mut()
+            .and_then(|steam| steam.auth.pop_front())
+        {
+            let peer = match self.auth.pending.remove(&response.steam_id) {
+                Some(peer) => peer,
+                None => match self
+                    .auth
+                    .verified
+                    .iter()
+                    .find(|(_, id)| **id == response.steam_id)
+                {
+                    Some((peer, _)) => *peer,
+                    None => continue,
+                },
+            };
+            let result = match response.response {
+                Ok(()) => {
+                    self.auth.verified.insert(peer, response.steam_id);
+                    AuthResult::Valid {
+                        owner: response.owner_steam_id.into(),
+                    }
+                }
+                Err(e) => {
+                    self.auth.verified.remove(&peer);
+                    if let Some(steam) = &self.steam_client {
+                        steam
+                            .steamworks
+                            .user()
+                            .end_authentication_session(response.steam_id);
+                    }
+                    AuthResult::Rejected(e.to_string())
+                }
+            };
+            self.auth_result(peer, result);
+        }
+    }
+    pub(crate) fn end_auth(&mut self, peer: PeerId) {
+        let Some(steam) = &self.steam_client else {
+            return;
+        };
+        let user = steam.steamworks.user();
+        if let Some(steam_id) = self.auth.verified.remove(&peer) {
+            user.end_authentication_session(steam_id)
+        }
+        self.auth.pending.retain(|steam_id, p| {
+            if *p == peer {
+                user.end_authentication_session(*steam_id)
+            }
+            *p != peer
+        });
+    }
+    pub(crate) fn cancel_auth_tickets(&mut self) {
+        let Some(steam) = &self.steam_client else {
+            return;
+        };
+        let user = steam.steamworks.user();
+        for ticket in self.auth.tickets.drain(..) {
+            user.cancel_authentication_ticket(ticket)
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::Client;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+pub struct BackgroundClient {
+    client: Arc<Mutex<Client>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+impl BackgroundClient {
+    pub fn lock(&self) -> MutexGuard<'_, Client> {
+        self.client.lock().unwrap()
+    }
+    pub fn shared(&self) -> Arc<Mutex<Client>> {
+        self.client.clone()
+    }
+    pub fn stop(mut self) -> Option<Client> {
+        self.join();
+        let client = self.client.clone();
+        drop(self);
+        Arc::into_inner(client).map(|c| c.into_inner().unwrap())
+    }
+    fn join(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+impl Drop for BackgroundClient {
+    fn drop(&mut self) {
+        self.join()
+    }
+}
+impl Client {
+    pub fn spawn_background(self, rate: Duration) -> BackgroundClient {
+        let client = Arc::new(Mutex::new(self));
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let client = client.clone();
+            let running = running.clone();
+            std::thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    let start = Instant::now();
+                    {
+                        let mut client = client.lock().unwrap();
+                        let _ = client.update();
+                        client.poll_transport();
+                    }
+                    std::thread::sleep(rate.saturating_sub(start.elapsed()));
+                }
+            })
+        };
+        BackgroundClient {
+            client,
+            running,
+            thread: Some(thread),
+        }
+    }
+}
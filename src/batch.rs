@@ -0,0 +1,86 @@
+use crate::{Client, MsgType, PeerId, Reliability, tick};
+use bitcode::encode;
+use std::collections::HashMap;
+use std::sync::Mutex;
+type Coalesced = HashMap<(PeerId, Reliability), Vec<Vec<u8>>>;
+type Batched = Vec<(PeerId, Vec<u8>, Reliability)>;
+#[derive(Default)]
+pub(crate) struct Batches {
+    coalesced: Mutex<Coalesced>,
+    batched: Mutex<Batched>,
+}
+impl Batches {
+    pub(crate) fn coalesce(&self, dest: PeerId, packet: Vec<u8>, reliability: Reliability) {
+        self.coalesced
+            .lock()
+            .unwrap()
+            .entry((dest, reliability))
+            .or_default()
+            .push(packet);
+    }
+    pub(crate) fn batch(&self, dest: PeerId, packet: Vec<u8>, reliability: Reliability) {
+        self.batched
+            .lock()
+            .unwrap()
+            .push((dest, packet, reliability));
+    }
+    fn take(&self) -> (Coalesced, Batched) {
+        (
+            std::mem::take(&mut *self.coalesced.lock().unwrap()),
+            std::mem::take(&mut *self.batched.lock().unwrap()),
+        )
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.coalesced.lock().unwrap().is_empty() && self.batched.lock().unwrap().is_empty()
+    }
+    pub(crate) fn bytes(&self, peer: PeerId) -> usize {
+        let coalesced: usize = self
+            .coalesced
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((dest, _), _)| *dest == peer)
+            .flat_map(|(_, packets)| packets.iter().map(Vec::len))
+            .sum();
+        let batched: usize = self
+            .batched
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(dest, _, _)| *dest == peer)
+            .map(|(_, packet, _)| packet.len())
+            .sum();
+        coalesced + batched
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.coalesced
+            .get_mut()
+            .unwrap()
+            .retain(|(p, _), _| *p != peer);
+    }
+}
+impl Client {
+    pub(crate) fn send_batched(&self) {
+        let (coalesced, batched) = self.batches.take();
+        let mtu = self.config.coalesce.unwrap_or(usize::MAX);
+        for ((dest, reliability), packets) in coalesced {
+            for mut batch in tick::split(packets, mtu) {
+                let packet = if batch.len() == 1 {
+                    batch.pop().unwrap()
+                } else {
+                    encode(&MsgType::Batch(batch))
+                };
+                let _ = self.report(Some(dest), self.pace(dest, packet, reliability));
+            }
+        }
+        for (dest, packet, reliability) in batched {
+            let _ = self.report(Some(dest), self.pace(dest, packet, reliability));
+        }
+        for (dest, packet, reliability) in self.queue.drain() {
+            let _ = self.report(Some(dest), self.pace(dest, packet, reliability));
+        }
+        self.send_channel_queues();
+        self.send_paced();
+        self.send_expired();
+    }
+}
@@ -0,0 +1,131 @@
+use crate::seed::{mix, random};
+use crate::{Channel, Client, ClientTrait, Compression, NetError, PeerId, Reliability};
+use std::ops::RangeInclusive;
+use std::time::Instant;
+#[derive(Debug, Clone, PartialEq)]
+pub struct Traffic {
+    pub channel: Channel,
+    pub size: RangeInclusive<usize>,
+    pub rate: f64,
+    pub reliability: Reliability,
+    pub compression: Compression,
+    pub dest: Option<PeerId>,
+    pub broadcast: bool,
+}
+impl Default for Traffic {
+    fn default() -> Self {
+        Self {
+            channel: Channel::DEFAULT,
+            size: 32..=256,
+            rate: 20.0,
+            reliability: Reliability::Unreliable,
+            compression: Compression::Uncompressed,
+            dest: None,
+            broadcast: false,
+        }
+    }
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BotStats {
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub messages_received: u64,
+    pub send_errors: u64,
+}
+pub struct BotPeer {
+    client: Client,
+    traffic: Vec<(Traffic, f64)>,
+    last: Option<Instant>,
+    seed: u64,
+    counter: u64,
+    stats: BotStats,
+}
+impl BotPeer {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            traffic: Vec::new(),
+            last: None,
+            seed: random(),
+            counter: 0,
+            stats: BotStats::default(),
+        }
+    }
+    pub fn with_traffic(mut self, traffic: Traffic) -> Self {
+        self.traffic.push((traffic, 0.0));
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn add_traffic(&mut self, traffic: Traffic) {
+        self.traffic.push((traffic, 0.0))
+    }
+    pub fn clear_traffic(&mut self) {
+        self.traffic.clear()
+    }
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+    pub fn stats(&self) -> BotStats {
+        self.stats
+    }
+    pub fn update(&mut self) {
+        let _ = self.client.update();
+        let mut received = 0;
+        self.client.recv_raw(|_, _| received += 1);
+        self.stats.messages_received += received;
+        self.client.events().for_each(drop);
+        let now = Instant::now();
+        let dt = self.last.map_or(0.0, |t| (now - t).as_secs_f64());
+        self.last = Some(now);
+        if !self.client.is_connected() {
+            return;
+        }
+        for i in 0..self.traffic.len() {
+            self.traffic[i].1 += dt * self.traffic[i].0.rate;
+            while self.traffic[i].1 >= 1.0 {
+                self.traffic[i].1 -= 1.0;
+                let traffic = self.traffic[i].0.clone();
+                match self.emit(&traffic) {
+                    Ok(len) => {
+                        self.stats.messages_sent += 1;
+                        self.stats.bytes_sent += len as u64;
+                    }
+                    Err(_) => self.stats.send_errors += 1,
+                }
+            }
+        }
+    }
+    fn next(&mut self) -> u64 {
+        self.counter += 1;
+        mix(self.seed, self.counter)
+    }
+    fn emit(&mut self, traffic: &Traffic) -> Result<usize, NetError> {
+        let (min, max) = (*traffic.size.start(), *traffic.size.end());
+        let len = min + (self.next() as usize) % (max.saturating_sub(min) + 1);
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            data.extend_from_slice(&self.next().to_le_bytes());
+        }
+        data.truncate(len);
+        let (channel, reliability, compression) =
+            (traffic.channel, traffic.reliability, traffic.compression);
+        let dest = traffic.dest.unwrap_or(self.client.host_id());
+        if traffic.broadcast {
+            self.client
+                .broadcast_raw_on(channel, data, reliability, compression)?
+        } else {
+            self.client
+                .send_raw_on(channel, dest, data, reliability, compression)?
+        }
+        Ok(len)
+    }
+}
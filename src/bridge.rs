@@ -0,0 +1,141 @@
+#[cfg(all(feature = "steam", feature = "tangled"))]
+use crate::ip::{IpClient, host_addr};
+use crate::{
+    Client, ClientTrait, DisconnectReason, MsgType, NetEvent, PeerId, Reliability, unpack,
+};
+use bitcode::encode;
+use std::collections::HashSet;
+#[cfg(all(feature = "steam", feature = "tangled"))]
+use tangled::TangledInitError;
+#[cfg(all(feature = "steam", feature = "tangled"))]
+pub(crate) struct Bridge {
+    pub(crate) ip: IpClient,
+    roster: (Vec<PeerId>, Vec<PeerId>),
+}
+impl Client {
+    #[cfg(all(feature = "steam", feature = "tangled"))]
+    pub fn host_bridge(&mut self, port: u16) -> Result<(), TangledInitError> {
+        let runtime = self.ip_runtime();
+        let _guard = runtime.as_ref().map(tokio::runtime::Handle::enter);
+        self.bridge = Some(Bridge {
+            ip: IpClient::host(host_addr(port, false), false, self.sealer(), None, None)?,
+            roster: Default::default(),
+        });
+        Ok(())
+    }
+    #[cfg(all(feature = "steam", feature = "tangled"))]
+    pub fn stop_bridge(&mut self) {
+        self.bridge = None
+    }
+    #[cfg(all(feature = "steam", feature = "tangled"))]
+    pub fn bridge_addr(&self) -> Option<std::net::SocketAddr> {
+        self.bridge.as_ref()?.ip.local_addr
+    }
+    pub fn is_bridged(&self, peer: PeerId) -> bool {
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        if let Some(bridge) = &self.bridge
+            && bridge.ip.has_peer(peer)
+        {
+            return true;
+        }
+        self.remote_peers.contains(&peer)
+    }
+    pub(crate) fn bridged_peers(&self) -> Vec<PeerId> {
+        #[allow(unused_mut)]
+        let mut peers: Vec<PeerId> = self.remote_peers.iter().copied().collect();
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        if let Some(bridge) = &self.bridge {
+            peers.extend(bridge.ip.peers())
+        }
+        peers
+    }
+    pub(crate) fn relay(&self, dest: Option<PeerId>, packet: Vec<u8>, reliability: Reliability) {
+        let packet = encode(&MsgType::Relay { dest, packet });
+        let _ = self.send_packet(self.host_id(), packet, reliability);
+    }
+    #[allow(unused_variables)]
+    pub(crate) fn receive_relay(&self, src: PeerId, dest: Option<PeerId>, packet: Vec<u8>) {
+        if self.config.relay_via_host && self.is_ip() && self.is_host() {
+            if matches!(
+                unpack(&packet),
+                Some(MsgType::Relay { .. } | MsgType::Bridged { .. })
+            ) {
+                return;
+            }
+            let targets = match dest {
+                Some(dest) if dest == src || dest == self.my_id() => return,
+                Some(dest) if self.has_peer(dest) => vec![dest],
+                Some(_) => return,
+                None => self
+                    .connected_peers()
+                    .into_iter()
+                    .filter(|p| *p != src)
+                    .collect(),
+            };
+            let packet = encode(&MsgType::Bridged { src, packet });
+            for peer in targets {
+                let _ = self.send_packet(peer, packet.clone(), Reliability::Reliable);
+            }
+            return;
+        }
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        if let Some(bridge) = &self.bridge {
+            let bridged = bridge.ip.has_peer(src);
+            let targets = match dest {
+                Some(dest) if bridge.ip.has_peer(dest) != bridged => vec![dest],
+                Some(_) => return,
+                None if bridged => self
+                    .steam_client
+                    .as_ref()
+                    .map_or_else(Vec::new, |steam| steam.peers()),
+                None => bridge.ip.peers(),
+            };
+            let packet = encode(&MsgType::Bridged { src, packet });
+            for peer in targets {
+                let _ = self.send_packet(peer, packet.clone(), Reliability::Reliable);
+            }
+        }
+    }
+    pub(crate) fn receive_roster(&mut self, src: PeerId, peers: Vec<PeerId>) {
+        if src != self.host_id() || self.is_host() {
+            return;
+        }
+        let me = self.my_id();
+        let peers: HashSet<PeerId> = peers.into_iter().filter(|p| *p != me).collect();
+        for peer in peers.difference(&self.remote_peers) {
+            self.net_events.push_back(NetEvent::PeerConnected(*peer))
+        }
+        for peer in self.remote_peers.difference(&peers) {
+            self.net_events.push_back(NetEvent::PeerDisconnected {
+                peer: *peer,
+                reason: DisconnectReason::Quit,
+            })
+        }
+        self.remote_peers = peers;
+    }
+    #[cfg(all(feature = "steam", feature = "tangled"))]
+    pub(crate) fn pump_bridge(&mut self) {
+        let Some(bridge) = &mut self.bridge else {
+            return;
+        };
+        bridge.ip.update();
+        let (Some(bridge), Some(steam)) = (&self.bridge, &self.steam_client) else {
+            return;
+        };
+        let roster = (steam.peers(), bridge.ip.peers());
+        if roster == bridge.roster {
+            return;
+        }
+        let _ = bridge.ip.broadcast_packet(
+            encode(&MsgType::Roster(roster.0.clone())),
+            Reliability::Reliable,
+        );
+        let _ = steam.broadcast_packet(
+            encode(&MsgType::Roster(roster.1.clone())),
+            Reliability::Reliable,
+        );
+        if let Some(bridge) = &mut self.bridge {
+            bridge.roster = roster
+        }
+    }
+}
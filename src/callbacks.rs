@@ -0,0 +1,65 @@
+use crate::{Client, ClientTrait, ClientTypeRef, PeerId};
+type Callback = Box<dyn FnMut(ClientTypeRef, PeerId) + Send + Sync + 'static>;
+#[derive(Copy, Debug, Clone, Hash, PartialEq, Eq)]
+pub struct CallbackId(u64);
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Hook {
+    PeerConnected,
+    PeerDisconnected,
+    ConnectedToHost,
+}
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    next: u64,
+    hooks: Vec<(CallbackId, Hook, Callback)>,
+}
+impl Callbacks {
+    fn add(&mut self, hook: Hook, callback: Callback) -> CallbackId {
+        let id = CallbackId(self.next);
+        self.next += 1;
+        self.hooks.push((id, hook, callback));
+        id
+    }
+}
+impl Client {
+    pub fn on_peer_connected(
+        &mut self,
+        f: impl FnMut(ClientTypeRef, PeerId) + Send + Sync + 'static,
+    ) -> CallbackId {
+        self.callbacks.add(Hook::PeerConnected, Box::new(f))
+    }
+    pub fn on_peer_disconnected(
+        &mut self,
+        f: impl FnMut(ClientTypeRef, PeerId) + Send + Sync + 'static,
+    ) -> CallbackId {
+        self.callbacks.add(Hook::PeerDisconnected, Box::new(f))
+    }
+    pub fn on_connected_to_host(
+        &mut self,
+        f: impl FnMut(ClientTypeRef, PeerId) + Send + Sync + 'static,
+    ) -> CallbackId {
+        self.callbacks.add(Hook::ConnectedToHost, Box::new(f))
+    }
+    pub fn remove_callback(&mut self, id: CallbackId) -> bool {
+        let len = self.callbacks.hooks.len();
+        self.callbacks.hooks.retain(|(i, _, _)| *i != id);
+        self.callbacks.hooks.len() != len
+    }
+    pub(crate) fn run_callbacks(&mut self, peer: PeerId, connected: bool) {
+        let mut hooks = std::mem::take(&mut self.callbacks.hooks);
+        let host = connected && !self.is_host() && peer == self.host_id();
+        if let Some(c) = self.client_ref() {
+            for (_, hook, f) in &mut hooks {
+                let run = match hook {
+                    Hook::PeerConnected => connected,
+                    Hook::PeerDisconnected => !connected,
+                    Hook::ConnectedToHost => host,
+                };
+                if run {
+                    f(c, peer)
+                }
+            }
+        }
+        self.callbacks.hooks = hooks;
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{Channel, Client, MsgType, PeerId, Reliability, unpack};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum CapPolicy {
+    #[default]
+    Drop,
+    Queue,
+}
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelCap {
+    pub bytes_per_sec: u64,
+    pub burst: u64,
+    pub policy: CapPolicy,
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub bytes_sent: u64,
+    pub messages_sent: u64,
+    pub bytes_received: u64,
+    pub messages_received: u64,
+    pub dropped: u64,
+    pub queued: usize,
+}
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+    queue: VecDeque<(PeerId, Vec<u8>, Reliability)>,
+}
+impl Bucket {
+    fn refill(&mut self, cap: ChannelCap) {
+        let dt = self.last.elapsed().as_secs_f64();
+        self.last = Instant::now();
+        self.tokens = (self.tokens + dt * cap.bytes_per_sec as f64).min(cap.burst as f64);
+    }
+}
+#[derive(Default)]
+pub(crate) struct ChannelCaps {
+    caps: HashMap<Channel, ChannelCap>,
+    buckets: Mutex<HashMap<Channel, Bucket>>,
+    stats: Mutex<HashMap<Channel, ChannelStats>>,
+}
+impl ChannelCaps {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buckets
+            .lock()
+            .unwrap()
+            .values()
+            .all(|b| b.queue.is_empty())
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        for bucket in self.buckets.get_mut().unwrap().values_mut() {
+            bucket.queue.retain(|(dest, _, _)| *dest != peer)
+        }
+    }
+}
+fn channel_of(packet: &[u8]) -> Option<Channel> {
+    match unpack(packet)? {
+        MsgType::Data { channel, .. } | MsgType::Unique { channel, .. } => Some(channel),
+        _ => None,
+    }
+}
+impl Client {
+    pub fn set_channel_cap(&mut self, channel: Channel, cap: Option<ChannelCap>) {
+        match cap {
+            Some(cap) => {
+                self.channel_caps.caps.insert(channel, cap);
+            }
+            None => {
+                self.channel_caps.caps.remove(&channel);
+            }
+        }
+    }
+    pub fn channel_cap(&self, channel: Channel) -> Option<ChannelCap> {
+        self.channel_caps.caps.get(&channel).copied()
+    }
+    pub fn channel_stats(&self, channel: Channel) -> ChannelStats {
+        let mut stats = self
+            .channel_caps
+            .stats
+            .lock()
+            .unwrap()
+            .get(&channel)
+            .copied()
+            .unwrap_or_default();
+        stats.queued = self
+            .channel_caps
+            .buckets
+            .lock()
+            .unwrap()
+            .get(&channel)
+            .map_or(0, |b| b.queue.len());
+        stats
+    }
+    pub fn all_channel_stats(&self) -> HashMap<Channel, ChannelStats> {
+        let channels: Vec<Channel> = self
+            .channel_caps
+            .stats
+            .lock()
+            .unwrap()
+            .keys()
+            .copied()
+            .collect();
+        channels
+            .into_iter()
+            .map(|c| (c, self.channel_stats(c)))
+            .collect()
+    }
+    fn tracks_channels(&self) -> bool {
+        self.config.channel_stats || !self.channel_caps.caps.is_empty()
+    }
+    pub(crate) fn throttle_channel(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Option<Vec<u8>> {
+        if !self.tracks_channels() {
+            return Some(packet);
+        }
+        let Some(channel) = channel_of(&packet) else {
+            return Some(packet);
+        };
+        let mut stats = self.channel_caps.stats.lock().unwrap();
+        let stats = stats.entry(channel).or_default();
+        let Some(cap) = self.channel_caps.caps.get(&channel).copied() else {
+            stats.bytes_sent += packet.len() as u64;
+            stats.messages_sent += 1;
+            return Some(packet);
+        };
+        let mut buckets = self.channel_caps.buckets.lock().unwrap();
+        let bucket = buckets.entry(channel).or_insert_with(|| Bucket {
+            tokens: cap.burst as f64,
+            last: Instant::now(),
+            queue: VecDeque::new(),
+        });
+        bucket.refill(cap);
+        if bucket.queue.is_empty() && bucket.tokens > 0.0 {
+            bucket.tokens -= packet.len() as f64;
+            stats.bytes_sent += packet.len() as u64;
+            stats.messages_sent += 1;
+            return Some(packet);
+        }
+        match cap.policy {
+            CapPolicy::Drop => {
+                stats.dropped += 1;
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            CapPolicy::Queue => bucket.queue.push_back((dest, packet, reliability)),
+        }
+        None
+    }
+    pub(crate) fn send_channel_queues(&self) {
+        let mut ready = Vec::new();
+        {
+            let mut buckets = self.channel_caps.buckets.lock().unwrap();
+            let mut stats = self.channel_caps.stats.lock().unwrap();
+            for (channel, bucket) in buckets.iter_mut() {
+                let Some(cap) = self.channel_caps.caps.get(channel).copied() else {
+                    ready.extend(bucket.queue.drain(..));
+                    continue;
+                };
+                bucket.refill(cap);
+                while bucket.tokens > 0.0
+                    && let Some((dest, packet, reliability)) = bucket.queue.pop_front()
+                {
+                    bucket.tokens -= packet.len() as f64;
+                    let stats = stats.entry(*channel).or_default();
+                    stats.bytes_sent += packet.len() as u64;
+                    stats.messages_sent += 1;
+                    ready.push((dest, packet, reliability));
+                }
+            }
+        }
+        for (dest, packet, reliability) in ready {
+            let _ = self.report(Some(dest), self.pace(dest, packet, reliability));
+        }
+    }
+    pub(crate) fn channel_received(&self, channel: Channel, len: usize) {
+        if !self.tracks_channels() {
+            return;
+        }
+        let mut stats = self.channel_caps.stats.lock().unwrap();
+        let stats = stats.entry(channel).or_default();
+        stats.bytes_received += len as u64;
+        stats.messages_received += 1;
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{Channel, Client, ClientTrait, MsgType, NetError, PeerId, Reliability, Role};
+#[cfg(feature = "bevy")]
+use bevy_ecs::message::{Message, MessageWriter};
+#[cfg(feature = "bevy")]
+use bevy_ecs::system::ResMut;
+use bitcode::encode;
+use std::collections::{HashSet, VecDeque};
+type Filter = Box<dyn FnMut(PeerId, Channel, &str) -> Option<String> + Send + Sync + 'static>;
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(Message))]
+pub struct ChatLine {
+    pub peer: PeerId,
+    pub channel: Channel,
+    pub text: String,
+    pub sent: u64,
+}
+pub(crate) struct Chat {
+    lines: VecDeque<ChatLine>,
+    muted: HashSet<PeerId>,
+    filter: Option<Filter>,
+    max_len: usize,
+}
+impl Default for Chat {
+    fn default() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            muted: HashSet::new(),
+            filter: None,
+            max_len: 256,
+        }
+    }
+}
+impl Client {
+    pub fn send_chat(&mut self, channel: Channel, text: &str) -> Result<(), NetError> {
+        let sent = self.clock.now();
+        if self.is_host() {
+            self.publish_chat(self.my_id(), channel, text.to_owned(), sent);
+            return Ok(());
+        }
+        let packet = encode(&MsgType::Chat {
+            channel,
+            text: text.to_owned(),
+            sent,
+        });
+        self.send_packet(self.host_id(), packet, Reliability::Reliable)
+    }
+    pub fn set_chat_filter(
+        &mut self,
+        filter: impl FnMut(PeerId, Channel, &str) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.chat.filter = Some(Box::new(filter))
+    }
+    pub fn clear_chat_filter(&mut self) {
+        self.chat.filter = None
+    }
+    pub fn set_max_chat_len(&mut self, max_len: usize) {
+        self.chat.max_len = max_len
+    }
+    pub fn mute_chat(&mut self, peer: PeerId) {
+        self.chat.muted.insert(peer);
+        self.chat.lines.retain(|l| l.peer != peer);
+    }
+    pub fn unmute_chat(&mut self, peer: PeerId) {
+        self.chat.muted.remove(&peer);
+    }
+    pub fn is_chat_muted(&self, peer: PeerId) -> bool {
+        self.chat.muted.contains(&peer)
+    }
+    pub fn chat_lines(&mut self) -> impl Iterator<Item = ChatLine> + '_ {
+        self.chat.lines.drain(..)
+    }
+    pub(crate) fn receive_chat(&mut self, src: PeerId, channel: Channel, text: String, sent: u64) {
+        if self.is_host() && !self.has_role(src, Role::MUTED) {
+            self.publish_chat(src, channel, text, sent)
+        }
+    }
+    pub(crate) fn receive_chat_line(&mut self, src: PeerId, line: ChatLine) {
+        if src == self.host_id() && !self.is_host() {
+            self.push_chat(line)
+        }
+    }
+    fn publish_chat(&mut self, peer: PeerId, channel: Channel, text: String, sent: u64) {
+        let mut text: String = text.chars().take(self.chat.max_len).collect();
+        if let Some(filter) = &mut self.chat.filter {
+            let Some(filtered) = filter(peer, channel, &text) else {
+                return;
+            };
+            text = filtered;
+        }
+        let line = ChatLine {
+            peer,
+            channel,
+            text,
+            sent,
+        };
+        let packet = encode(&MsgType::ChatLine {
+            peer,
+            channel,
+            text: line.text.clone(),
+            sent,
+        });
+        let _ = self.broadcast_packet(packet, Reliability::Reliable);
+        self.push_chat(line)
+    }
+    fn push_chat(&mut self, line: ChatLine) {
+        if !self.chat.muted.contains(&line.peer) {
+            self.chat.lines.push_back(line)
+        }
+    }
+}
+#[cfg(feature = "bevy")]
+pub fn receive_chat(mut client: ResMut<Client>, mut writer: MessageWriter<ChatLine>) {
+    writer.write_batch(client.chat_lines().collect::<Vec<_>>());
+}
@@ -0,0 +1,53 @@
+use crate::{Client, PeerId};
+#[cfg(feature = "bevy")]
+use bevy_ecs::resource::Resource;
+use std::collections::HashMap;
+#[cfg(feature = "log")]
+use tracing::warn;
+#[derive(Default)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
+pub struct Clients(HashMap<String, Client>);
+impl Clients {
+    pub fn insert(&mut self, label: impl Into<String>, client: Client) -> Option<Client> {
+        self.0.insert(label.into(), client)
+    }
+    pub fn remove(&mut self, label: &str) -> Option<Client> {
+        self.0.remove(label)
+    }
+    pub fn get(&self, label: &str) -> Option<&Client> {
+        self.0.get(label)
+    }
+    pub fn get_mut(&mut self, label: &str) -> Option<&mut Client> {
+        self.0.get_mut(label)
+    }
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Client)> {
+        self.0.iter().map(|(l, c)| (l.as_str(), c))
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Client)> {
+        self.0.iter_mut().map(|(l, c)| (l.as_str(), c))
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn peers(&self, label: &str) -> Vec<PeerId> {
+        self.get(label).map(Client::peers).unwrap_or_default()
+    }
+    pub fn update(&mut self) {
+        for (_label, client) in self.0.iter_mut() {
+            if let Err(_s) = client.update() {
+                #[cfg(feature = "log")]
+                warn!("{_label}: {_s}")
+            }
+        }
+    }
+}
+#[cfg(feature = "bevy")]
+pub fn update_clients(mut clients: bevy_ecs::system::ResMut<Clients>) {
+    clients.update()
+}
@@ -0,0 +1,183 @@
+use crate::{Client, ClientTrait, MsgType, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+const WINDOW: usize = 32;
+const MIN_PINGS: u32 = 8;
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub struct ClockSkew {
+    pub offset_micros: i64,
+    pub drift_ppm: f64,
+    pub rtt: Duration,
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct NetworkTime {
+    pub now: Duration,
+    pub offset_micros: i64,
+    pub rtt: Option<Duration>,
+}
+#[derive(Default)]
+struct Estimate {
+    samples: VecDeque<(f64, f64)>,
+    anchor: (f64, f64),
+    drift: f64,
+    rtt: Duration,
+    stale: bool,
+}
+impl Estimate {
+    fn predict(&self, t: f64) -> f64 {
+        self.anchor.1 + self.drift * (t - self.anchor.0)
+    }
+    fn fit(&mut self, t: f64) -> f64 {
+        let n = self.samples.len() as f64;
+        let (st, so) = self
+            .samples
+            .iter()
+            .fold((0.0, 0.0), |(a, b), (t, o)| (a + t, b + o));
+        let (mt, mo) = (st / n, so / n);
+        if self.samples.len() >= 4 {
+            let (mut cov, mut var) = (0.0, 0.0);
+            for (t, o) in &self.samples {
+                cov += (t - mt) * (o - mo);
+                var += (t - mt) * (t - mt);
+            }
+            if var > 0.0 {
+                self.drift = cov / var
+            }
+        }
+        mo + self.drift * (t - mt)
+    }
+}
+pub(crate) struct Clock {
+    epoch: Instant,
+    peers: HashMap<PeerId, Estimate>,
+    pings: HashMap<PeerId, (f32, bool, u32)>,
+    pub(crate) last_sync: Instant,
+}
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+            peers: HashMap::new(),
+            pings: HashMap::new(),
+            last_sync: Instant::now(),
+        }
+    }
+}
+impl Clock {
+    pub(crate) fn now(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+    pub(crate) fn sample(
+        &mut self,
+        peer: PeerId,
+        sent: u64,
+        remote: u64,
+        threshold: Duration,
+    ) -> bool {
+        if let Some((loss, outstanding, samples)) = self.pings.get_mut(&peer)
+            && *outstanding
+        {
+            *loss *= 0.9;
+            *outstanding = false;
+            *samples += 1;
+        }
+        let now = self.now();
+        let rtt = now.saturating_sub(sent);
+        let t = now as f64;
+        let offset = remote as f64 + rtt as f64 / 2.0 - t;
+        let first = !self.peers.contains_key(&peer);
+        let e = self.peers.entry(peer).or_default();
+        e.rtt = Duration::from_micros(rtt);
+        e.samples.push_back((t, offset));
+        if e.samples.len() > WINDOW {
+            e.samples.pop_front();
+        }
+        let fitted = e.fit(t);
+        if first || e.stale || (fitted - e.predict(t)).abs() > threshold.as_micros() as f64 {
+            e.anchor = (t, fitted);
+            e.stale = false;
+            return true;
+        }
+        false
+    }
+    pub(crate) fn drifted(&mut self, threshold: Duration) -> Vec<PeerId> {
+        let t = self.now() as f64;
+        let threshold = threshold.as_micros() as f64;
+        self.peers
+            .iter_mut()
+            .filter(|(_, e)| !e.stale && (e.drift * (t - e.anchor.0)).abs() > threshold)
+            .map(|(peer, e)| {
+                e.stale = true;
+                *peer
+            })
+            .collect()
+    }
+    pub(crate) fn skew(&self, peer: PeerId) -> Option<ClockSkew> {
+        let e = self.peers.get(&peer)?;
+        Some(ClockSkew {
+            offset_micros: e.predict(self.now() as f64).round() as i64,
+            drift_ppm: e.drift * 1e6,
+            rtt: e.rtt,
+        })
+    }
+    pub(crate) fn pinged(&mut self, peer: PeerId) {
+        let (loss, outstanding, samples) = self.pings.entry(peer).or_default();
+        if *outstanding {
+            *loss = *loss * 0.9 + 0.1;
+            *samples += 1;
+        }
+        *outstanding = true;
+    }
+    pub(crate) fn loss(&self, peer: PeerId) -> Option<f32> {
+        self.peers.get(&peer)?;
+        self.pings
+            .get(&peer)
+            .filter(|(_, _, samples)| *samples >= MIN_PINGS)
+            .map(|(loss, _, _)| *loss)
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.peers.remove(&peer);
+        self.pings.remove(&peer);
+    }
+}
+impl Client {
+    pub(crate) fn sync_clocks(&mut self) {
+        let Some(interval) = self.config.clock_sync.filter(|_| !self.idle.hibernating) else {
+            return;
+        };
+        let peers = if self.clock.last_sync.elapsed() >= interval {
+            self.clock.last_sync = Instant::now();
+            self.connected_peers()
+        } else {
+            self.clock.drifted(self.config.drift_threshold)
+        };
+        let packet = encode(&MsgType::ClockPing(self.clock.now()));
+        for peer in peers {
+            self.clock.pinged(peer);
+            let _ = self.dispatch(peer, packet.clone(), Reliability::Unreliable);
+        }
+    }
+    pub fn clock_skew(&self, peer: PeerId) -> Option<ClockSkew> {
+        self.clock.skew(peer)
+    }
+    pub fn estimated_offset(&self, peer: PeerId) -> Option<i64> {
+        self.clock.skew(peer).map(|s| s.offset_micros)
+    }
+    pub fn rtt(&self, peer: PeerId) -> Option<Duration> {
+        self.clock.skew(peer).map(|s| s.rtt)
+    }
+    pub fn network_time(&self) -> NetworkTime {
+        let local = self.clock.now() as i64;
+        let skew = (!self.is_host())
+            .then(|| self.clock.skew(self.host_id()))
+            .flatten();
+        let offset_micros = skew.map_or(0, |s| s.offset_micros);
+        NetworkTime {
+            now: Duration::from_micros((local + offset_micros).max(0) as u64),
+            offset_micros,
+            rtt: skew.map(|s| s.rtt),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    Backoff, Bandwidth, ConnectionBudget, Hibernate, QualityThresholds, RateLimit, SendQueue,
+};
+#[cfg(feature = "steam")]
+use crate::{SteamConfig, SteamPath};
+use std::net::SocketAddr;
+use std::time::Duration;
+#[cfg(feature = "tangled")]
+use tokio::runtime::Handle;
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub enum UnknownPeerPolicy {
+    Error,
+    Drop,
+    Buffer(Duration),
+}
+#[derive(Debug, Clone)]
+pub struct NetConfig {
+    pub unknown_peer: UnknownPeerPolicy,
+    pub reconnect: Option<Backoff>,
+    pub keepalive: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub clock_sync: Option<Duration>,
+    pub drift_threshold: Duration,
+    pub max_file_size: u64,
+    pub steam_fallback: Option<SocketAddr>,
+    pub hibernate: Option<Hibernate>,
+    pub max_message_size: usize,
+    pub mtu: usize,
+    pub max_decompressed_size: usize,
+    pub max_violations: Option<u32>,
+    pub prefer_compression: bool,
+    pub batch_sends: bool,
+    pub rate_limit: Option<RateLimit>,
+    pub connection_budget: Option<ConnectionBudget>,
+    pub offload_threshold: Option<usize>,
+    pub frame_budget: Option<Duration>,
+    pub tick_rate: Option<u32>,
+    pub legacy_wire: bool,
+    pub coalesce: Option<usize>,
+    pub send_queue: Option<SendQueue>,
+    pub identity: Option<u128>,
+    pub dedicated: bool,
+    pub version: Option<u32>,
+    pub join_timeout: Option<Duration>,
+    pub report_send_errors: bool,
+    pub game_id: Option<u64>,
+    pub discard_expired: bool,
+    pub suppress_duplicates: bool,
+    pub relay_via_host: bool,
+    pub mesh: bool,
+    pub quality: Option<QualityThresholds>,
+    pub shutdown_timeout: Duration,
+    pub channel_stats: bool,
+    pub best_effort_nack_after: u32,
+    pub unordered_max_attempts: u32,
+    pub bandwidth: Option<Bandwidth>,
+    #[cfg(feature = "tangled")]
+    pub runtime: Option<Handle>,
+    #[cfg(feature = "upnp")]
+    pub port_forward: bool,
+    #[cfg(feature = "steam")]
+    pub steam_path: SteamPath,
+    #[cfg(feature = "steam")]
+    pub steam: SteamConfig,
+    #[cfg(feature = "steam")]
+    pub require_steam_auth: bool,
+    #[cfg(feature = "encrypt")]
+    pub psk: Option<[u8; 32]>,
+    #[cfg(feature = "hmac")]
+    pub hmac_key: Option<Vec<u8>>,
+}
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            unknown_peer: UnknownPeerPolicy::Drop,
+            reconnect: None,
+            keepalive: None,
+            timeout: None,
+            clock_sync: Some(Duration::from_secs(5)),
+            drift_threshold: Duration::from_millis(5),
+            max_file_size: 64 * 1024 * 1024,
+            steam_fallback: None,
+            hibernate: None,
+            max_message_size: 16 * 1024 * 1024,
+            mtu: 1200,
+            max_decompressed_size: 64 * 1024 * 1024,
+            max_violations: Some(8),
+            prefer_compression: false,
+            batch_sends: false,
+            rate_limit: None,
+            connection_budget: None,
+            offload_threshold: None,
+            frame_budget: None,
+            tick_rate: None,
+            legacy_wire: false,
+            coalesce: None,
+            send_queue: None,
+            identity: None,
+            dedicated: false,
+            version: None,
+            join_timeout: None,
+            report_send_errors: false,
+            game_id: None,
+            discard_expired: false,
+            suppress_duplicates: false,
+            relay_via_host: false,
+            mesh: false,
+            quality: None,
+            shutdown_timeout: Duration::from_millis(250),
+            channel_stats: false,
+            best_effort_nack_after: 3,
+            unordered_max_attempts: 25,
+            bandwidth: None,
+            #[cfg(feature = "tangled")]
+            runtime: None,
+            #[cfg(feature = "upnp")]
+            port_forward: false,
+            #[cfg(feature = "steam")]
+            steam_path: SteamPath::Auto,
+            #[cfg(feature = "steam")]
+            steam: SteamConfig::default(),
+            #[cfg(feature = "steam")]
+            require_steam_auth: false,
+            #[cfg(feature = "encrypt")]
+            psk: None,
+            #[cfg(feature = "hmac")]
+            hmac_key: None,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{Client, NetError, PeerId, Reliability};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+const ADAPT_INTERVAL: Duration = Duration::from_millis(250);
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct Bandwidth {
+    pub bytes_per_sec: u64,
+    pub burst: u64,
+    pub adaptive: bool,
+}
+impl Default for Bandwidth {
+    fn default() -> Self {
+        Self {
+            bytes_per_sec: 256 * 1024,
+            burst: 64 * 1024,
+            adaptive: true,
+        }
+    }
+}
+struct Pace {
+    tokens: f64,
+    last: Instant,
+    scale: f64,
+    base_rtt: Option<Duration>,
+    adapted: Instant,
+    queue: VecDeque<(Vec<u8>, Reliability)>,
+}
+impl Pace {
+    fn new(limit: Bandwidth) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            last: Instant::now(),
+            scale: 1.0,
+            base_rtt: None,
+            adapted: Instant::now(),
+            queue: VecDeque::new(),
+        }
+    }
+    fn refill(&mut self, limit: Bandwidth) {
+        let dt = self.last.elapsed().as_secs_f64();
+        self.last = Instant::now();
+        self.tokens =
+            (self.tokens + dt * limit.bytes_per_sec as f64 * self.scale).min(limit.burst as f64);
+    }
+    fn take(&mut self, size: usize) -> bool {
+        if self.tokens <= 0.0 {
+            return false;
+        }
+        self.tokens -= size as f64;
+        true
+    }
+}
+#[derive(Default)]
+pub(crate) struct Pacer {
+    peers: Mutex<HashMap<PeerId, Pace>>,
+    pub(crate) overrides: HashMap<PeerId, Option<Bandwidth>>,
+}
+impl Pacer {
+    pub(crate) fn limit(&self, peer: PeerId, default: Option<Bandwidth>) -> Option<Bandwidth> {
+        self.overrides.get(&peer).copied().unwrap_or(default)
+    }
+    pub(crate) fn admit(
+        &self,
+        peer: PeerId,
+        limit: Bandwidth,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Option<Vec<u8>> {
+        let mut peers = self.peers.lock().unwrap();
+        let pace = peers.entry(peer).or_insert_with(|| Pace::new(limit));
+        pace.refill(limit);
+        if pace.queue.is_empty() && pace.take(packet.len()) {
+            return Some(packet);
+        }
+        pace.queue.push_back((packet, reliability));
+        None
+    }
+    pub(crate) fn ready(
+        &self,
+        limit: impl Fn(PeerId) -> Option<Bandwidth>,
+    ) -> Vec<(PeerId, Vec<u8>, Reliability)> {
+        let mut ready = Vec::new();
+        for (peer, pace) in self.peers.lock().unwrap().iter_mut() {
+            let Some(limit) = limit(*peer) else {
+                ready.extend(pace.queue.drain(..).map(|(p, r)| (*peer, p, r)));
+                continue;
+            };
+            pace.refill(limit);
+            while let Some(len) = pace.queue.front().map(|(p, _)| p.len()) {
+                if !pace.take(len) {
+                    break;
+                }
+                let (packet, reliability) = pace.queue.pop_front().unwrap();
+                ready.push((*peer, packet, reliability))
+            }
+        }
+        ready
+    }
+    pub(crate) fn adapt(&self, peer: PeerId, rtt: Option<Duration>, quality: Option<f32>) {
+        let mut peers = self.peers.lock().unwrap();
+        let Some(pace) = peers.get_mut(&peer) else {
+            return;
+        };
+        if pace.adapted.elapsed() < ADAPT_INTERVAL {
+            return;
+        }
+        pace.adapted = Instant::now();
+        if let Some(rtt) = rtt {
+            pace.base_rtt = Some(pace.base_rtt.map_or(rtt, |b| b.min(rtt)));
+        }
+        let rising = rtt
+            .zip(pace.base_rtt)
+            .is_some_and(|(rtt, base)| rtt > base * 3 / 2 + Duration::from_millis(10));
+        let lossy = quality.is_some_and(|q| q < 0.9);
+        pace.scale = if rising || lossy {
+            (pace.scale * 0.85).max(0.1)
+        } else {
+            (pace.scale * 1.05).min(1.0)
+        };
+    }
+    pub(crate) fn rate(&self, peer: PeerId, limit: Bandwidth) -> f64 {
+        let scale = self
+            .peers
+            .lock()
+            .unwrap()
+            .get(&peer)
+            .map_or(1.0, |p| p.scale);
+        limit.bytes_per_sec as f64 * scale
+    }
+    pub(crate) fn queued(&self, peer: PeerId) -> usize {
+        self.peers
+            .lock()
+            .unwrap()
+            .get(&peer)
+            .map_or(0, |p| p.queue.len())
+    }
+    pub(crate) fn queued_bytes(&self, peer: PeerId) -> usize {
+        self.peers
+            .lock()
+            .unwrap()
+            .get(&peer)
+            .map_or(0, |p| p.queue.iter().map(|(packet, _)| packet.len()).sum())
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.peers
+            .lock()
+            .unwrap()
+            .values()
+            .all(|p| p.queue.is_empty())
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.peers.get_mut().unwrap().remove(&peer);
+        self.overrides.remove(&peer);
+    }
+}
+impl Client {
+    pub(crate) fn pace(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        let Some(limit) = self.pacer.limit(dest, self.config.bandwidth) else {
+            return self.dispatch(dest, packet, reliability);
+        };
+        match self.pacer.admit(dest, limit, packet, reliability) {
+            Some(packet) => self.dispatch(dest, packet, reliability),
+            None => Ok(()),
+        }
+    }
+    pub(crate) fn send_paced(&self) {
+        for (dest, packet, reliability) in self
+            .pacer
+            .ready(|peer| self.pacer.limit(peer, self.config.bandwidth))
+        {
+            let _ = self.report(Some(dest), self.dispatch(dest, packet, reliability));
+        }
+    }
+    pub(crate) fn adapt_bandwidth(&self) {
+        for peer in self.connected_peers() {
+            if !self
+                .pacer
+                .limit(peer, self.config.bandwidth)
+                .is_some_and(|l| l.adaptive)
+            {
+                continue;
+            }
+            #[cfg(feature = "steam")]
+            let quality = match &self.steam_client {
+                Some(steam) if !self.is_ip() => {
+                    steam.status(peer).map(|s| s.connection_quality_local())
+                }
+                _ => None,
+            };
+            #[cfg(not(feature = "steam"))]
+            let quality = None;
+            self.pacer.adapt(peer, self.rtt(peer), quality)
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{Bandwidth, Client, ConnectionQuality, NetError, PeerId, RateLimit};
+use std::time::Duration;
+#[cfg(feature = "steam")]
+use steamworks::networking_types::NetConnectionRealTimeInfo;
+pub struct PeerConnection<'a> {
+    pub(crate) client: &'a mut Client,
+    pub(crate) peer: PeerId,
+}
+impl PeerConnection<'_> {
+    pub fn peer(&self) -> PeerId {
+        self.peer
+    }
+    pub fn rtt(&self) -> Option<Duration> {
+        self.client.rtt(self.peer)
+    }
+    pub fn idle(&self) -> Option<Duration> {
+        self.client
+            .liveness
+            .last_seen
+            .get(&self.peer)
+            .map(|t| t.elapsed())
+    }
+    pub fn metadata(&self, key: &str) -> Option<String> {
+        self.client.peer_metadata(self.peer, key)
+    }
+    #[cfg(feature = "steam")]
+    pub fn steam_status(&self) -> Option<NetConnectionRealTimeInfo> {
+        self.client.steam_client.as_ref()?.status(self.peer)
+    }
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.client
+            .limiter
+            .limit(self.peer, self.client.config.rate_limit)
+    }
+    pub fn set_rate_limit(&mut self, limit: Option<RateLimit>) {
+        self.client.limiter.overrides.insert(self.peer, limit);
+    }
+    pub fn bandwidth(&self) -> Option<Bandwidth> {
+        self.client
+            .pacer
+            .limit(self.peer, self.client.config.bandwidth)
+    }
+    pub fn set_bandwidth(&mut self, limit: Option<Bandwidth>) {
+        self.client.pacer.overrides.insert(self.peer, limit);
+    }
+    pub fn send_rate(&self) -> Option<f64> {
+        self.bandwidth()
+            .map(|limit| self.client.pacer.rate(self.peer, limit))
+    }
+    pub fn paced(&self) -> usize {
+        self.client.pacer.queued(self.peer)
+    }
+    pub fn quality(&self) -> ConnectionQuality {
+        self.client.connection_quality(self.peer)
+    }
+    pub fn close(self, reason: &str) -> Result<(), NetError> {
+        self.client.kick(self.peer, reason)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::NetError;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+const NONCE: usize = 24;
+pub(crate) fn seal(key: &[u8; 32], packet: &[u8]) -> Result<Vec<u8>, NetError> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = cipher
+        .encrypt(&nonce, packet)
+        .map_err(|_| NetError::Crypto)?;
+    let mut out = Vec::with_capacity(NONCE + sealed.len());
+    out.extend_from_slice(&nonce);
+    out.extend(sealed);
+    Ok(out)
+}
+pub(crate) fn open(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, NetError> {
+    if data.len() < NONCE {
+        return Err(NetError::Crypto);
+    }
+    let (nonce, sealed) = data.split_at(NONCE);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), sealed)
+        .map_err(|_| NetError::Crypto)
+}
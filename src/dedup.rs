@@ -0,0 +1,96 @@
+use crate::{Channel, Client, MsgType, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+const WINDOW: u32 = 64;
+#[derive(Default)]
+struct Window {
+    last: u32,
+    seen: u64,
+}
+impl Window {
+    fn accept(&mut self, seq: u32) -> bool {
+        let delta = seq.wrapping_sub(self.last) as i32;
+        if delta > 0 {
+            self.seen = if delta as u32 >= WINDOW {
+                0
+            } else {
+                self.seen << delta
+            } | 1;
+            self.last = seq;
+            return true;
+        }
+        let back = delta.unsigned_abs();
+        if back >= WINDOW {
+            return false;
+        }
+        let bit = 1 << back;
+        let fresh = self.seen & bit == 0;
+        self.seen |= bit;
+        fresh
+    }
+}
+#[derive(Default)]
+pub(crate) struct Dedup {
+    sequences: Mutex<HashMap<Channel, u32>>,
+    windows: HashMap<(PeerId, Channel), Window>,
+}
+impl Dedup {
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.windows.retain(|(p, _), _| *p != peer);
+    }
+}
+impl Client {
+    pub(crate) fn stamp_unique(
+        &self,
+        channel: Channel,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Vec<u8> {
+        if !self.config.suppress_duplicates
+            || reliability != Reliability::Unreliable
+            || !self.is_ip()
+        {
+            return packet;
+        }
+        let seq = {
+            let mut sequences = self.dedup.sequences.lock().unwrap();
+            let seq = sequences.entry(channel).or_default();
+            *seq = seq.wrapping_add(1);
+            *seq
+        };
+        encode(&MsgType::Unique {
+            channel,
+            seq,
+            packet,
+        })
+    }
+    pub(crate) fn unique(&mut self, src: PeerId, channel: Channel, seq: u32) -> bool {
+        let fresh = self
+            .dedup
+            .windows
+            .entry((src, channel))
+            .or_default()
+            .accept(seq);
+        if !fresh {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        fresh
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_dedup_window() {
+    let mut window = Window {
+        last: u32::MAX - 1,
+        seen: 1,
+    };
+    assert!(window.accept(u32::MAX));
+    assert!(window.accept(1));
+    assert!(window.accept(0));
+    assert!(!window.accept(u32::MAX));
+    assert!(!window.accept(1));
+    assert!(window.accept(WINDOW + 1));
+    assert!(!window.accept(0));
+}
@@ -0,0 +1,67 @@
+use crate::{Client, NetStats};
+use bevy_app::{App, Plugin, Update};
+use bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy_ecs::system::{Local, Res};
+use std::time::Instant;
+pub struct NetworkDiagnosticsPlugin;
+impl NetworkDiagnosticsPlugin {
+    pub const RTT: DiagnosticPath = DiagnosticPath::const_new("net/rtt");
+    pub const BYTES_IN: DiagnosticPath = DiagnosticPath::const_new("net/bytes_in");
+    pub const BYTES_OUT: DiagnosticPath = DiagnosticPath::const_new("net/bytes_out");
+    pub const MESSAGES_IN: DiagnosticPath = DiagnosticPath::const_new("net/messages_in");
+    pub const MESSAGES_OUT: DiagnosticPath = DiagnosticPath::const_new("net/messages_out");
+    pub const PEERS: DiagnosticPath = DiagnosticPath::const_new("net/peers");
+}
+impl Plugin for NetworkDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::RTT).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::BYTES_IN).with_suffix("B/s"))
+            .register_diagnostic(Diagnostic::new(Self::BYTES_OUT).with_suffix("B/s"))
+            .register_diagnostic(Diagnostic::new(Self::MESSAGES_IN).with_suffix("/s"))
+            .register_diagnostic(Diagnostic::new(Self::MESSAGES_OUT).with_suffix("/s"))
+            .register_diagnostic(Diagnostic::new(Self::PEERS))
+            .add_systems(Update, diagnostics);
+    }
+}
+fn diagnostics(
+    client: Option<Res<Client>>,
+    mut diagnostics: Diagnostics,
+    mut last: Local<Option<(Instant, NetStats)>>,
+) {
+    let Some(client) = client else {
+        return;
+    };
+    let peers = client.peers();
+    let rtts: Vec<f64> = peers
+        .iter()
+        .filter_map(|p| client.rtt(*p))
+        .map(|rtt| rtt.as_secs_f64() * 1000.0)
+        .collect();
+    if !rtts.is_empty() {
+        diagnostics.add_measurement(&NetworkDiagnosticsPlugin::RTT, || {
+            rtts.iter().sum::<f64>() / rtts.len() as f64
+        });
+    }
+    diagnostics.add_measurement(&NetworkDiagnosticsPlugin::PEERS, || peers.len() as f64);
+    let now = Instant::now();
+    let stats = client.stats();
+    if let Some((then, prev)) = last.as_ref() {
+        let secs = (now - *then).as_secs_f64();
+        if secs > 0.0 {
+            let rate = |a: u64, b: u64| a.saturating_sub(b) as f64 / secs;
+            diagnostics.add_measurement(&NetworkDiagnosticsPlugin::BYTES_IN, || {
+                rate(stats.bytes_received, prev.bytes_received)
+            });
+            diagnostics.add_measurement(&NetworkDiagnosticsPlugin::BYTES_OUT, || {
+                rate(stats.bytes_sent, prev.bytes_sent)
+            });
+            diagnostics.add_measurement(&NetworkDiagnosticsPlugin::MESSAGES_IN, || {
+                rate(stats.messages_received, prev.messages_received)
+            });
+            diagnostics.add_measurement(&NetworkDiagnosticsPlugin::MESSAGES_OUT, || {
+                rate(stats.messages_sent, prev.messages_sent)
+            });
+        }
+    }
+    *last = Some((now, stats));
+}
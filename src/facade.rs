@@ -0,0 +1,150 @@
+use crate::{
+    Channel, Client, ClientTrait, Compression, Message, NetConfig, NetError, NetEvent, PeerId,
+    Reliability,
+};
+use bitcode::{DecodeOwned, Encode, decode};
+use std::net::SocketAddr;
+pub struct NetClient(Client);
+impl NetClient {
+    #[allow(unused_variables)]
+    pub fn new(app_id: u32, config: NetConfig) -> Option<Self> {
+        let mut client = Client::new(
+            #[cfg(feature = "steam")]
+            app_id,
+            #[cfg(feature = "steam")]
+            None,
+            #[cfg(feature = "steam")]
+            None,
+        )?;
+        *client.config_mut() = config;
+        Some(Self(client))
+    }
+    pub fn host_steam(&mut self) -> Result<(), NetError> {
+        #[cfg(feature = "steam")]
+        {
+            self.0
+                .host_steam()
+                .map_err(|e| NetError::Init(format!("{e:?}")))
+        }
+        #[cfg(not(feature = "steam"))]
+        {
+            Err(NetError::Unsupported)
+        }
+    }
+    #[allow(unused_variables)]
+    pub fn join_steam(&mut self, lobby: u64) -> Result<(), NetError> {
+        #[cfg(feature = "steam")]
+        {
+            self.0.join_steam(crate::LobbyId::from_raw(lobby));
+            Ok(())
+        }
+        #[cfg(not(feature = "steam"))]
+        {
+            Err(NetError::Unsupported)
+        }
+    }
+    #[allow(unused_variables)]
+    pub fn host_ip(&mut self, port: u16) -> Result<(), NetError> {
+        #[cfg(feature = "tangled")]
+        {
+            self.0
+                .host_ip(port, None, None)
+                .map_err(|e| NetError::Init(format!("{e:?}")))
+        }
+        #[cfg(not(feature = "tangled"))]
+        {
+            Err(NetError::Unsupported)
+        }
+    }
+    #[allow(unused_variables)]
+    pub fn join_ip(&mut self, addr: SocketAddr) -> Result<(), NetError> {
+        #[cfg(feature = "tangled")]
+        {
+            self.0
+                .join_ip(addr, None, None)
+                .map_err(|e| NetError::Init(format!("{e:?}")))
+        }
+        #[cfg(not(feature = "tangled"))]
+        {
+            Err(NetError::Unsupported)
+        }
+    }
+    pub fn update(&mut self) -> Result<(), NetError> {
+        #[cfg(feature = "steam")]
+        {
+            self.0.update().map_err(NetError::from)
+        }
+        #[cfg(not(feature = "steam"))]
+        {
+            let _ = self.0.update();
+            Ok(())
+        }
+    }
+    pub fn recv<T: DecodeOwned, F: FnMut(Message<T>)>(&mut self, mut f: F) {
+        self.0.recv_raw(|_, m| {
+            if let Ok(data) = decode(m.data) {
+                f(Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data,
+                })
+            }
+        })
+    }
+    pub fn events(&mut self) -> impl Iterator<Item = NetEvent> + '_ {
+        self.0.events()
+    }
+    pub fn send<T: Encode>(
+        &self,
+        channel: Channel,
+        dest: PeerId,
+        data: &T,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        self.0
+            .send_on(channel, dest, data, reliability, Compression::Uncompressed)
+    }
+    pub fn broadcast<T: Encode>(
+        &self,
+        channel: Channel,
+        data: &T,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        self.0
+            .broadcast_on(channel, data, reliability, Compression::Uncompressed)
+    }
+    pub fn kick(&mut self, peer: PeerId, reason: &str) -> Result<(), NetError> {
+        self.0.kick(peer, reason)
+    }
+    pub fn leave(&mut self) {
+        self.0.leave()
+    }
+    pub fn peers(&self) -> Vec<PeerId> {
+        self.0.peers()
+    }
+    pub fn my_id(&self) -> PeerId {
+        self.0.my_id()
+    }
+    pub fn host_id(&self) -> PeerId {
+        self.0.host_id()
+    }
+    pub fn is_host(&self) -> bool {
+        self.0.is_host()
+    }
+    pub fn is_connected(&self) -> bool {
+        self.0.is_connected()
+    }
+    pub fn config(&self) -> &NetConfig {
+        self.0.config()
+    }
+    pub fn config_mut(&mut self) -> &mut NetConfig {
+        self.0.config_mut()
+    }
+    pub fn client(&self) -> &Client {
+        &self.0
+    }
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.0
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{NetError, PeerId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+#[derive(Default)]
+struct FenceState {
+    acked: Option<bool>,
+    waker: Option<Waker>,
+}
+pub struct Fence(Arc<Mutex<FenceState>>);
+impl Future for Fence {
+    type Output = Result<(), NetError>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+        match state.acked {
+            Some(true) => Poll::Ready(Ok(())),
+            Some(false) => Poll::Ready(Err(NetError::Disconnected)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+impl Fence {
+    pub fn is_done(&self) -> bool {
+        self.0.lock().unwrap().acked.is_some()
+    }
+}
+fn finish(state: &Mutex<FenceState>, acked: bool) {
+    let mut state = state.lock().unwrap();
+    state.acked = Some(acked);
+    if let Some(waker) = state.waker.take() {
+        waker.wake()
+    }
+}
+#[derive(Default)]
+pub(crate) struct Fences {
+    next: u64,
+    pending: HashMap<u64, (PeerId, Arc<Mutex<FenceState>>)>,
+    pub(crate) acks: Vec<(PeerId, u64)>,
+}
+impl Fences {
+    pub(crate) fn create(&mut self, peer: PeerId) -> (u64, Fence) {
+        let id = self.next;
+        self.next += 1;
+        let state = Arc::new(Mutex::new(FenceState::default()));
+        self.pending.insert(id, (peer, state.clone()));
+        (id, Fence(state))
+    }
+    pub(crate) fn complete(&mut self, peer: PeerId, id: u64) {
+        if let Some((p, state)) = self.pending.remove(&id) {
+            finish(&state, p == peer)
+        }
+    }
+    pub(crate) fn fail(&mut self, peer: PeerId) {
+        self.pending.retain(|_, (p, state)| {
+            if *p == peer {
+                finish(state, false);
+                false
+            } else {
+                true
+            }
+        });
+        self.acks.retain(|(p, _)| *p != peer)
+    }
+}
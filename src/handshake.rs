@@ -0,0 +1,161 @@
+use crate::seed::random;
+use crate::{MsgType, PeerId};
+use bitcode::{Decode, Encode, encode};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionBudget {
+    pub max_pending: usize,
+    pub max_attempts_per_minute: usize,
+    pub timeout: Duration,
+}
+impl Default for ConnectionBudget {
+    fn default() -> Self {
+        Self {
+            max_pending: 16,
+            max_attempts_per_minute: 60,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    pub attempts: u64,
+    pub accepted: u64,
+    pub refused: u64,
+    pub pending: usize,
+}
+#[derive(Encode, Decode, Copy, Debug, Clone, PartialEq, Eq)]
+pub enum RefuseReason {
+    Budget,
+    Timeout,
+    WrongPassword,
+    Denied,
+    WrongGame,
+}
+const MAGIC: [u8; 4] = *b"BTNG";
+const HELD: usize = 64;
+pub(crate) fn hello(game: Option<u64>, proof: Option<[u8; 32]>) -> Vec<u8> {
+    let mut packet = MAGIC.to_vec();
+    packet.extend_from_slice(&game.unwrap_or(0).to_le_bytes());
+    packet.extend_from_slice(&encode(&MsgType::Hello(proof)));
+    packet
+}
+pub(crate) fn is_hello(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+pub(crate) fn strip_hello(data: &[u8], game: Option<u64>) -> Option<&[u8]> {
+    let rest = data.strip_prefix(&MAGIC)?;
+    let (id, rest) = rest.split_first_chunk::<8>()?;
+    game.is_none_or(|g| g == u64::from_le_bytes(*id))
+        .then_some(rest)
+}
+#[derive(Default)]
+pub(crate) struct Handshakes {
+    pending: HashMap<PeerId, (Instant, u64)>,
+    refused: HashSet<PeerId>,
+    held: HashMap<PeerId, Vec<Vec<u8>>>,
+    recent: VecDeque<Instant>,
+    stats: ConnectionStats,
+}
+impl Handshakes {
+    pub(crate) fn begin(&mut self, peer: PeerId, budget: ConnectionBudget) -> bool {
+        let now = Instant::now();
+        self.stats.attempts += 1;
+        while self
+            .recent
+            .front()
+            .is_some_and(|t| now - *t >= Duration::from_secs(60))
+        {
+            self.recent.pop_front();
+        }
+        if self.pending.len() >= budget.max_pending
+            || self.recent.len() >= budget.max_attempts_per_minute
+        {
+            return false;
+        }
+        self.recent.push_back(now);
+        self.pending.insert(peer, (now, random()));
+        true
+    }
+    pub(crate) fn complete(&mut self, peer: PeerId) -> bool {
+        let done = self.pending.remove(&peer).is_some();
+        if done {
+            self.stats.accepted += 1;
+        }
+        done
+    }
+    pub(crate) fn hold(&mut self, peer: PeerId, packet: Vec<u8>) {
+        if !self.pending.contains_key(&peer) {
+            return;
+        }
+        let held = self.held.entry(peer).or_default();
+        if held.len() < HELD {
+            held.push(packet)
+        }
+    }
+    pub(crate) fn release(&mut self, peer: PeerId) -> Vec<Vec<u8>> {
+        self.held.remove(&peer).unwrap_or_default()
+    }
+    pub(crate) fn refuse(&mut self, peer: PeerId) {
+        self.pending.remove(&peer);
+        self.held.remove(&peer);
+        self.refused.insert(peer);
+        self.stats.refused += 1;
+    }
+    pub(crate) fn expired(&self, timeout: Duration) -> Vec<PeerId> {
+        self.pending
+            .iter()
+            .filter(|(_, (t, _))| t.elapsed() >= timeout)
+            .map(|(p, _)| *p)
+            .collect()
+    }
+    pub(crate) fn nonce(&self, peer: PeerId) -> Option<u64> {
+        self.pending.get(&peer).map(|(_, n)| *n)
+    }
+    pub(crate) fn contains(&self, peer: PeerId) -> bool {
+        self.pending.contains_key(&peer) || self.refused.contains(&peer)
+    }
+    pub(crate) fn is_active(&self) -> bool {
+        !self.pending.is_empty() || !self.refused.is_empty()
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) -> bool {
+        self.held.remove(&peer);
+        self.pending.remove(&peer).is_some() | self.refused.remove(&peer)
+    }
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            pending: self.pending.len(),
+            ..self.stats
+        }
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_connection_budget() {
+    let budget = ConnectionBudget {
+        max_pending: 2,
+        max_attempts_per_minute: 3,
+        timeout: Duration::ZERO,
+    };
+    let mut handshakes = Handshakes::default();
+    assert!(handshakes.begin(PeerId(1), budget));
+    assert!(handshakes.begin(PeerId(2), budget));
+    assert!(!handshakes.begin(PeerId(3), budget));
+    assert!(handshakes.complete(PeerId(1)));
+    assert!(handshakes.begin(PeerId(3), budget));
+    handshakes.remove(PeerId(3));
+    assert!(!handshakes.begin(PeerId(4), budget));
+    assert_eq!(handshakes.expired(budget.timeout), vec![PeerId(2)]);
+    handshakes.refuse(PeerId(2));
+    assert!(handshakes.contains(PeerId(2)));
+    assert_eq!(
+        handshakes.stats(),
+        ConnectionStats {
+            attempts: 5,
+            accepted: 1,
+            refused: 1,
+            pending: 0,
+        }
+    );
+}
@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub struct Hibernate {
+    pub idle_after: Duration,
+    pub keepalive: Duration,
+    pub poll: Duration,
+}
+impl Default for Hibernate {
+    fn default() -> Self {
+        Self {
+            idle_after: Duration::from_secs(10),
+            keepalive: Duration::from_secs(5),
+            poll: Duration::from_millis(100),
+        }
+    }
+}
+pub(crate) struct Idle {
+    active: AtomicBool,
+    last: Instant,
+    last_poll: Instant,
+    pub(crate) hibernating: bool,
+}
+impl Default for Idle {
+    fn default() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            last: Instant::now(),
+            last_poll: Instant::now(),
+            hibernating: false,
+        }
+    }
+}
+impl Idle {
+    pub(crate) fn touch(&self) {
+        self.active.store(true, Ordering::Relaxed)
+    }
+    pub(crate) fn poll_due(&mut self, config: Option<Hibernate>) -> bool {
+        let due = !self.hibernating || config.is_none_or(|h| self.last_poll.elapsed() >= h.poll);
+        if due {
+            self.last_poll = Instant::now();
+        }
+        due
+    }
+    pub(crate) fn update(&mut self, config: Option<Hibernate>) -> Option<bool> {
+        if self.active.swap(false, Ordering::Relaxed) {
+            self.last = Instant::now();
+            if self.hibernating {
+                self.hibernating = false;
+                return Some(false);
+            }
+        }
+        let h = config?;
+        if !self.hibernating && self.last.elapsed() >= h.idle_after {
+            self.hibernating = true;
+            return Some(true);
+        }
+        None
+    }
+}
@@ -0,0 +1,21 @@
+use crate::{Client, NetEvent, PeerId};
+impl Client {
+    pub fn identity(&self) -> Option<u128> {
+        self.config.identity
+    }
+    pub fn peer_identity(&self, peer: PeerId) -> Option<u128> {
+        self.identities.get(&peer).copied()
+    }
+    pub fn peer_by_identity(&self, identity: u128) -> Option<PeerId> {
+        self.identities
+            .iter()
+            .find(|(_, id)| **id == identity)
+            .map(|(peer, _)| *peer)
+    }
+    pub(crate) fn receive_identity(&mut self, peer: PeerId, identity: u128) {
+        if self.identities.insert(peer, identity) != Some(identity) {
+            self.net_events
+                .push_back(NetEvent::PeerIdentified { peer, identity })
+        }
+    }
+}
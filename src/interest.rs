@@ -0,0 +1,89 @@
+use crate::{Channel, Client, Compression, NetError, PeerId, Reliability};
+use bitcode::Encode;
+use std::collections::{HashMap, HashSet};
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerInterest {
+    pub team: Option<u32>,
+    pub position: Option<[f32; 3]>,
+    pub radius: Option<f32>,
+    pub subscriptions: HashSet<u64>,
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::component::Component))]
+pub struct Relevancy {
+    pub id: u64,
+    pub team: Option<u32>,
+    pub position: Option<[f32; 3]>,
+}
+impl PeerInterest {
+    pub fn is_empty(&self) -> bool {
+        self.team.is_none() && self.radius.is_none() && self.subscriptions.is_empty()
+    }
+    pub fn cares(&self, relevancy: &Relevancy) -> bool {
+        if self.is_empty() || self.subscriptions.contains(&relevancy.id) {
+            return true;
+        }
+        if self.team.is_some() && self.team == relevancy.team {
+            return true;
+        }
+        match (self.position, self.radius, relevancy.position) {
+            (Some(a), Some(r), Some(b)) => {
+                a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum::<f32>() <= r * r
+            }
+            _ => false,
+        }
+    }
+}
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Interests {
+    pub(crate) peers: HashMap<PeerId, PeerInterest>,
+}
+impl Interests {
+    pub(crate) fn cares(&self, peer: PeerId, relevancy: &Relevancy) -> bool {
+        self.peers.get(&peer).is_none_or(|i| i.cares(relevancy))
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.peers.remove(&peer);
+    }
+}
+impl Client {
+    pub fn set_interest(&mut self, peer: PeerId, interest: PeerInterest) {
+        self.interests.peers.insert(peer, interest);
+    }
+    pub fn interest(&self, peer: PeerId) -> Option<&PeerInterest> {
+        self.interests.peers.get(&peer)
+    }
+    pub fn subscribe(&mut self, peer: PeerId, id: u64) {
+        self.interests
+            .peers
+            .entry(peer)
+            .or_default()
+            .subscriptions
+            .insert(id);
+    }
+    pub fn unsubscribe(&mut self, peer: PeerId, id: u64) {
+        if let Some(interest) = self.interests.peers.get_mut(&peer) {
+            interest.subscriptions.remove(&id);
+        }
+    }
+    pub fn relevant_peers(&self, relevancy: &Relevancy) -> Vec<PeerId> {
+        self.peers()
+            .into_iter()
+            .filter(|p| self.interests.cares(*p, relevancy))
+            .collect()
+    }
+    pub fn broadcast_relevant<T: Encode>(
+        &self,
+        channel: Channel,
+        relevancy: &Relevancy,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(channel, None, data, reliability, compression)?;
+        for peer in self.relevant_peers(relevancy) {
+            self.send_packet(peer, packet.clone(), reliability)?;
+        }
+        Ok(())
+    }
+}
@@ -1,33 +1,60 @@
+use crate::seal::Seal;
+#[cfg(feature = "upnp")]
+use crate::upnp::PortMapping;
 use crate::{
-    Client, ClientCallback, ClientMode, ClientTrait, ClientTypeRef, Compression, Message, NetError,
-    PeerId, Reliability, pack, unpack,
+    Channel, Client, ClientCallback, ClientMode, ClientTrait, Compression, DisconnectReason, Event,
+    MsgType, NetError, PeerId, Reliability, decompress, frame, pack, unpack,
 };
-use bitcode::{DecodeOwned, Encode};
-use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use bitcode::Encode;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, UdpSocket};
 use tangled::TangledInitError;
 use tangled::{NetworkEvent, Peer};
-use tokio::runtime::Runtime;
+use tokio::runtime::{Builder, Handle, Runtime};
+const EPHEMERAL_ATTEMPTS: usize = 8;
 pub struct IpClient {
     pub(crate) peer: Peer,
     pub(crate) peer_connected: ClientCallback,
     pub(crate) peer_disconnected: ClientCallback,
+    pub(crate) local_addr: Option<SocketAddr>,
+    remote: Option<SocketAddr>,
+    #[cfg(feature = "upnp")]
+    pub(crate) port_mapping: Option<PortMapping>,
     connected: bool,
+    pub(crate) legacy: bool,
+    seal: Seal,
 }
 impl IpClient {
+    #[allow(unused_variables)]
     pub(crate) fn host(
         socket_addr: SocketAddr,
+        forward: bool,
+        seal: Seal,
         peer_connected: ClientCallback,
         peer_disconnected: ClientCallback,
     ) -> Result<Self, TangledInitError> {
+        let (peer, socket_addr) = if socket_addr.port() == 0 {
+            bind_ephemeral(socket_addr)?
+        } else {
+            (Peer::host(socket_addr, None)?, socket_addr)
+        };
         Ok(Self {
-            peer: Peer::host(socket_addr, None)?,
+            peer,
             peer_connected,
             peer_disconnected,
+            local_addr: (socket_addr.port() != 0).then_some(socket_addr),
+            remote: None,
+            #[cfg(feature = "upnp")]
+            port_mapping: (forward && socket_addr.port() != 0)
+                .then(|| PortMapping::new(socket_addr.port())),
             connected: true,
+            legacy: false,
+            seal,
         })
     }
     pub(crate) fn join(
         socket_addr: SocketAddr,
+        seal: Seal,
         peer_connected: ClientCallback,
         peer_disconnected: ClientCallback,
     ) -> Result<Self, TangledInitError> {
@@ -35,66 +62,106 @@ impl IpClient {
             peer: Peer::connect(socket_addr, None)?,
             peer_connected,
             peer_disconnected,
+            local_addr: None,
+            remote: Some(socket_addr),
+            #[cfg(feature = "upnp")]
+            port_mapping: None,
             connected: false,
+            legacy: false,
+            seal,
         })
     }
-    pub(crate) fn recv<T, F>(&mut self, mut f: F)
-    where
-        F: FnMut(ClientTypeRef, Message<T>),
-        T: DecodeOwned,
-    {
+    pub(crate) fn rejoin(&mut self) -> Result<(), TangledInitError> {
+        if let Some(remote) = self.remote {
+            self.peer = Peer::connect(remote, None)?;
+            self.connected = false;
+        }
+        Ok(())
+    }
+    pub(crate) fn poll(&mut self, events: &mut Vec<Event>) {
         if self.connected {
             for n in self.peer.recv() {
                 match n {
                     NetworkEvent::Message(m) => {
-                        let src = m.src.into();
-                        let data = unpack(&m.data);
-                        f(ClientTypeRef::Ip(self), Message { src, data })
+                        let data = if self.legacy {
+                            frame(
+                                Channel::DEFAULT,
+                                None,
+                                m.data.into(),
+                                Compression::Uncompressed,
+                            )
+                        } else {
+                            m.data.into()
+                        };
+                        events.push(Event::Message(m.src.into(), data))
                     }
                     NetworkEvent::PeerConnected(peer) => {
-                        if PeerId::from(peer) != self.my_id()
-                            && let Some(mut c) = self.peer_connected.take()
-                        {
-                            c(ClientTypeRef::Ip(self), peer.into());
-                            self.peer_connected = Some(c);
+                        if PeerId::from(peer) != self.my_id() {
+                            events.push(Event::Connected(peer.into()))
                         }
                     }
                     NetworkEvent::PeerDisconnected(peer) => {
-                        if let Some(mut d) = self.peer_disconnected.take() {
-                            d(ClientTypeRef::Ip(self), peer.into());
-                            self.peer_disconnected = Some(d);
-                        }
+                        events.push(Event::Disconnected(peer.into(), DisconnectReason::Timeout))
                     }
                 }
             }
         }
     }
-    pub(crate) fn recv_raw<F>(&mut self, mut f: F)
-    where
-        F: FnMut(ClientTypeRef, Message<&[u8]>),
-    {
+    pub fn id(&self) -> Option<PeerId> {
+        self.peer.my_id().map(PeerId::from)
+    }
+    pub(crate) fn peers(&self) -> Vec<PeerId> {
+        let me = self.peer.my_id();
+        self.peer
+            .iter_peer_ids()
+            .filter(|p| Some(*p) != me)
+            .map(PeerId::from)
+            .collect()
+    }
+    pub(crate) fn remote(&self) -> Option<SocketAddr> {
+        self.remote
+    }
+    pub(crate) fn has_peer(&self, peer: PeerId) -> bool {
+        self.peer.iter_peer_ids().any(|p| PeerId::from(p) == peer)
+    }
+    pub(crate) fn send_packet(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        let Some(packet) = self.outgoing(packet) else {
+            return Ok(());
+        };
+        let packet = self.seal.apply(packet, reliability)?;
         if self.connected {
-            for n in self.peer.recv() {
-                match n {
-                    NetworkEvent::Message(m) => {
-                        let src = m.src.into();
-                        let data = &m.data;
-                        f(ClientTypeRef::Ip(self), Message { src, data })
-                    }
-                    NetworkEvent::PeerConnected(peer) => {
-                        if let Some(mut c) = self.peer_connected.take() {
-                            c(ClientTypeRef::Ip(self), peer.into());
-                            self.peer_connected = Some(c);
-                        }
-                    }
-                    NetworkEvent::PeerDisconnected(peer) => {
-                        if let Some(mut d) = self.peer_disconnected.take() {
-                            d(ClientTypeRef::Ip(self), peer.into());
-                            self.peer_disconnected = Some(d);
-                        }
-                    }
-                }
-            }
+            self.peer.send(dest.into(), packet, reliability.into())?;
+        }
+        Ok(())
+    }
+    pub(crate) fn broadcast_packet(
+        &self,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        let Some(packet) = self.outgoing(packet) else {
+            return Ok(());
+        };
+        let packet = self.seal.apply(packet, reliability)?;
+        if self.connected {
+            self.peer.broadcast(packet, reliability.into())?;
+        }
+        Ok(())
+    }
+    fn outgoing(&self, packet: Vec<u8>) -> Option<Vec<u8>> {
+        if !self.legacy {
+            return Some(packet);
+        }
+        match unpack(&packet)? {
+            MsgType::Data {
+                compressed, data, ..
+            } => decompress(compressed, data, usize::MAX).ok(),
+            _ => None,
         }
     }
     pub(crate) fn update(&mut self) {
@@ -103,6 +170,20 @@ impl IpClient {
         }
     }
 }
+fn bind_ephemeral(socket_addr: SocketAddr) -> Result<(Peer, SocketAddr), TangledInitError> {
+    let mut attempts = 0;
+    loop {
+        let Ok(addr) = UdpSocket::bind(socket_addr).and_then(|s| s.local_addr()) else {
+            return Ok((Peer::host(socket_addr, None)?, socket_addr));
+        };
+        attempts += 1;
+        match Peer::host(addr, None) {
+            Ok(peer) => return Ok((peer, addr)),
+            Err(e) if attempts >= EPHEMERAL_ATTEMPTS => return Err(e),
+            Err(_) => {}
+        }
+    }
+}
 impl ClientTrait for IpClient {
     fn send<T: Encode>(
         &self,
@@ -111,7 +192,7 @@ impl ClientTrait for IpClient {
         reliability: Reliability,
         compression: Compression,
     ) -> Result<(), NetError> {
-        self.send_raw(dest, pack(data, compression), reliability)
+        self.send_packet(dest, pack(data, compression), reliability)
     }
     fn broadcast<T: Encode>(
         &self,
@@ -119,7 +200,7 @@ impl ClientTrait for IpClient {
         reliability: Reliability,
         compression: Compression,
     ) -> Result<(), NetError> {
-        self.broadcast_raw(pack(data, compression), reliability)
+        self.broadcast_packet(pack(data, compression), reliability)
     }
     fn send_raw(
         &self,
@@ -127,25 +208,21 @@ impl ClientTrait for IpClient {
         data: Vec<u8>,
         reliability: Reliability,
     ) -> Result<(), NetError> {
-        if self.connected {
-            self.peer.send(dest.into(), data, reliability.into())?;
-        }
-        Ok(())
+        let packet = frame(Channel::DEFAULT, None, data, Compression::Uncompressed);
+        self.send_packet(dest, packet, reliability)
     }
     fn broadcast_raw(&self, data: Vec<u8>, reliability: Reliability) -> Result<(), NetError> {
-        if self.connected {
-            self.peer.broadcast(data, reliability.into())?;
-        }
-        Ok(())
+        let packet = frame(Channel::DEFAULT, None, data, Compression::Uncompressed);
+        self.broadcast_packet(packet, reliability)
     }
     fn my_id(&self) -> PeerId {
-        self.peer.my_id().unwrap().into()
+        self.id().unwrap_or(PeerId::UNASSIGNED)
     }
     fn host_id(&self) -> PeerId {
         PeerId(0)
     }
     fn is_host(&self) -> bool {
-        self.peer.my_id().unwrap() == tangled::PeerId(0) && self.connected
+        self.connected && self.id() == Some(self.host_id())
     }
     fn peer_len(&self) -> usize {
         self.peer.iter_peer_ids().count()
@@ -166,14 +243,32 @@ impl ClientTrait for IpClient {
         None
     }
 }
+pub(crate) fn host_addr(port: u16, public: bool) -> SocketAddr {
+    let ip = if public {
+        Ipv6Addr::UNSPECIFIED
+    } else {
+        Ipv6Addr::LOCALHOST
+    };
+    SocketAddr::new(IpAddr::V6(ip), port)
+}
 impl From<Reliability> for tangled::Reliability {
     fn from(value: Reliability) -> Self {
         match value {
-            Reliability::Reliable => tangled::Reliability::Reliable,
-            Reliability::Unreliable => tangled::Reliability::Unreliable,
+            Reliability::Reliable | Reliability::ReliableUnordered => {
+                tangled::Reliability::Reliable
+            }
+            Reliability::Unreliable | Reliability::BestEffort | Reliability::Sequenced => {
+                tangled::Reliability::Unreliable
+            }
         }
     }
 }
+pub(crate) fn proof(password: &str, nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    hasher.update(nonce.to_le_bytes());
+    hasher.finalize().into()
+}
 impl From<tangled::PeerId> for PeerId {
     fn from(value: tangled::PeerId) -> Self {
         Self(value.0.into())
@@ -184,6 +279,11 @@ impl From<PeerId> for tangled::PeerId {
         Self(value.raw() as u16)
     }
 }
+#[derive(Copy, Clone)]
+enum Target {
+    Host(u16),
+    Join(SocketAddr),
+}
 impl Client {
     pub fn host_ip(
         &mut self,
@@ -191,9 +291,28 @@ impl Client {
         peer_connected: ClientCallback,
         peer_disconnected: ClientCallback,
     ) -> Result<(), TangledInitError> {
-        let socket = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), port);
-        self.ip_client = Some(IpClient::host(socket, peer_connected, peer_disconnected)?);
-        Ok(())
+        self.start_ip(
+            Target::Host(port),
+            None,
+            None,
+            peer_connected,
+            peer_disconnected,
+        )
+    }
+    pub fn host_ip_with_password(
+        &mut self,
+        port: u16,
+        password: &str,
+        peer_connected: ClientCallback,
+        peer_disconnected: ClientCallback,
+    ) -> Result<(), TangledInitError> {
+        self.start_ip(
+            Target::Host(port),
+            Some(password),
+            None,
+            peer_connected,
+            peer_disconnected,
+        )
     }
     pub fn join_ip(
         &mut self,
@@ -201,8 +320,28 @@ impl Client {
         peer_connected: ClientCallback,
         peer_disconnected: ClientCallback,
     ) -> Result<(), TangledInitError> {
-        self.ip_client = Some(IpClient::join(socket, peer_connected, peer_disconnected)?);
-        Ok(())
+        self.start_ip(
+            Target::Join(socket),
+            None,
+            None,
+            peer_connected,
+            peer_disconnected,
+        )
+    }
+    pub fn join_ip_with_password(
+        &mut self,
+        socket: SocketAddr,
+        password: &str,
+        peer_connected: ClientCallback,
+        peer_disconnected: ClientCallback,
+    ) -> Result<(), TangledInitError> {
+        self.start_ip(
+            Target::Join(socket),
+            Some(password),
+            None,
+            peer_connected,
+            peer_disconnected,
+        )
     }
     pub fn host_ip_runtime(
         &mut self,
@@ -211,11 +350,13 @@ impl Client {
         peer_disconnected: ClientCallback,
         runtime: &Runtime,
     ) -> Result<(), TangledInitError> {
-        let socket = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), port);
-        let client =
-            runtime.block_on(async { IpClient::host(socket, peer_connected, peer_disconnected) });
-        self.ip_client = Some(client?);
-        Ok(())
+        self.start_ip(
+            Target::Host(port),
+            None,
+            Some(runtime.handle().clone()),
+            peer_connected,
+            peer_disconnected,
+        )
     }
     pub fn join_ip_runtime(
         &mut self,
@@ -224,9 +365,126 @@ impl Client {
         peer_disconnected: ClientCallback,
         runtime: &Runtime,
     ) -> Result<(), TangledInitError> {
-        let client =
-            runtime.block_on(async { IpClient::join(socket, peer_connected, peer_disconnected) });
-        self.ip_client = Some(client?);
+        self.start_ip(
+            Target::Join(socket),
+            None,
+            Some(runtime.handle().clone()),
+            peer_connected,
+            peer_disconnected,
+        )
+    }
+    fn start_ip(
+        &mut self,
+        target: Target,
+        password: Option<&str>,
+        runtime: Option<Handle>,
+        peer_connected: ClientCallback,
+        peer_disconnected: ClientCallback,
+    ) -> Result<(), TangledInitError> {
+        let runtime = runtime.or_else(|| self.ip_runtime());
+        let _guard = runtime.as_ref().map(Handle::enter);
+        let seal = self.sealer();
+        self.ip_client = Some(match target {
+            Target::Host(port) => {
+                let forward = self.port_forward();
+                IpClient::host(
+                    host_addr(port, forward),
+                    forward,
+                    seal,
+                    peer_connected,
+                    peer_disconnected,
+                )?
+            }
+            Target::Join(socket) => {
+                IpClient::join(socket, seal, peer_connected, peer_disconnected)?
+            }
+        });
+        self.password = password.map(str::to_owned);
+        if let Target::Join(_) = target {
+            self.begin_join();
+        }
         Ok(())
     }
+    fn port_forward(&self) -> bool {
+        #[cfg(feature = "upnp")]
+        {
+            self.config.port_forward
+        }
+        #[cfg(not(feature = "upnp"))]
+        {
+            false
+        }
+    }
+    pub(crate) fn ip_runtime(&mut self) -> Option<Handle> {
+        if let Some(handle) = &self.config.runtime {
+            return Some(handle.clone());
+        }
+        if Handle::try_current().is_ok() {
+            return None;
+        }
+        if self.runtime.is_none() {
+            self.runtime = Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build()
+                .ok();
+        }
+        Some(self.runtime.as_ref()?.handle().clone())
+    }
+}
+#[cfg(test)]
+#[tokio::test]
+async fn test_password_handshake() {
+    use crate::{NetEvent, RefuseReason};
+    use std::time::Duration;
+    let client = || {
+        #[cfg(feature = "steam")]
+        let mut client = Client::without_steam();
+        #[cfg(not(feature = "steam"))]
+        let mut client = Client::new().unwrap();
+        client.config_mut().game_id = Some(9);
+        client
+    };
+    let mut host = client();
+    host.host_ip_with_password(0, "hunter2", None, None)
+        .unwrap();
+    let addr = host.local_addr().unwrap();
+    let mut peers = [client(), client()];
+    peers[0]
+        .join_ip_with_password(addr, "hunter2", None, None)
+        .unwrap();
+    peers[1]
+        .join_ip_with_password(addr, "guess", None, None)
+        .unwrap();
+    let mut events = Vec::new();
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        for peer in &mut peers {
+            let _ = peer.update();
+        }
+        let _ = host.update();
+        events.extend(host.events());
+    }
+    let accepted = peers[0].my_id();
+    assert!(events.contains(&NetEvent::PeerConnected(accepted)));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        NetEvent::ConnectionRefused {
+            reason: RefuseReason::WrongPassword,
+            ..
+        }
+    )));
+    assert_eq!(host.peers(), vec![accepted]);
+    peers[0]
+        .send(
+            PeerId(0),
+            &5u32,
+            Reliability::Reliable,
+            Compression::Uncompressed,
+        )
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let mut received = Vec::new();
+    host.recv::<u32, _>(|_, m| received.push(m.data));
+    assert_eq!(received, vec![5]);
 }
@@ -0,0 +1,68 @@
+use crate::{Client, ClientTrait, DisconnectReason, MsgType, NetEvent, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+pub(crate) struct Liveness {
+    pub(crate) last_seen: HashMap<PeerId, Instant>,
+    pub(crate) last_keepalive: Instant,
+    pub(crate) timed_out: HashSet<PeerId>,
+    pub(crate) since: HashMap<PeerId, Instant>,
+}
+impl Default for Liveness {
+    fn default() -> Self {
+        Self {
+            last_seen: HashMap::new(),
+            last_keepalive: Instant::now(),
+            timed_out: HashSet::new(),
+            since: HashMap::new(),
+        }
+    }
+}
+impl Client {
+    pub(crate) fn keepalive(&mut self) {
+        let now = Instant::now();
+        let keepalive = match self.config.hibernate.filter(|_| self.idle.hibernating) {
+            Some(h) => self.config.keepalive.map(|k| {
+                let k = k.max(h.keepalive);
+                self.config.timeout.map_or(k, |t| k.min(t / 2))
+            }),
+            None => self.config.keepalive,
+        };
+        if keepalive.is_some_and(|k| now - self.liveness.last_keepalive >= k) {
+            self.liveness.last_keepalive = now;
+            let packet = encode(&MsgType::KeepAlive);
+            let _ = self.broadcast_packet(packet, Reliability::Unreliable);
+        }
+        let Some(timeout) = self.config.timeout else {
+            return;
+        };
+        let mut expired = Vec::new();
+        for peer in self.connected_peers() {
+            if now - *self.liveness.last_seen.entry(peer).or_insert(now) > timeout {
+                expired.push(peer)
+            }
+        }
+        for peer in expired {
+            let reason = DisconnectReason::Timeout;
+            self.net_events.push_back(NetEvent::ConnectionLost {
+                peer,
+                reason: reason.clone(),
+            });
+            #[cfg(feature = "tangled")]
+            if self.is_ip() {
+                if peer == self.host_id() && !self.is_host() && self.config.reconnect.is_none() {
+                    self.end_session(reason);
+                    return;
+                }
+                self.liveness.timed_out.insert(peer);
+                self.disconnected(peer, reason);
+                continue;
+            }
+            #[cfg(feature = "steam")]
+            if let Some(steam) = &mut self.steam_client {
+                steam.connections.remove(&peer);
+            }
+            self.disconnected(peer, reason);
+        }
+    }
+}
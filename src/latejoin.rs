@@ -0,0 +1,85 @@
+use crate::{
+    Bytes, Client, ClientTrait, Message, MsgType, NetError, NetEvent, PeerId, Reliability,
+};
+use bitcode::encode;
+use std::collections::VecDeque;
+const CHUNK: usize = 64 * 1024;
+type Hook = Box<dyn FnMut(PeerId) -> Option<Vec<u8>> + Send + Sync + 'static>;
+#[derive(Default)]
+pub(crate) struct LateJoin {
+    hook: Option<Hook>,
+    incoming: Option<(u64, Vec<u8>)>,
+    held: VecDeque<Message<Bytes>>,
+    snapshot: Option<Vec<u8>>,
+}
+impl Client {
+    pub fn on_late_join(
+        &mut self,
+        hook: impl FnMut(PeerId) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) {
+        self.late_join.hook = Some(Box::new(hook))
+    }
+    pub fn clear_late_join(&mut self) {
+        self.late_join.hook = None
+    }
+    pub fn send_join_snapshot(&self, peer: PeerId, data: &[u8]) -> Result<(), NetError> {
+        let total = data.len() as u64;
+        if data.is_empty() {
+            let packet = encode(&MsgType::JoinSnapshot {
+                total,
+                data: Vec::new(),
+            });
+            return self.send_packet(peer, packet, Reliability::Reliable);
+        }
+        for chunk in data.chunks(CHUNK) {
+            let packet = encode(&MsgType::JoinSnapshot {
+                total,
+                data: chunk.to_vec(),
+            });
+            self.send_packet(peer, packet, Reliability::Reliable)?;
+        }
+        Ok(())
+    }
+    pub fn take_join_snapshot(&mut self) -> Option<Vec<u8>> {
+        self.late_join.snapshot.take()
+    }
+    pub fn is_receiving_snapshot(&self) -> bool {
+        self.late_join.incoming.is_some()
+    }
+    pub(crate) fn supply_snapshot(&mut self, peer: PeerId) {
+        let Some(hook) = &mut self.late_join.hook else {
+            return;
+        };
+        if let Some(data) = hook(peer) {
+            let _ = self.send_join_snapshot(peer, &data);
+        }
+    }
+    pub(crate) fn receive_snapshot(&mut self, src: PeerId, total: u64, data: Vec<u8>) {
+        if src != self.host_id() || self.is_host() || total > self.config.max_file_size {
+            return;
+        }
+        let (_, buf) = self
+            .late_join
+            .incoming
+            .get_or_insert_with(|| (total, Vec::new()));
+        buf.extend_from_slice(&data);
+        if (buf.len() as u64) < total {
+            return;
+        }
+        let (_, buf) = self.late_join.incoming.take().unwrap();
+        self.late_join.snapshot = Some(buf);
+        self.net_events.push_back(NetEvent::JoinSnapshot);
+        self.inbox.extend(self.late_join.held.drain(..));
+    }
+    pub(crate) fn deliver(&mut self, message: Message<Bytes>) {
+        if self.late_join.incoming.is_some() {
+            self.late_join.held.push_back(message)
+        } else {
+            self.inbox.push_back(message)
+        }
+    }
+    pub(crate) fn reset_late_join(&mut self) {
+        self.late_join.incoming = None;
+        self.inbox.extend(self.late_join.held.drain(..));
+    }
+}
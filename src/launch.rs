@@ -0,0 +1,66 @@
+use crate::{Client, NetError, NetEvent};
+use std::net::SocketAddr;
+#[cfg(feature = "steam")]
+use steamworks::LobbyId;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingJoin {
+    #[cfg(feature = "steam")]
+    Lobby(LobbyId),
+    Addr(SocketAddr),
+}
+impl PendingJoin {
+    pub fn parse<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Option<Self> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                #[cfg(feature = "steam")]
+                "+connect_lobby" => {
+                    if let Some(id) = args.next().and_then(|a| a.as_ref().parse().ok()) {
+                        return Some(Self::Lobby(LobbyId::from_raw(id)));
+                    }
+                }
+                "+connect" => {
+                    if let Some(addr) = args.next().and_then(|a| a.as_ref().parse().ok()) {
+                        return Some(Self::Addr(addr));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+    pub fn parse_str(line: &str) -> Option<Self> {
+        Self::parse(line.split_whitespace())
+    }
+    pub fn from_args() -> Option<Self> {
+        Self::parse(std::env::args())
+    }
+}
+impl Client {
+    pub fn pending_join(&self) -> Option<&PendingJoin> {
+        self.pending_join.as_ref()
+    }
+    pub fn decline_join(&mut self) -> Option<PendingJoin> {
+        self.pending_join.take()
+    }
+    pub fn accept_join(&mut self) -> Result<(), NetError> {
+        match self.pending_join.take() {
+            #[cfg(feature = "steam")]
+            Some(PendingJoin::Lobby(lobby)) => {
+                self.join_steam(lobby);
+                Ok(())
+            }
+            #[cfg(feature = "tangled")]
+            Some(PendingJoin::Addr(addr)) => self
+                .join_ip(addr, None, None)
+                .map_err(|e| NetError::Init(format!("{e:?}"))),
+            #[cfg(not(feature = "tangled"))]
+            Some(PendingJoin::Addr(_)) => Err(NetError::Unsupported),
+            None => Err(NetError::Disconnected),
+        }
+    }
+    pub(crate) fn set_pending_join(&mut self, join: PendingJoin) {
+        self.pending_join = Some(join.clone());
+        self.net_events.push_back(NetEvent::PendingJoin(join))
+    }
+}
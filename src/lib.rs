@@ -1,37 +1,417 @@
+mod access;
+#[cfg(feature = "steam")]
+mod auth;
+mod background;
+mod batch;
+#[cfg(feature = "bots")]
+mod bot;
+mod bridge;
+mod callbacks;
+mod channels;
+#[cfg(feature = "chat")]
+mod chat;
+mod clients;
+mod clock;
+mod config;
+mod congestion;
+mod connection;
+#[cfg(feature = "encrypt")]
+mod crypto;
+mod dedup;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod facade;
+mod fence;
+mod handshake;
+mod hibernate;
+mod identity;
+mod interest;
 #[cfg(feature = "tangled")]
 mod ip;
+mod keepalive;
+mod latejoin;
+mod launch;
+mod lockstep;
+#[cfg(feature = "hmac")]
+mod mac;
+#[cfg(feature = "master")]
+mod master;
+#[cfg(feature = "tangled")]
+mod mesh;
+mod metadata;
+mod migration;
+mod offload;
+mod ownership;
+mod phase;
+mod pool;
+mod power;
+pub mod prelude;
+mod priority;
+mod progress;
+mod quality;
+mod queue;
+mod rate;
+mod ready;
+mod reconnect;
+mod registry;
+#[cfg(any(feature = "encrypt", feature = "hmac"))]
+mod replay;
+#[cfg(feature = "bevy_reflect")]
+mod replicate;
+mod resource;
+mod roles;
+mod rollback;
+mod router;
+mod seal;
+mod seed;
+mod sequence;
+mod settings;
+mod shutdown;
+mod snapshot;
+mod stats;
 #[cfg(feature = "steam")]
 mod steam;
+mod sticky;
+mod stream;
+#[cfg(any(test, feature = "testnet"))]
+mod testnet;
+mod tick;
+mod ticket;
+mod transfer;
+#[cfg(feature = "transform")]
+mod transform;
+mod ttl;
+mod unordered;
+mod upgrade;
+#[cfg(feature = "upnp")]
+mod upnp;
+#[cfg(feature = "steam")]
+mod voice;
+use crate::access::Access;
+use crate::clock::Clock;
+use crate::fence::Fences;
+use crate::handshake::Handshakes;
+use crate::hibernate::Idle;
+use crate::interest::Interests;
 #[cfg(feature = "tangled")]
 use crate::ip::IpClient;
+use crate::metadata::Metadata;
+use crate::offload::Offload;
+use crate::phase::Phases;
+use crate::pool::Pool;
+use crate::queue::{Pushed, SendQueues};
+use crate::rate::Limiter;
+use crate::reconnect::Reconnect;
+use crate::seed::Seeds;
+use crate::sequence::Sequences;
+use crate::stats::Counters;
 #[cfg(feature = "steam")]
 use crate::steam::SteamClient;
+use crate::sticky::Sticky;
+use crate::stream::Streams;
+use crate::tick::Ticker;
+use crate::ticket::Tickets;
+use crate::transfer::Transfers;
+use crate::unordered::Unordered;
+use crate::upgrade::Upgrade;
+#[cfg(feature = "steam")]
+use crate::voice::Voice;
+pub use access::AccessRule;
+#[cfg(feature = "steam")]
+pub use auth::AuthResult;
+pub use background::BackgroundClient;
 #[cfg(feature = "bevy")]
 use bevy_ecs::component::Component;
 #[cfg(feature = "bevy")]
 use bevy_ecs::resource::Resource;
 use bitcode::{Decode, Encode};
 use bitcode::{DecodeOwned, decode, encode};
+#[cfg(feature = "bots")]
+pub use bot::{BotPeer, BotStats, Traffic};
+pub use bytes::Bytes;
+pub use callbacks::CallbackId;
+pub use channels::{CapPolicy, ChannelCap, ChannelStats};
+#[cfg(feature = "chat")]
+pub use chat::ChatLine;
+#[cfg(all(feature = "chat", feature = "bevy"))]
+pub use chat::receive_chat;
+pub use clients::Clients;
+#[cfg(feature = "bevy")]
+pub use clients::update_clients;
+pub use clock::{ClockSkew, NetworkTime};
+pub use config::{NetConfig, UnknownPeerPolicy};
+pub use congestion::Bandwidth;
+pub use connection::PeerConnection;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::NetworkDiagnosticsPlugin;
+pub use facade::NetClient;
+pub use fence::Fence;
+pub use handshake::{ConnectionBudget, ConnectionStats, RefuseReason};
+pub use hibernate::Hibernate;
+pub use interest::{PeerInterest, Relevancy};
+pub use launch::PendingJoin;
+pub use lockstep::{Lockstep, LockstepEvent, TickInputs};
 #[cfg(feature = "compress")]
 use lz4_flex::{compress_prepend_size, decompress_size_prepended};
+#[cfg(feature = "master")]
+pub use master::{ServerFilter, ServerInfo, query_servers};
+pub use phase::{Phase, PhasePolicy};
+pub use pool::PoolStats;
+pub use power::{Platform, PowerProfile, on_battery};
+pub use priority::{Priority, PriorityAccumulator, PriorityDrops};
+pub use progress::{JoinFailure, JoinProgress};
+pub use quality::{ConnectionQuality, QualityThresholds};
+pub use queue::{OverflowPolicy, SendQueue};
+pub use rate::{RateLimit, ThrottlePolicy};
+pub use reconnect::Backoff;
+pub use registry::{MessageRegistry, NetMessage};
+#[cfg(feature = "bevy_reflect")]
+pub use replicate::{Replicated, ReplicationRegistry, receive_replicated, send_replicated};
+pub use roles::Role;
+pub use rollback::Rollback;
+pub use router::{Quota, QuotaKind, Router, RouterEvent, SessionId};
+pub use seed::mix as tick_seed;
+pub use snapshot::Snapshots;
+pub use stats::NetStats;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
 use std::ops::Deref;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+#[cfg(feature = "steam")]
+pub use steam::{
+    Avatar, AvatarSize, LobbyComparison, LobbyDistance, LobbyFilter, LobbySummary, SteamConfig,
+    SteamPath,
+};
 #[cfg(feature = "steam")]
 pub use steamworks::LobbyId;
 #[cfg(feature = "steam")]
 pub use steamworks::SteamError;
 #[cfg(feature = "steam")]
+pub use steamworks::SteamId;
+#[cfg(feature = "steam")]
 use steamworks::networking_types::NetConnectionRealTimeInfo;
+pub use stream::{NetStreamReader, NetStreamWriter};
+#[cfg(any(test, feature = "testnet"))]
+pub use testnet::{Loopback, TestNet};
+pub use tick::NetworkTick;
+pub use ticket::{SendStatus, SendTicket};
+pub use transfer::FileSource;
+#[cfg(feature = "transform")]
+pub use transform::{RemoteTransform, SyncTransform, TransformSync, TransformSyncPlugin};
+#[cfg(feature = "steam")]
+pub use voice::VoiceFrame;
 type ClientCallback = Option<Box<dyn FnMut(ClientTypeRef, PeerId) + Send + Sync + 'static>>;
 pub struct Message<T> {
     pub src: PeerId,
+    pub channel: Channel,
+    pub tick: Option<u64>,
     pub data: T,
 }
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecvBudget {
+    pub messages: Option<usize>,
+    pub bytes: Option<usize>,
+}
+pub struct RecvIter<'a, T> {
+    client: &'a mut Client,
+    _marker: PhantomData<T>,
+}
+impl<T: DecodeOwned> Iterator for RecvIter<'_, T> {
+    type Item = Result<Message<T>, NetError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.client.inbox.pop_front()?;
+        Some(match decode(&m.data) {
+            Ok(data) => Ok(Message {
+                src: m.src,
+                channel: m.channel,
+                tick: m.tick,
+                data,
+            }),
+            Err(_) => {
+                self.client.malformed(m.src);
+                Err(NetError::Malformed)
+            }
+        })
+    }
+}
+impl<T> Drop for RecvIter<'_, T> {
+    fn drop(&mut self) {
+        self.client.ack_fences()
+    }
+}
+pub struct RawRecvIter<'a> {
+    client: &'a mut Client,
+}
+impl Iterator for RawRecvIter<'_> {
+    type Item = Message<Bytes>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.client.inbox.pop_front()
+    }
+}
+impl Drop for RawRecvIter<'_> {
+    fn drop(&mut self) {
+        self.client.ack_fences()
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetEvent {
+    PeerConnected(PeerId),
+    PeerDisconnected {
+        peer: PeerId,
+        reason: DisconnectReason,
+    },
+    MetadataChanged {
+        peer: PeerId,
+        key: String,
+    },
+    Reconnecting {
+        attempt: u32,
+    },
+    Reconnected,
+    ReconnectFailed,
+    PeerRejoined {
+        old: PeerId,
+        new: PeerId,
+    },
+    ConnectionLost {
+        peer: PeerId,
+        reason: DisconnectReason,
+    },
+    Promoted,
+    HostMigrated {
+        host: PeerId,
+    },
+    SeedReceived {
+        seed: u64,
+    },
+    SeedAcknowledged {
+        seed: u64,
+    },
+    ClockResynced {
+        peer: PeerId,
+    },
+    FileProgress {
+        peer: PeerId,
+        id: u64,
+        done: u64,
+        total: u64,
+    },
+    FileSent {
+        peer: PeerId,
+        id: u64,
+    },
+    FileReceived {
+        peer: PeerId,
+        id: u64,
+    },
+    FileRejected {
+        peer: PeerId,
+        id: u64,
+    },
+    FileFailed {
+        peer: PeerId,
+        id: u64,
+    },
+    SteamUnavailable,
+    SteamRestored,
+    IpFallback {
+        addr: SocketAddr,
+    },
+    Hibernating,
+    Awake,
+    StreamOpened {
+        peer: PeerId,
+        id: u64,
+    },
+    LimitExceeded {
+        peer: PeerId,
+        size: usize,
+    },
+    PeerThrottled {
+        peer: PeerId,
+    },
+    CryptoFailed {
+        peer: PeerId,
+    },
+    ReplayDetected {
+        peer: PeerId,
+    },
+    ConnectionRefused {
+        peer: PeerId,
+        reason: RefuseReason,
+    },
+    JoinRejected(RefuseReason),
+    PhaseChanged(Phase),
+    ExperimentChanged(Option<String>),
+    ResourceReplicated(String),
+    OwnershipChanged {
+        id: u64,
+        owner: Option<PeerId>,
+    },
+    OwnershipViolation {
+        peer: PeerId,
+        id: u64,
+    },
+    LobbyChat {
+        peer: PeerId,
+        message: String,
+    },
+    PendingJoin(PendingJoin),
+    JoinProgress(JoinProgress),
+    CompressionUnsupported {
+        peer: PeerId,
+    },
+    PeerIdentified {
+        peer: PeerId,
+        identity: u128,
+    },
+    RoleChanged {
+        peer: PeerId,
+        role: Role,
+    },
+    ReadyChanged {
+        peer: PeerId,
+        ready: bool,
+    },
+    ReadyCountdown(Option<Duration>),
+    AllReady,
+    SettingsChanged,
+    JoinSnapshot,
+    ConnectionQualityChanged {
+        peer: PeerId,
+        quality: ConnectionQuality,
+    },
+    #[cfg(feature = "steam")]
+    AuthResult {
+        peer: PeerId,
+        result: AuthResult,
+    },
+    MalformedMessage {
+        peer: PeerId,
+    },
+    DeliveryFailed {
+        peer: PeerId,
+    },
+}
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub enum DisconnectReason {
+    Timeout,
+    Kicked(String),
+    Quit,
+    TransportError,
+}
 #[derive(Copy, Debug, Clone, Hash, PartialEq, PartialOrd, Ord, Eq)]
 pub enum Reliability {
     Reliable,
+    ReliableUnordered,
     Unreliable,
+    BestEffort,
+    Sequenced,
 }
 #[derive(Copy, Debug, Clone, Hash, PartialEq, PartialOrd, Ord, Eq)]
 pub enum Compression {
@@ -46,133 +426,2272 @@ impl Deref for PeerId {
     fn deref(&self) -> &Self::Target {
         &self.0
     }
-}
-impl Display for PeerId {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+}
+impl Display for PeerId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl PeerId {
+    pub const UNASSIGNED: Self = Self(u64::MAX);
+    pub fn raw(&self) -> u64 {
+        self.0
+    }
+}
+#[derive(Encode, Decode, Copy, Debug, Clone, Hash, PartialEq, PartialOrd, Ord, Eq)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+pub struct NetEntity(pub u64);
+#[derive(Encode, Decode, Copy, Debug, Clone, Default, Hash, PartialEq, PartialOrd, Ord, Eq)]
+pub struct Channel(pub u8);
+impl Deref for Channel {
+    type Target = u8;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl Channel {
+    pub const DEFAULT: Self = Self(0);
+    pub fn raw(&self) -> u8 {
+        self.0
+    }
+}
+#[derive(Encode, Decode)]
+pub(crate) enum MsgType<'a> {
+    Data {
+        channel: Channel,
+        seq: Option<u32>,
+        compressed: bool,
+        data: &'a [u8],
+    },
+    Fence(u64),
+    FenceAck(u64),
+    Upgradable {
+        seq: u32,
+        packet: Vec<u8>,
+    },
+    Nack(Vec<u32>),
+    Metadata(Vec<(String, Option<String>)>),
+    Goodbye(DisconnectReason),
+    Rejoin(PeerId),
+    KeepAlive,
+    CoHost(Option<PeerId>),
+    Ownership {
+        id: u64,
+        owner: Option<PeerId>,
+    },
+    Standby(Vec<u8>),
+    Seed {
+        id: u32,
+        seed: u64,
+    },
+    SeedAck(u32),
+    Unordered {
+        id: u32,
+        packet: Vec<u8>,
+    },
+    UnorderedAck(u32),
+    Tracked {
+        id: u64,
+        packet: Vec<u8>,
+    },
+    TrackedAck(u64),
+    FileOffer {
+        id: u64,
+        size: u64,
+    },
+    FileAccept(u64),
+    FileReject(u64),
+    FileChunk {
+        id: u64,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    FileAck {
+        id: u64,
+        received: u64,
+    },
+    FileCancel(u64),
+    Fallback(String),
+    Hello(Option<[u8; 32]>),
+    Challenge(u64),
+    Refused(RefuseReason),
+    Phase(Phase),
+    Experiment(Option<String>),
+    Resource {
+        name: String,
+        data: Vec<u8>,
+    },
+    Tick {
+        tick: u64,
+        packets: Vec<Vec<u8>>,
+    },
+    Batch(Vec<Vec<u8>>),
+    Roster(Vec<PeerId>),
+    Identity(u128),
+    Dedicated,
+    Welcome(Option<u32>),
+    Capabilities {
+        compress: bool,
+    },
+    Expiring {
+        sent: u64,
+        ttl: u64,
+        packet: Vec<u8>,
+    },
+    Unique {
+        channel: Channel,
+        seq: u32,
+        packet: Vec<u8>,
+    },
+    Relay {
+        dest: Option<PeerId>,
+        packet: Vec<u8>,
+    },
+    Bridged {
+        src: PeerId,
+        packet: Vec<u8>,
+    },
+    MeshAddr(String),
+    MeshPeers(Vec<(PeerId, String)>),
+    MeshHello(PeerId),
+    Role {
+        peer: PeerId,
+        role: Role,
+    },
+    Chat {
+        channel: Channel,
+        text: String,
+        sent: u64,
+    },
+    ChatLine {
+        peer: PeerId,
+        channel: Channel,
+        text: String,
+        sent: u64,
+    },
+    SetReady(bool),
+    Settings(Vec<u8>),
+    JoinSnapshot {
+        total: u64,
+        data: Vec<u8>,
+    },
+    Ready {
+        peer: PeerId,
+        ready: bool,
+    },
+    AuthRequest,
+    AuthTicket {
+        steam_id: u64,
+        ticket: Vec<u8>,
+    },
+    Voice {
+        speaker: PeerId,
+        data: Vec<u8>,
+    },
+    StreamOpen(u64),
+    StreamData {
+        id: u64,
+        data: Vec<u8>,
+    },
+    StreamAck {
+        id: u64,
+        consumed: u64,
+    },
+    StreamEnd(u64),
+    StreamReset(u64),
+    ClockPing(u64),
+    ClockPong {
+        sent: u64,
+        time: u64,
+    },
+}
+pub(crate) enum Event {
+    Connected(PeerId),
+    Disconnected(PeerId, DisconnectReason),
+    Message(PeerId, Vec<u8>),
+}
+pub(crate) fn pack<T: Encode>(data: &T, compression: Compression) -> Vec<u8> {
+    frame(Channel::DEFAULT, None, encode(data), compression)
+}
+#[allow(unused_variables)]
+pub(crate) fn frame(
+    channel: Channel,
+    seq: Option<u32>,
+    data: Vec<u8>,
+    compression: Compression,
+) -> Vec<u8> {
+    #[cfg(feature = "compress")]
+    let (compressed, data) = match compression {
+        Compression::Compressed => (true, compress_prepend_size(&data)),
+        Compression::Uncompressed => (false, data),
+    };
+    #[cfg(not(feature = "compress"))]
+    let compressed = false;
+    encode(&MsgType::Data {
+        channel,
+        seq,
+        compressed,
+        data: &data,
+    })
+}
+pub(crate) fn unpack(data: &[u8]) -> Option<MsgType<'_>> {
+    decode(data).ok()
+}
+pub(crate) fn decompress(compressed: bool, data: &[u8], max: usize) -> Result<Vec<u8>, NetError> {
+    if !compressed {
+        return if data.len() > max {
+            Err(NetError::TooLarge(data.len()))
+        } else {
+            Ok(data.to_vec())
+        };
+    }
+    let Some(size) = data.first_chunk().map(|b| u32::from_le_bytes(*b) as usize) else {
+        return Err(NetError::Malformed);
+    };
+    if size > max {
+        return Err(NetError::TooLarge(size));
+    }
+    #[cfg(feature = "compress")]
+    {
+        decompress_size_prepended(data).map_err(|_| NetError::Malformed)
+    }
+    #[cfg(not(feature = "compress"))]
+    {
+        Err(NetError::Unsupported)
+    }
+}
+#[derive(Clone, Copy)]
+pub enum ClientTypeRef<'a> {
+    #[cfg(feature = "steam")]
+    Steam(&'a SteamClient),
+    #[cfg(feature = "tangled")]
+    Ip(&'a IpClient),
+    #[cfg(any(test, feature = "testnet"))]
+    Loopback(&'a Loopback),
+    #[cfg(not(any(feature = "steam", feature = "tangled")))]
+    None(&'a ()),
+}
+#[cfg_attr(feature = "bevy", derive(Resource))]
+pub struct Client {
+    #[cfg(feature = "steam")]
+    steam_client: Option<SteamClient>,
+    #[cfg(feature = "tangled")]
+    ip_client: Option<IpClient>,
+    pending: Vec<Event>,
+    inbox: VecDeque<Message<Bytes>>,
+    sticky: Sticky,
+    fences: Fences,
+    config: NetConfig,
+    #[allow(clippy::type_complexity)]
+    buffered: Mutex<Vec<(Instant, PeerId, Vec<u8>, Reliability)>>,
+    dropped: AtomicUsize,
+    upgrade: Upgrade,
+    metadata: Metadata,
+    net_events: VecDeque<NetEvent>,
+    goodbyes: HashMap<PeerId, DisconnectReason>,
+    reconnect: Option<Reconnect>,
+    liveness: keepalive::Liveness,
+    migration: migration::Migration,
+    seeds: Seeds,
+    sequences: Sequences,
+    unordered: Unordered,
+    clock: Clock,
+    tickets: Tickets,
+    transfers: Transfers,
+    #[cfg(feature = "steam")]
+    steam_online: bool,
+    #[cfg(feature = "steam")]
+    voice: Voice,
+    fallback: Option<SocketAddr>,
+    idle: Idle,
+    streams: Streams,
+    violations: HashMap<PeerId, u32>,
+    batches: batch::Batches,
+    queue: SendQueues,
+    limiter: Limiter,
+    interests: Interests,
+    handshakes: Handshakes,
+    phases: Phases,
+    #[cfg(feature = "tangled")]
+    password: Option<String>,
+    access: Access,
+    offload: Offload,
+    counters: Counters,
+    pool: Pool,
+    experiment: Option<String>,
+    resources: resource::Resources,
+    owners: HashMap<u64, PeerId>,
+    ticker: Ticker,
+    stamp: Option<u64>,
+    pending_join: Option<PendingJoin>,
+    #[cfg(all(feature = "steam", feature = "tangled"))]
+    bridge: Option<bridge::Bridge>,
+    #[cfg(any(test, feature = "testnet"))]
+    loopback: Option<Loopback>,
+    #[cfg(feature = "tangled")]
+    mesh: Option<mesh::Mesh>,
+    remote_peers: HashSet<PeerId>,
+    identities: HashMap<PeerId, u128>,
+    dedicated: bool,
+    joining: progress::Joining,
+    #[cfg(feature = "tangled")]
+    runtime: Option<tokio::runtime::Runtime>,
+    send_errors: Mutex<Vec<NetSendError>>,
+    callbacks: callbacks::Callbacks,
+    uncompressed: HashSet<PeerId>,
+    expiring: ttl::Expiring,
+    priority_drops: Mutex<PriorityDrops>,
+    pacer: congestion::Pacer,
+    dedup: dedup::Dedup,
+    roles: roles::Roles,
+    ready: ready::Ready,
+    settings: Option<Vec<u8>>,
+    late_join: latejoin::LateJoin,
+    quality: quality::Quality,
+    channel_caps: channels::ChannelCaps,
+    #[cfg(feature = "steam")]
+    auth: auth::Auth,
+    #[cfg(feature = "hmac")]
+    rejected: mac::Rejected,
+    seal: seal::Seal,
+    #[cfg(any(feature = "encrypt", feature = "hmac"))]
+    replay: replay::Replay,
+    #[cfg(feature = "chat")]
+    chat: chat::Chat,
+    #[cfg(feature = "master")]
+    master: Option<master::Heartbeat>,
+}
+pub enum ClientMode {
+    Steam,
+    Ip,
+    Loopback,
+    None,
+}
+impl Client {
+    pub fn new(
+        #[cfg(feature = "steam")] app_id: u32,
+        #[cfg(feature = "steam")] peer_connected: ClientCallback,
+        #[cfg(feature = "steam")] peer_disconnected: ClientCallback,
+    ) -> Option<Self> {
+        Some(Self::from_backend(
+            #[cfg(feature = "steam")]
+            Some(SteamClient::new(app_id, peer_connected, peer_disconnected).ok()?),
+        ))
+    }
+    #[cfg(feature = "steam")]
+    pub fn without_steam() -> Self {
+        Self::from_backend(None)
+    }
+    #[cfg(feature = "steam")]
+    pub fn with_steam_client(
+        steamworks: steamworks::Client,
+        peer_connected: ClientCallback,
+        peer_disconnected: ClientCallback,
+    ) -> Self {
+        Self::from_backend(Some(SteamClient::from_client(
+            steamworks,
+            peer_connected,
+            peer_disconnected,
+        )))
+    }
+    fn from_backend(#[cfg(feature = "steam")] steam_client: Option<SteamClient>) -> Self {
+        let mut client = Self {
+            #[cfg(feature = "steam")]
+            steam_client,
+            #[cfg(feature = "tangled")]
+            ip_client: None,
+            pending: Vec::with_capacity(64),
+            inbox: VecDeque::with_capacity(64),
+            sticky: Default::default(),
+            fences: Default::default(),
+            config: Default::default(),
+            buffered: Default::default(),
+            dropped: Default::default(),
+            upgrade: Default::default(),
+            metadata: Default::default(),
+            net_events: VecDeque::new(),
+            goodbyes: HashMap::new(),
+            reconnect: None,
+            liveness: Default::default(),
+            migration: Default::default(),
+            seeds: Default::default(),
+            sequences: Default::default(),
+            unordered: Default::default(),
+            clock: Default::default(),
+            tickets: Default::default(),
+            transfers: Default::default(),
+            #[cfg(feature = "steam")]
+            steam_online: true,
+            #[cfg(feature = "steam")]
+            voice: Default::default(),
+            fallback: None,
+            idle: Default::default(),
+            streams: Default::default(),
+            violations: HashMap::new(),
+            batches: Default::default(),
+            queue: Default::default(),
+            limiter: Default::default(),
+            interests: Default::default(),
+            handshakes: Default::default(),
+            phases: Default::default(),
+            #[cfg(feature = "tangled")]
+            password: None,
+            access: Default::default(),
+            offload: Default::default(),
+            counters: Default::default(),
+            pool: Default::default(),
+            experiment: None,
+            resources: Default::default(),
+            owners: HashMap::new(),
+            ticker: Default::default(),
+            stamp: None,
+            pending_join: None,
+            #[cfg(all(feature = "steam", feature = "tangled"))]
+            bridge: None,
+            #[cfg(any(test, feature = "testnet"))]
+            loopback: None,
+            #[cfg(feature = "tangled")]
+            mesh: None,
+            remote_peers: HashSet::new(),
+            identities: HashMap::new(),
+            dedicated: false,
+            joining: Default::default(),
+            #[cfg(feature = "tangled")]
+            runtime: None,
+            send_errors: Default::default(),
+            callbacks: Default::default(),
+            uncompressed: HashSet::new(),
+            expiring: Default::default(),
+            priority_drops: Default::default(),
+            pacer: Default::default(),
+            dedup: Default::default(),
+            roles: Default::default(),
+            ready: Default::default(),
+            settings: None,
+            late_join: Default::default(),
+            quality: Default::default(),
+            channel_caps: Default::default(),
+            #[cfg(feature = "steam")]
+            auth: Default::default(),
+            #[cfg(feature = "hmac")]
+            rejected: Default::default(),
+            seal: Default::default(),
+            #[cfg(any(feature = "encrypt", feature = "hmac"))]
+            replay: Default::default(),
+            #[cfg(feature = "chat")]
+            chat: Default::default(),
+            #[cfg(feature = "master")]
+            master: None,
+        };
+        if let Some(join) = PendingJoin::from_args() {
+            client.set_pending_join(join)
+        }
+        client
+    }
+    pub fn recv<T, F>(&mut self, mut f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<T>),
+        T: DecodeOwned,
+    {
+        let mut malformed = Vec::new();
+        self.recv_raw(|c, m| match decode(m.data) {
+            Ok(data) => f(
+                c,
+                Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data,
+                },
+            ),
+            Err(_) => malformed.push(m.src),
+        });
+        for peer in malformed {
+            self.malformed(peer)
+        }
+    }
+    pub fn recv_budgeted<T, F>(&mut self, budget: RecvBudget, mut f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<T>),
+        T: DecodeOwned,
+    {
+        let mut malformed = Vec::new();
+        self.recv_raw_budgeted(budget, |c, m| match decode(m.data) {
+            Ok(data) => f(
+                c,
+                Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data,
+                },
+            ),
+            Err(_) => malformed.push(m.src),
+        });
+        for peer in malformed {
+            self.malformed(peer)
+        }
+    }
+    pub fn recv_raw<F>(&mut self, f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<&[u8]>),
+    {
+        self.recv_raw_budgeted(RecvBudget::default(), f)
+    }
+    pub fn recv_iter<T: DecodeOwned>(&mut self) -> RecvIter<'_, T> {
+        self.poll_transport();
+        RecvIter {
+            client: self,
+            _marker: PhantomData,
+        }
+    }
+    pub fn recv_raw_iter(&mut self) -> RawRecvIter<'_> {
+        self.poll_transport();
+        RawRecvIter { client: self }
+    }
+    fn poll_transport(&mut self) {
+        if !self.idle.poll_due(self.config.hibernate) {
+            return;
+        }
+        self.seal.configure(&self.config);
+        let mut events = std::mem::take(&mut self.pending);
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            lo.poll(&mut events);
+        }
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &mut self.ip_client {
+            ip.legacy = self.config.legacy_wire;
+            ip.poll(&mut events);
+        }
+        #[cfg(feature = "steam")]
+        if !self.is_ip()
+            && let Some(steam) = &mut self.steam_client
+        {
+            steam.poll(&mut events);
+        }
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        if let Some(bridge) = &mut self.bridge {
+            bridge.ip.poll(&mut events);
+        }
+        #[cfg(feature = "tangled")]
+        self.poll_mesh(&mut events);
+        self.handle(&mut events);
+        self.pending = events;
+    }
+    fn ack_fences(&mut self) {
+        if self.inbox.is_empty() {
+            for (peer, id) in std::mem::take(&mut self.fences.acks) {
+                let _ =
+                    self.send_packet(peer, encode(&MsgType::FenceAck(id)), Reliability::Reliable);
+            }
+        }
+    }
+    pub fn recv_raw_budgeted<F>(&mut self, budget: RecvBudget, mut f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<&[u8]>),
+    {
+        self.poll_transport();
+        let (mut messages, mut bytes) = (0, 0);
+        while let Some(len) = self.inbox.front().map(|m| m.data.len()) {
+            if budget.messages.is_some_and(|max| messages >= max)
+                || budget
+                    .bytes
+                    .is_some_and(|max| messages > 0 && bytes + len > max)
+            {
+                break;
+            }
+            let m = self.inbox.pop_front().unwrap();
+            let Some(c) = self.client_ref() else {
+                self.inbox.push_front(m);
+                break;
+            };
+            messages += 1;
+            bytes += m.data.len();
+            let data = &m.data;
+            f(
+                c,
+                Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data,
+                },
+            )
+        }
+        self.ack_fences()
+    }
+    pub fn recv_on<T, F>(&mut self, channel: Channel, mut f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<T>),
+        T: DecodeOwned,
+    {
+        let mut malformed = Vec::new();
+        self.recv_raw_on(channel, |c, m| match decode(m.data) {
+            Ok(data) => f(
+                c,
+                Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data,
+                },
+            ),
+            Err(_) => malformed.push(m.src),
+        });
+        for peer in malformed {
+            self.malformed(peer)
+        }
+    }
+    pub fn recv_raw_on<F>(&mut self, channel: Channel, mut f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<&[u8]>),
+    {
+        self.poll_transport();
+        if self.client_ref().is_none() {
+            return;
+        }
+        let (matching, rest) = std::mem::take(&mut self.inbox)
+            .into_iter()
+            .partition::<VecDeque<_>, _>(|m| m.channel == channel);
+        self.inbox = rest;
+        let Some(c) = self.client_ref() else {
+            return;
+        };
+        for m in matching {
+            f(
+                c,
+                Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data: &m.data,
+                },
+            )
+        }
+        self.ack_fences()
+    }
+    pub fn peek_raw<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Message<&[u8]>),
+    {
+        self.poll_transport();
+        for m in &self.inbox {
+            f(Message {
+                src: m.src,
+                channel: m.channel,
+                tick: m.tick,
+                data: &m.data,
+            })
+        }
+    }
+    pub fn queue_depth(&self) -> usize {
+        self.inbox.len()
+    }
+    pub fn queued_bytes(&self) -> usize {
+        self.inbox.iter().map(|m| m.data.len()).sum()
+    }
+    pub fn take_channel(&mut self, channel: Channel) -> Vec<Message<Bytes>> {
+        let (taken, rest): (VecDeque<_>, VecDeque<_>) = std::mem::take(&mut self.inbox)
+            .into_iter()
+            .partition(|m| m.channel == channel);
+        self.inbox = rest;
+        taken.into()
+    }
+    #[allow(clippy::result_unit_err)]
+    pub fn update(&mut self) -> UResult {
+        if !self.idle.poll_due(self.config.hibernate) {
+            return Ok(());
+        }
+        let mut events = std::mem::take(&mut self.pending);
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &mut self.ip_client {
+            ip.legacy = self.config.legacy_wire;
+            ip.update();
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &mut self.steam_client {
+            steam.timeout = self.config.timeout;
+            steam.path = self.config.steam_path;
+            steam.settings = self.config.steam;
+        }
+        #[cfg(feature = "steam")]
+        let result = match &mut self.steam_client {
+            Some(steam) if !self.is_ip() => steam.update(&mut events),
+            _ => Ok(()),
+        };
+        #[cfg(not(feature = "steam"))]
+        let result = Ok(());
+        #[cfg(feature = "steam")]
+        self.steam_status();
+        #[cfg(feature = "steam")]
+        self.lobby_chat();
+        #[cfg(feature = "steam")]
+        self.steam_launch();
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        self.pump_bridge();
+        #[cfg(feature = "master")]
+        self.pump_heartbeat();
+        #[cfg(feature = "tangled")]
+        self.pump_mesh();
+        self.check_join_timeout();
+        self.pump_ready();
+        if let UnknownPeerPolicy::Buffer(grace) = self.config.unknown_peer {
+            let buffered = self.buffered.get_mut().unwrap();
+            let len = buffered.len();
+            buffered.retain(|(t, _, _, _)| t.elapsed() <= grace);
+            self.dropped
+                .fetch_add(len - buffered.len(), Ordering::Relaxed);
+        }
+        self.handle(&mut events);
+        self.pending = events;
+        #[allow(unused_variables)]
+        let (due, expired) = self.unordered.due(self.config.unordered_max_attempts);
+        #[cfg(any(feature = "tangled", feature = "steam"))]
+        for (peer, packet) in due {
+            #[cfg(feature = "tangled")]
+            if let Some(ip) = &self.ip_client {
+                let _ = ip.send_packet(peer, packet, Reliability::Unreliable);
+                continue;
+            }
+            #[cfg(feature = "steam")]
+            if let Some(steam) = &self.steam_client {
+                let _ = steam.send_packet(peer, packet, Reliability::Unreliable);
+            }
+        }
+        for peer in expired {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            let _ = self.report(Some(peer), Err(NetError::Timeout));
+            self.net_events.push_back(NetEvent::DeliveryFailed { peer });
+        }
+        match self.idle.update(self.config.hibernate) {
+            Some(true) => self.net_events.push_back(NetEvent::Hibernating),
+            Some(false) => self.net_events.push_back(NetEvent::Awake),
+            None => {}
+        }
+        if let Some(budget) = self.gate() {
+            for peer in self.handshakes.expired(budget.timeout) {
+                self.refuse(peer, RefuseReason::Timeout)
+            }
+        }
+        self.keepalive();
+        self.sync_clocks();
+        self.adapt_bandwidth();
+        self.pump_quality();
+        #[cfg(feature = "steam")]
+        self.pump_auth();
+        self.pump_files();
+        self.pump_streams();
+        #[cfg(feature = "steam")]
+        self.pump_voice();
+        self.send_batched();
+        self.send_tick();
+        self.reconnect();
+        result
+    }
+    fn handle(&mut self, events: &mut Vec<Event>) {
+        for (src, channel, tick, data) in self.offload.done() {
+            match data {
+                Ok(data) => self.deliver(Message {
+                    src,
+                    channel,
+                    tick,
+                    data: data.into(),
+                }),
+                Err(NetError::TooLarge(size)) => self.violation(src, size),
+                Err(_) => {}
+            }
+        }
+        let start = Instant::now();
+        let mut queue = std::mem::take(events).into_iter();
+        while let Some(event) = queue.next() {
+            if self
+                .config
+                .frame_budget
+                .is_some_and(|b| start.elapsed() >= b)
+            {
+                events.push(event);
+                events.extend(queue);
+                break;
+            }
+            match event {
+                Event::Connected(peer) => {
+                    self.liveness.since.insert(peer, Instant::now());
+                    if !self.is_host() && peer == self.host_id() {
+                        self.join_progress(JoinProgress::HandshakeSent)
+                    }
+                    if self.is_ip_like() && !self.is_host() && peer == self.host_id() {
+                        let packet = handshake::hello(self.config.game_id, None);
+                        let _ = self.dispatch(peer, packet, Reliability::Reliable);
+                    }
+                    if let Some(budget) = self.gate() {
+                        if !self.access.permits(peer) {
+                            self.handshakes.begin(peer, budget);
+                            self.refuse(peer, RefuseReason::Denied)
+                        } else if !self.handshakes.begin(peer, budget) {
+                            self.refuse(peer, RefuseReason::Budget)
+                        } else if self.has_password()
+                            && let Some(nonce) = self.handshakes.nonce(peer)
+                        {
+                            let packet = encode(&MsgType::Challenge(nonce));
+                            let _ = self.dispatch(peer, packet, Reliability::Reliable);
+                        }
+                        continue;
+                    }
+                    self.accept(peer)
+                }
+                Event::Disconnected(peer, reason) => {
+                    #[cfg(feature = "hmac")]
+                    self.rejected.remove(peer);
+                    if !self.liveness.timed_out.remove(&peer) && !self.handshakes.remove(peer) {
+                        self.disconnected(peer, reason)
+                    }
+                }
+                Event::Message(src, data) => {
+                    if self.liveness.timed_out.contains(&src) {
+                        continue;
+                    }
+                    if data.len() > self.config.max_message_size {
+                        self.violation(src, data.len());
+                        continue;
+                    }
+                    #[cfg(feature = "hmac")]
+                    let data = match self
+                        .config
+                        .hmac_key
+                        .as_deref()
+                        .filter(|_| self.is_ip_like() || self.is_bridged(src))
+                    {
+                        Some(key) => match mac::verify(key, data) {
+                            Some(data) => data,
+                            None => {
+                                self.rejected.record(src);
+                                self.dropped.fetch_add(1, Ordering::Relaxed);
+                                continue;
+                            }
+                        },
+                        None => data,
+                    };
+                    #[cfg(feature = "encrypt")]
+                    let data = match self
+                        .config
+                        .psk
+                        .filter(|_| self.is_ip_like() || self.is_bridged(src))
+                    {
+                        Some(key) => match crypto::open(&key, &data) {
+                            Ok(data) => data,
+                            Err(_) => {
+                                self.net_events
+                                    .push_back(NetEvent::CryptoFailed { peer: src });
+                                if self.is_host() {
+                                    let _ = self.kick(src, "encryption mismatch");
+                                }
+                                continue;
+                            }
+                        },
+                        None => data,
+                    };
+                    #[cfg(any(feature = "encrypt", feature = "hmac"))]
+                    let data =
+                        if self.replay_protected() && (self.is_ip_like() || self.is_bridged(src)) {
+                            match self.check_replay(src, data) {
+                                Some(data) => data,
+                                None => continue,
+                            }
+                        } else {
+                            data
+                        };
+                    if self.handshakes.contains(src) {
+                        if !handshake::is_hello(&data) {
+                            self.handshakes.hold(src, data);
+                            continue;
+                        }
+                        let Some(hello) = handshake::strip_hello(&data, self.config.game_id) else {
+                            self.refuse(src, RefuseReason::WrongGame);
+                            continue;
+                        };
+                        if let Some(MsgType::Hello(proof)) = unpack(hello) {
+                            self.verify(src, proof)
+                        }
+                        continue;
+                    }
+                    if let Some(limit) = self.limiter.limit(src, self.config.rate_limit)
+                        && let Some(first) = self.limiter.allow(src, data.len(), limit)
+                    {
+                        if first && limit.policy != ThrottlePolicy::Drop {
+                            self.net_events
+                                .push_back(NetEvent::PeerThrottled { peer: src });
+                            if limit.policy == ThrottlePolicy::Kick && self.is_host() {
+                                let _ = self.kick(src, "rate limit exceeded");
+                            }
+                        }
+                        continue;
+                    }
+                    self.counters.received(data.len());
+                    self.liveness.last_seen.insert(src, Instant::now());
+                    self.handle_packet(src, data.into())
+                }
+            }
+        }
+    }
+    fn gate(&self) -> Option<ConnectionBudget> {
+        let budget = self.config.connection_budget.or_else(|| {
+            (self.has_password() || !self.access.is_empty() || self.config.game_id.is_some()).then(
+                || ConnectionBudget {
+                    max_pending: usize::MAX,
+                    max_attempts_per_minute: usize::MAX,
+                    ..Default::default()
+                },
+            )
+        })?;
+        (self.is_ip_like() && self.is_host()).then_some(budget)
+    }
+    fn has_password(&self) -> bool {
+        #[cfg(feature = "tangled")]
+        {
+            self.password.is_some()
+        }
+        #[cfg(not(feature = "tangled"))]
+        {
+            false
+        }
+    }
+    #[allow(unused_variables)]
+    fn verify(&mut self, peer: PeerId, proof: Option<[u8; 32]>) {
+        #[cfg(feature = "tangled")]
+        if let Some(password) = &self.password {
+            let Some(nonce) = self.handshakes.nonce(peer) else {
+                return;
+            };
+            match proof {
+                None => return,
+                Some(proof) if proof == ip::proof(password, nonce) => {}
+                Some(_) => {
+                    self.refuse(peer, RefuseReason::WrongPassword);
+                    return;
+                }
+            }
+        }
+        if self.handshakes.complete(peer) {
+            self.accept(peer);
+            for packet in self.handshakes.release(peer) {
+                self.handle_packet(peer, packet.into())
+            }
+        }
+    }
+    fn refuse(&mut self, peer: PeerId, reason: RefuseReason) {
+        self.handshakes.refuse(peer);
+        let _ = self.dispatch(
+            peer,
+            encode(&MsgType::Refused(reason)),
+            Reliability::Reliable,
+        );
+        self.flush();
+        self.net_events
+            .push_back(NetEvent::ConnectionRefused { peer, reason });
+    }
+    pub fn connection_stats(&self) -> ConnectionStats {
+        self.handshakes.stats()
+    }
+    fn accept(&mut self, peer: PeerId) {
+        let buffered: Vec<_> = self
+            .buffered
+            .get_mut()
+            .unwrap()
+            .extract_if(.., |(_, p, _, _)| *p == peer)
+            .collect();
+        for (_, _, packet, reliability) in buffered {
+            let _ = self.send_packet(peer, packet, reliability);
+        }
+        if !self.metadata.local.is_empty() {
+            let packet = encode(&MsgType::Metadata(self.metadata.sync()));
+            let _ = self.send_packet(peer, packet, Reliability::Reliable);
+        }
+        let packet = encode(&MsgType::Capabilities {
+            compress: cfg!(feature = "compress"),
+        });
+        let _ = self.send_packet(peer, packet, Reliability::Reliable);
+        if let Some(identity) = self.config.identity {
+            let packet = encode(&MsgType::Identity(identity));
+            let _ = self.send_packet(peer, packet, Reliability::Reliable);
+        }
+        if self.is_host() {
+            let packet = encode(&MsgType::Welcome(self.config.version));
+            let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            if self.config.dedicated {
+                let _ = self.send_packet(peer, encode(&MsgType::Dedicated), Reliability::Reliable);
+            }
+            #[cfg(feature = "steam")]
+            if self.config.require_steam_auth {
+                let _ = self.request_auth(peer);
+            }
+            self.sync_settings(peer);
+            if let Some(addr) = self.config.steam_fallback.filter(|_| !self.is_ip()) {
+                let packet = encode(&MsgType::Fallback(addr.to_string()));
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            if self.experiment.is_some() {
+                let packet = encode(&MsgType::Experiment(self.experiment.clone()));
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            if self.phases.current != Phase::default() {
+                let packet = encode(&MsgType::Phase(self.phases.current));
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            self.sync_roles(peer);
+            self.sync_ready(peer);
+            for (id, owner) in &self.owners {
+                let packet = encode(&MsgType::Ownership {
+                    id: *id,
+                    owner: Some(*owner),
+                });
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            for (name, data) in &self.resources.data {
+                let packet = encode(&MsgType::Resource {
+                    name: name.clone(),
+                    data: data.clone(),
+                });
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            if self.migration.cohost.is_some() {
+                let packet = encode(&MsgType::CoHost(self.migration.cohost));
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            if let Some((id, seed)) = self.seeds.current {
+                self.seeds.pending.insert(peer);
+                let packet = encode(&MsgType::Seed { id, seed });
+                let _ = self.send_packet(peer, packet, Reliability::Reliable);
+            }
+            self.supply_snapshot(peer);
+            for (packet, reliability) in self.sticky.history() {
+                let _ = self.send_packet(peer, packet, reliability);
+            }
+        }
+        if self.config.clock_sync.is_some() {
+            let packet = encode(&MsgType::ClockPing(self.clock.now()));
+            let _ = self.dispatch(peer, packet, Reliability::Unreliable);
+        }
+        self.liveness.last_seen.insert(peer, Instant::now());
+        self.net_events.push_back(NetEvent::PeerConnected(peer));
+        self.peer_connected(peer);
+        self.run_callbacks(peer, true)
+    }
+    fn disconnected(&mut self, peer: PeerId, reason: DisconnectReason) {
+        let reason = self.goodbyes.remove(&peer).unwrap_or(reason);
+        let lost_host = peer == self.host_id()
+            && !self.is_host()
+            && !matches!(reason, DisconnectReason::Kicked(_));
+        if lost_host
+            && !self.migrate(peer)
+            && self.config.reconnect.is_some()
+            && self.reconnect.is_none()
+            && reason != DisconnectReason::Quit
+        {
+            self.reconnect = Some(Reconnect {
+                attempt: 0,
+                next: Instant::now(),
+                old_id: self.my_id(),
+                host: peer,
+            });
+        }
+        if peer == self.host_id() && !self.is_host() {
+            self.dedicated = false;
+            self.join_progress(JoinProgress::Failed(JoinFailure::Disconnected(
+                reason.clone(),
+            )));
+        }
+        self.fences.fail(peer);
+        self.tickets.fail(peer);
+        self.upgrade.remove(peer);
+        self.metadata.peers.remove(&peer);
+        self.liveness.last_seen.remove(&peer);
+        self.sequences.remove(peer);
+        self.unordered.remove(peer);
+        self.clock.remove(peer);
+        self.liveness.since.remove(&peer);
+        self.streams.remove(peer);
+        self.violations.remove(&peer);
+        self.limiter.remove(peer);
+        self.pacer.remove(peer);
+        self.dedup.remove(peer);
+        self.channel_caps.remove(peer);
+        #[cfg(feature = "steam")]
+        self.end_auth(peer);
+        #[cfg(any(feature = "encrypt", feature = "hmac"))]
+        self.replay.remove(peer);
+        self.roles.peers.remove(&peer);
+        self.ready.peers.remove(&peer);
+        self.quality.peers.remove(&peer);
+        #[cfg(feature = "tangled")]
+        if let Some(mesh) = &mut self.mesh {
+            mesh.remove(peer)
+        }
+        self.remote_peers.remove(&peer);
+        self.identities.remove(&peer);
+        self.uncompressed.remove(&peer);
+        if self.is_host() {
+            let owned: Vec<u64> = self
+                .owners
+                .iter()
+                .filter(|(_, o)| **o == peer)
+                .map(|(id, _)| *id)
+                .collect();
+            for id in owned {
+                let _ = self.set_owner(id, None);
+            }
+        }
+        self.interests.remove(peer);
+        self.ticker.remove(peer);
+        self.queue.remove(peer);
+        self.batches.remove(peer);
+        self.handshakes.remove(peer);
+        for id in self.transfers.remove(peer) {
+            self.net_events.push_back(NetEvent::FileFailed { peer, id })
+        }
+        if self.seeds.pending.remove(&peer)
+            && self.seeds.pending.is_empty()
+            && let Some((_, seed)) = self.seeds.current
+        {
+            self.net_events
+                .push_back(NetEvent::SeedAcknowledged { seed });
+        }
+        self.net_events
+            .push_back(NetEvent::PeerDisconnected { peer, reason });
+        self.peer_disconnected(peer);
+        self.run_callbacks(peer, false)
+    }
+    fn end_session(&mut self, reason: DisconnectReason) {
+        for peer in self.connected_peers() {
+            self.disconnected(peer, reason.clone())
+        }
+        self.goodbyes.clear();
+        self.reset_late_join();
+        #[cfg(feature = "steam")]
+        self.cancel_auth_tickets();
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = self.loopback.take() {
+            lo.leave();
+            return;
+        }
+        #[cfg(feature = "tangled")]
+        {
+            self.mesh = None;
+        }
+        #[cfg(feature = "tangled")]
+        if self.ip_client.take().is_some() {
+            return;
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &mut self.steam_client {
+            steam.leave()
+        }
+    }
+    pub fn kick(&mut self, peer: PeerId, reason: &str) -> Result<(), NetError> {
+        let reason = DisconnectReason::Kicked(reason.to_owned());
+        let packet = encode(&MsgType::Goodbye(reason.clone()));
+        self.send_packet(peer, packet, Reliability::Reliable)?;
+        self.flush();
+        self.goodbyes.insert(peer, reason.clone());
+        #[cfg(feature = "steam")]
+        if !self.is_ip()
+            && let Some(steam) = &mut self.steam_client
+            && steam.connections.remove(&peer).is_some()
+        {
+            self.disconnected(peer, reason)
+        }
+        Ok(())
+    }
+    pub fn leave(&mut self) {
+        let packet = encode(&MsgType::Goodbye(DisconnectReason::Quit));
+        let _ = self.broadcast_packet(packet, Reliability::Reliable);
+        self.flush();
+        self.end_session(DisconnectReason::Quit)
+    }
+    fn violation(&mut self, peer: PeerId, size: usize) {
+        self.net_events
+            .push_back(NetEvent::LimitExceeded { peer, size });
+        self.strike(peer, "message size limit exceeded")
+    }
+    fn malformed(&mut self, peer: PeerId) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.net_events
+            .push_back(NetEvent::MalformedMessage { peer });
+        self.strike(peer, "malformed message")
+    }
+    fn strike(&mut self, peer: PeerId, reason: &str) {
+        let count = self.violations.entry(peer).or_default();
+        *count += 1;
+        let count = *count;
+        if self.is_host() && self.config.max_violations.is_some_and(|m| count >= m) {
+            let _ = self.kick(peer, reason);
+        }
+    }
+    fn handle_packet(&mut self, src: PeerId, packet: Bytes) {
+        match unpack(&packet) {
+            Some(MsgType::Data {
+                channel,
+                seq,
+                compressed,
+                data,
+            }) => {
+                if !self.phases.receives(channel) {
+                    return;
+                }
+                if !self.may_receive(src, channel) {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                self.channel_received(channel, data.len());
+                if let Some(seq) = seq
+                    && !self.sequences.accept(src, channel, seq)
+                {
+                    return;
+                }
+                self.idle.touch();
+                if compressed
+                    && self
+                        .config
+                        .offload_threshold
+                        .is_some_and(|t| data.len() >= t)
+                {
+                    let max = self.config.max_decompressed_size;
+                    self.offload
+                        .submit(src, channel, self.stamp, data.to_vec(), max);
+                    return;
+                }
+                let data = if compressed {
+                    decompress(compressed, data, self.config.max_decompressed_size).map(Bytes::from)
+                } else if data.len() > self.config.max_decompressed_size {
+                    Err(NetError::TooLarge(data.len()))
+                } else {
+                    Ok(packet.slice_ref(data))
+                };
+                match data {
+                    Ok(data) => self.deliver(Message {
+                        src,
+                        channel,
+                        tick: self.stamp,
+                        data,
+                    }),
+                    Err(NetError::TooLarge(size)) => self.violation(src, size),
+                    Err(NetError::Unsupported) => self
+                        .net_events
+                        .push_back(NetEvent::CompressionUnsupported { peer: src }),
+                    Err(_) => {}
+                }
+            }
+            Some(MsgType::Fence(id)) => self.fences.acks.push((src, id)),
+            Some(MsgType::FenceAck(id)) => self.fences.complete(src, id),
+            Some(MsgType::Upgradable { seq, packet }) => {
+                let (deliver, missing) =
+                    self.upgrade
+                        .receive(src, seq, self.config.best_effort_nack_after);
+                if !missing.is_empty() {
+                    let nack = encode(&MsgType::Nack(missing));
+                    let _ = self.send_packet(src, nack, Reliability::Reliable);
+                }
+                if deliver {
+                    self.handle_packet(src, packet.into())
+                }
+            }
+            Some(MsgType::Unordered { id, packet }) => {
+                let ack = encode(&MsgType::UnorderedAck(id));
+                let _ = self.send_packet(src, ack, Reliability::Unreliable);
+                if self.unordered.receive(src, id) {
+                    self.handle_packet(src, packet.into())
+                }
+            }
+            Some(MsgType::UnorderedAck(id)) => self.unordered.ack(src, id),
+            Some(MsgType::Tracked { id, packet }) => {
+                self.handle_packet(src, packet.into());
+                let ack = encode(&MsgType::TrackedAck(id));
+                let _ = self.send_packet(src, ack, Reliability::Reliable);
+            }
+            Some(MsgType::TrackedAck(id)) => self.tickets.complete(src, id),
+            Some(MsgType::FileOffer { id, size }) => {
+                let packet = if self
+                    .transfers
+                    .incoming(src, id, size, self.config.max_file_size)
+                {
+                    MsgType::FileAccept(id)
+                } else {
+                    MsgType::FileReject(id)
+                };
+                let _ = self.send_packet(src, encode(&packet), Reliability::Reliable);
+            }
+            Some(MsgType::FileAccept(id)) => self.transfers.accept(src, id),
+            Some(MsgType::FileReject(id)) => {
+                if self.transfers.cancel(src, id) {
+                    self.net_events
+                        .push_back(NetEvent::FileRejected { peer: src, id })
+                }
+            }
+            Some(MsgType::FileChunk { id, offset, data }) => {
+                match self.transfers.chunk(src, id, offset, data) {
+                    Some(Ok((done, total))) => {
+                        let packet = encode(&MsgType::FileAck { id, received: done });
+                        let _ = self.send_packet(src, packet, Reliability::Reliable);
+                        self.net_events.push_back(NetEvent::FileProgress {
+                            peer: src,
+                            id,
+                            done,
+                            total,
+                        });
+                        if done == total {
+                            self.net_events
+                                .push_back(NetEvent::FileReceived { peer: src, id })
+                        }
+                    }
+                    Some(Err(())) => {
+                        let packet = encode(&MsgType::FileCancel(id));
+                        let _ = self.send_packet(src, packet, Reliability::Reliable);
+                        self.net_events
+                            .push_back(NetEvent::FileFailed { peer: src, id })
+                    }
+                    None => {}
+                }
+            }
+            Some(MsgType::FileAck { id, received }) => {
+                if let Some((done, total)) = self.transfers.ack(src, id, received) {
+                    self.net_events.push_back(NetEvent::FileProgress {
+                        peer: src,
+                        id,
+                        done,
+                        total,
+                    });
+                    if done == total {
+                        self.net_events
+                            .push_back(NetEvent::FileSent { peer: src, id })
+                    }
+                }
+            }
+            Some(MsgType::FileCancel(id)) => {
+                if self.transfers.cancel(src, id) {
+                    self.net_events
+                        .push_back(NetEvent::FileFailed { peer: src, id })
+                }
+            }
+            Some(MsgType::Nack(seqs)) => {
+                for packet in self.upgrade.resend(src, &seqs) {
+                    let _ = self.send_packet(src, packet, Reliability::Reliable);
+                }
+            }
+            Some(MsgType::Goodbye(reason)) => {
+                if matches!(reason, DisconnectReason::Kicked(_)) && src == self.host_id() {
+                    self.end_session(reason)
+                } else {
+                    self.goodbyes.insert(src, reason);
+                }
+            }
+            Some(MsgType::Rejoin(old)) => {
+                if self.is_host() {
+                    self.net_events
+                        .push_back(NetEvent::PeerRejoined { old, new: src })
+                }
+            }
+            Some(MsgType::KeepAlive) => {}
+            Some(MsgType::StreamOpen(id)) => {
+                self.streams.opened(src, id);
+                self.net_events
+                    .push_back(NetEvent::StreamOpened { peer: src, id })
+            }
+            Some(MsgType::StreamData { id, data }) => self.streams.data(src, id, &data),
+            Some(MsgType::StreamAck { id, consumed }) => self.streams.ack(src, id, consumed),
+            Some(MsgType::StreamEnd(id)) => self.streams.end(src, id),
+            Some(MsgType::StreamReset(id)) => self.streams.reset(src, id),
+            Some(MsgType::Hello(_)) => {}
+            #[allow(unused_variables)]
+            Some(MsgType::Challenge(nonce)) => {
+                if src == self.host_id() {
+                    self.join_progress(JoinProgress::Authenticating)
+                }
+                #[cfg(feature = "tangled")]
+                if src == self.host_id()
+                    && let Some(password) = &self.password
+                {
+                    let packet =
+                        handshake::hello(self.config.game_id, Some(ip::proof(password, nonce)));
+                    let _ = self.dispatch(src, packet, Reliability::Reliable);
+                }
+            }
+            Some(MsgType::Tick { tick, packets }) => {
+                self.stamp = Some(tick);
+                for packet in packets {
+                    self.handle_packet(src, packet.into())
+                }
+                self.stamp = None;
+            }
+            #[cfg(feature = "steam")]
+            Some(MsgType::Voice { speaker, data }) => self.receive_voice(src, speaker, data),
+            #[cfg(not(feature = "steam"))]
+            Some(MsgType::Voice { .. }) => {}
+            Some(MsgType::Expiring { sent, ttl, packet }) => {
+                if !self.expired(src, sent, ttl) {
+                    self.handle_packet(src, packet.into())
+                }
+            }
+            Some(MsgType::Unique {
+                channel,
+                seq,
+                packet,
+            }) => {
+                if self.unique(src, channel, seq) {
+                    self.handle_packet(src, packet.into())
+                }
+            }
+            Some(MsgType::Capabilities { compress }) => {
+                if compress {
+                    self.uncompressed.remove(&src);
+                } else {
+                    self.uncompressed.insert(src);
+                }
+            }
+            Some(MsgType::Welcome(version)) => {
+                if src == self.host_id() {
+                    self.welcome(version)
+                }
+            }
+            Some(MsgType::Dedicated) => {
+                if src == self.host_id() {
+                    self.dedicated = true
+                }
+            }
+            Some(MsgType::Identity(identity)) => self.receive_identity(src, identity),
+            Some(MsgType::Roster(peers)) => self.receive_roster(src, peers),
+            Some(MsgType::Relay { dest, packet }) => self.receive_relay(src, dest, packet),
+            #[cfg(feature = "tangled")]
+            Some(MsgType::MeshAddr(addr)) => self.receive_mesh_addr(src, addr),
+            #[cfg(feature = "tangled")]
+            Some(MsgType::MeshPeers(peers)) => self.receive_mesh_peers(src, peers),
+            #[cfg(not(feature = "tangled"))]
+            Some(MsgType::MeshAddr(_) | MsgType::MeshPeers(_)) => {}
+            Some(MsgType::MeshHello(_)) => {}
+            Some(MsgType::Role { peer, role }) => self.receive_role(src, peer, role),
+            Some(MsgType::SetReady(ready)) => self.receive_set_ready(src, ready),
+            Some(MsgType::Settings(data)) => self.receive_settings(src, data),
+            Some(MsgType::JoinSnapshot { total, data }) => self.receive_snapshot(src, total, data),
+            Some(MsgType::Ready { peer, ready }) => self.receive_ready(src, peer, ready),
+            #[cfg(feature = "steam")]
+            Some(MsgType::AuthRequest) => self.receive_auth_request(src),
+            #[cfg(feature = "steam")]
+            Some(MsgType::AuthTicket { steam_id, ticket }) => {
+                self.receive_auth_ticket(src, steam_id, ticket)
+            }
+            #[cfg(not(feature = "steam"))]
+            Some(MsgType::AuthRequest | MsgType::AuthTicket { .. }) => {}
+            #[cfg(feature = "chat")]
+            Some(MsgType::Chat {
+                channel,
+                text,
+                sent,
+            }) => self.receive_chat(src, channel, text, sent),
+            #[cfg(feature = "chat")]
+            Some(MsgType::ChatLine {
+                peer,
+                channel,
+                text,
+                sent,
+            }) => self.receive_chat_line(
+                src,
+                ChatLine {
+                    peer,
+                    channel,
+                    text,
+                    sent,
+                },
+            ),
+            #[cfg(not(feature = "chat"))]
+            Some(MsgType::Chat { .. } | MsgType::ChatLine { .. }) => {}
+            Some(MsgType::Bridged {
+                src: origin,
+                packet,
+            }) => {
+                if src == self.host_id() && !self.is_host() {
+                    self.handle_packet(origin, packet.into())
+                }
+            }
+            Some(MsgType::Batch(packets)) => {
+                for packet in packets {
+                    self.handle_packet(src, packet.into())
+                }
+            }
+            Some(MsgType::Experiment(tag)) => {
+                if src == self.host_id() && self.experiment != tag {
+                    self.experiment = tag.clone();
+                    self.net_events.push_back(NetEvent::ExperimentChanged(tag))
+                }
+            }
+            Some(MsgType::Resource { name, data }) => {
+                if src == self.host_id() {
+                    self.resources.data.insert(name.clone(), data);
+                    self.resources.updated.insert(name.clone());
+                    self.net_events
+                        .push_back(NetEvent::ResourceReplicated(name))
+                }
+            }
+            Some(MsgType::Phase(phase)) => {
+                if src == self.host_id() && self.phases.set(phase) {
+                    self.net_events.push_back(NetEvent::PhaseChanged(phase))
+                }
+            }
+            Some(MsgType::Refused(reason)) => {
+                if src == self.host_id() {
+                    self.net_events.push_back(NetEvent::JoinRejected(reason));
+                    self.join_progress(JoinProgress::Failed(JoinFailure::Refused(reason)));
+                    self.end_session(DisconnectReason::Kicked(format!("{reason:?}")))
+                }
+            }
+            Some(MsgType::Fallback(addr)) => {
+                if src == self.host_id() {
+                    self.fallback = addr.parse().ok()
+                }
+            }
+            Some(MsgType::ClockPing(sent)) => {
+                let time = self.clock.now();
+                let packet = encode(&MsgType::ClockPong { sent, time });
+                let _ = self.dispatch(src, packet, Reliability::Unreliable);
+            }
+            Some(MsgType::ClockPong { sent, time }) => {
+                if self
+                    .clock
+                    .sample(src, sent, time, self.config.drift_threshold)
+                {
+                    self.net_events
+                        .push_back(NetEvent::ClockResynced { peer: src })
+                }
+            }
+            Some(MsgType::Seed { id, seed }) => {
+                if src == self.host_id() {
+                    self.seeds.current = Some((id, seed));
+                    let packet = encode(&MsgType::SeedAck(id));
+                    let _ = self.send_packet(src, packet, Reliability::Reliable);
+                    self.net_events.push_back(NetEvent::SeedReceived { seed });
+                }
+            }
+            Some(MsgType::SeedAck(id)) => {
+                if let Some(seed) = self.seeds.ack(src, id) {
+                    self.net_events
+                        .push_back(NetEvent::SeedAcknowledged { seed });
+                }
+            }
+            Some(MsgType::Ownership { id, owner }) => {
+                if self.is_host() {
+                    if self.owners.get(&id) == Some(&src) {
+                        let _ = self.set_owner(id, owner);
+                    } else {
+                        self.net_events
+                            .push_back(NetEvent::OwnershipViolation { peer: src, id })
+                    }
+                } else if src == self.host_id() {
+                    self.apply_owner(id, owner)
+                }
+            }
+            Some(MsgType::CoHost(cohost)) => {
+                if src == self.host_id() {
+                    if cohost != Some(self.my_id()) {
+                        self.migration.standby = None;
+                    }
+                    self.migration.cohost = cohost
+                }
+            }
+            Some(MsgType::Standby(state)) => {
+                if src == self.host_id() && self.migration.cohost == Some(self.my_id()) {
+                    self.migration.standby = Some(state)
+                }
+            }
+            Some(MsgType::Metadata(entries)) => {
+                for key in self.metadata.apply(src, entries) {
+                    self.net_events
+                        .push_back(NetEvent::MetadataChanged { peer: src, key })
+                }
+            }
+            None => {}
+        }
+    }
+    fn peer_connected(&mut self, peer: PeerId) {
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &mut self.ip_client {
+            if let Some(mut c) = ip.peer_connected.take() {
+                c(ClientTypeRef::Ip(ip), peer);
+                ip.peer_connected = Some(c);
+            }
+            return;
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &mut self.steam_client
+            && let Some(mut c) = steam.peer_connected.take()
+        {
+            c(ClientTypeRef::Steam(steam), peer);
+            steam.peer_connected = Some(c);
+        }
+    }
+    fn peer_disconnected(&mut self, peer: PeerId) {
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &mut self.ip_client {
+            if let Some(mut d) = ip.peer_disconnected.take() {
+                d(ClientTypeRef::Ip(ip), peer);
+                ip.peer_disconnected = Some(d);
+            }
+            return;
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &mut self.steam_client
+            && let Some(mut d) = steam.peer_disconnected.take()
+        {
+            d(ClientTypeRef::Steam(steam), peer);
+            steam.peer_disconnected = Some(d);
+        }
+    }
+    fn client_ref(&self) -> Option<ClientTypeRef<'_>> {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return Some(ClientTypeRef::Loopback(lo));
+        }
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &self.ip_client {
+            return Some(ClientTypeRef::Ip(ip));
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &self.steam_client {
+            return Some(ClientTypeRef::Steam(steam));
+        }
+        #[cfg(any(feature = "steam", feature = "tangled"))]
+        {
+            None
+        }
+        #[cfg(not(any(feature = "steam", feature = "tangled")))]
+        {
+            Some(ClientTypeRef::None(&()))
+        }
+    }
+    fn is_ip(&self) -> bool {
+        #[cfg(feature = "tangled")]
+        {
+            self.ip_client.is_some()
+        }
+        #[cfg(not(feature = "tangled"))]
+        {
+            false
+        }
+    }
+    fn is_ip_like(&self) -> bool {
+        #[cfg(any(test, feature = "testnet"))]
+        if self.loopback.is_some() {
+            return true;
+        }
+        self.is_ip()
+    }
+    pub fn events(&mut self) -> impl Iterator<Item = NetEvent> + '_ {
+        self.net_events.drain(..)
+    }
+    pub fn config(&self) -> &NetConfig {
+        &self.config
+    }
+    pub fn config_mut(&mut self) -> &mut NetConfig {
+        &mut self.config
+    }
+    pub fn set_power_profile(&mut self, profile: PowerProfile) {
+        profile.apply(&mut self.config);
+        if !self.config.batch_sends {
+            self.send_batched()
+        }
+    }
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+    pub fn stats(&self) -> NetStats {
+        NetStats {
+            experiment: self.experiment.clone(),
+            dropped: self.dropped() as u64,
+            connections: self.handshakes.stats(),
+            ..self.counters.snapshot()
+        }
+    }
+    pub fn set_experiment(&mut self, tag: Option<&str>) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Ok(());
+        }
+        self.experiment = tag.map(str::to_owned);
+        let packet = encode(&MsgType::Experiment(self.experiment.clone()));
+        self.broadcast_packet(packet, Reliability::Reliable)?;
+        self.net_events
+            .push_back(NetEvent::ExperimentChanged(self.experiment.clone()));
+        Ok(())
+    }
+    pub fn experiment(&self) -> Option<&str> {
+        self.experiment.as_deref()
+    }
+    pub fn peers(&self) -> Vec<PeerId> {
+        let mut peers = self.connected_peers();
+        if self.dedicated && !self.is_host() {
+            let host = self.host_id();
+            peers.retain(|p| *p != host)
+        }
+        peers
+    }
+    pub fn is_dedicated(&self) -> bool {
+        if self.is_host() {
+            self.config.dedicated
+        } else {
+            self.dedicated
+        }
+    }
+    fn broadcast_data(&self, packet: Vec<u8>, reliability: Reliability) -> Result<(), NetError> {
+        if !self.dedicated || self.is_host() {
+            return self.broadcast_packet(packet, reliability);
+        }
+        for peer in self.peers() {
+            self.send_packet(peer, packet.clone(), reliability)?;
+        }
+        Ok(())
+    }
+    pub(crate) fn connected_peers(&self) -> Vec<PeerId> {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            let mut peers = lo.peers();
+            peers.retain(|p| !self.handshakes.contains(*p));
+            return peers;
+        }
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &self.ip_client {
+            let mut peers = ip.peers();
+            peers.extend(self.bridged_peers());
+            peers.retain(|p| !self.handshakes.contains(*p));
+            return peers;
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &self.steam_client {
+            let mut peers = steam.peers();
+            peers.extend(self.bridged_peers());
+            return peers;
+        }
+        Vec::new()
+    }
+    pub fn connection(&mut self, peer: PeerId) -> Option<PeerConnection<'_>> {
+        self.has_peer(peer)
+            .then_some(PeerConnection { client: self, peer })
+    }
+    pub fn has_peer(&self, peer: PeerId) -> bool {
+        if self.is_bridged(peer) {
+            return !self.handshakes.contains(peer);
+        }
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.has_peer(peer) && !self.handshakes.contains(peer);
+        }
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &self.ip_client {
+            return ip.has_peer(peer) && !self.handshakes.contains(peer);
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &self.steam_client {
+            return steam.has_peer(peer);
+        }
+        false
+    }
+    fn unknown_peer(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        match self.config.unknown_peer {
+            UnknownPeerPolicy::Error => Err(NetError::UnknownPeer(dest)),
+            UnknownPeerPolicy::Drop => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            UnknownPeerPolicy::Buffer(_) => {
+                self.buffered
+                    .lock()
+                    .unwrap()
+                    .push((Instant::now(), dest, packet, reliability));
+                Ok(())
+            }
+        }
+    }
+    pub fn flush(&self) {
+        self.send_batched();
+        #[cfg(feature = "steam")]
+        if !self.is_ip()
+            && let Some(steam) = &self.steam_client
+        {
+            steam.flush()
+        }
+    }
+    pub fn fence(&mut self, peer: PeerId) -> Fence {
+        let (id, fence) = self.fences.create(peer);
+        let packet = encode(&MsgType::Fence(id));
+        if !self.has_peer(peer)
+            || self
+                .send_packet(peer, packet, Reliability::Reliable)
+                .is_err()
+        {
+            self.fences.fail(peer)
+        }
+        self.flush();
+        fence
+    }
+    pub fn set_sticky(&self, channel: Channel, keep: usize, ttl: Option<Duration>) {
+        self.sticky.set(channel, keep, ttl)
+    }
+    pub fn send_on<T: Encode>(
+        &self,
+        channel: Channel,
+        dest: PeerId,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(channel, Some(dest), data, reliability, compression)?;
+        self.send_packet(dest, packet, reliability)
+    }
+    pub fn send_tracked<T: Encode>(
+        &mut self,
+        channel: Channel,
+        dest: PeerId,
+        data: &T,
+        compression: Compression,
+    ) -> SendTicket {
+        let (id, ticket) = self.tickets.create(dest);
+        let packet = self
+            .pack_for(
+                channel,
+                Some(dest),
+                data,
+                Reliability::Reliable,
+                compression,
+            )
+            .map(|packet| encode(&MsgType::Tracked { id, packet }));
+        if !self.has_peer(dest)
+            || packet
+                .and_then(|packet| self.send_packet(dest, packet, Reliability::Reliable))
+                .is_err()
+        {
+            self.tickets.fail(dest)
+        }
+        ticket
+    }
+    pub fn broadcast_on<T: Encode>(
+        &self,
+        channel: Channel,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(channel, None, data, reliability, compression)?;
+        if self.is_host() {
+            self.sticky.retain(channel, &packet, reliability);
+        }
+        self.broadcast_data(packet, reliability)
+    }
+    pub(crate) fn pack_for<T: Encode>(
+        &self,
+        channel: Channel,
+        dest: Option<PeerId>,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<Vec<u8>, NetError> {
+        self.phases.allow(channel)?;
+        self.may_send(channel)?;
+        let seq = self.sequence(channel, reliability);
+        let compression = self.compression_for(dest, compression);
+        let packet = self.pool.frame(channel, seq, data, compression);
+        Ok(self.stamp_unique(channel, packet, reliability))
+    }
+    fn pack_raw(
+        &self,
+        channel: Channel,
+        dest: Option<PeerId>,
+        data: Vec<u8>,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<Vec<u8>, NetError> {
+        self.phases.allow(channel)?;
+        self.may_send(channel)?;
+        let seq = self.sequence(channel, reliability);
+        let compression = self.compression_for(dest, compression);
+        let packet = frame(channel, seq, data, compression);
+        Ok(self.stamp_unique(channel, packet, reliability))
+    }
+    fn compression_for(&self, dest: Option<PeerId>, compression: Compression) -> Compression {
+        let compression = if self.config.prefer_compression {
+            Compression::Compressed
+        } else {
+            compression
+        };
+        match dest {
+            Some(dest) if self.uncompressed.contains(&dest) => Compression::Uncompressed,
+            None if !self.uncompressed.is_empty() => Compression::Uncompressed,
+            _ => compression,
+        }
+    }
+    pub fn send_raw_compressed(
+        &self,
+        dest: PeerId,
+        data: Vec<u8>,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        self.send_raw_on(Channel::DEFAULT, dest, data, reliability, compression)
+    }
+    pub fn send_raw_on(
+        &self,
+        channel: Channel,
+        dest: PeerId,
+        data: Vec<u8>,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_raw(channel, Some(dest), data, reliability, compression)?;
+        self.send_packet(dest, packet, reliability)
+    }
+    pub fn broadcast_raw_compressed(
+        &self,
+        data: Vec<u8>,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        self.broadcast_raw_on(Channel::DEFAULT, data, reliability, compression)
+    }
+    pub fn broadcast_raw_on(
+        &self,
+        channel: Channel,
+        data: Vec<u8>,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_raw(channel, None, data, reliability, compression)?;
+        self.broadcast_data(packet, reliability)
+    }
+    fn sequence(&self, channel: Channel, reliability: Reliability) -> Option<u32> {
+        self.idle.touch();
+        (reliability == Reliability::Sequenced).then(|| self.sequences.next(channel))
+    }
+    pub(crate) fn send_packet(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        self.send_prioritized(dest, packet, reliability, Priority::Normal)
+    }
+    pub(crate) fn send_prioritized(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+        priority: Priority,
+    ) -> Result<(), NetError> {
+        let result = self.route_packet(dest, packet, reliability, priority);
+        self.report(Some(dest), result)
+    }
+    pub fn send_with_priority<T: Encode>(
+        &self,
+        channel: Channel,
+        dest: PeerId,
+        data: &T,
+        reliability: Reliability,
+        priority: Priority,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(channel, Some(dest), data, reliability, compression)?;
+        self.send_prioritized(dest, packet, reliability, priority)
+    }
+    pub fn broadcast_with_priority<T: Encode>(
+        &self,
+        channel: Channel,
+        data: &T,
+        reliability: Reliability,
+        priority: Priority,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(channel, None, data, reliability, compression)?;
+        for peer in self.peers() {
+            self.send_prioritized(peer, packet.clone(), reliability, priority)?;
+        }
+        Ok(())
+    }
+    pub fn priority_drops(&self) -> PriorityDrops {
+        *self.priority_drops.lock().unwrap()
     }
-}
-impl PeerId {
-    pub fn raw(&self) -> u64 {
-        self.0
+    fn report(&self, peer: Option<PeerId>, result: Result<(), NetError>) -> Result<(), NetError> {
+        if let Err(error) = &result
+            && self.config.report_send_errors
+        {
+            self.send_errors.lock().unwrap().push(NetSendError {
+                peer,
+                error: error.duplicate(),
+            })
+        }
+        result
     }
-}
-#[allow(unused_variables)]
-pub(crate) fn pack<T: Encode>(data: &T, compression: Compression) -> Vec<u8> {
-    #[allow(unused_mut)]
-    let mut data = encode(data);
-    #[cfg(feature = "compress")]
-    {
-        match compression {
-            Compression::Compressed => {
-                data = compress_prepend_size(&data);
-                data.push(1);
-            }
-            Compression::Uncompressed => {
-                data.push(0);
-            }
+    pub fn take_send_errors(&self) -> Vec<NetSendError> {
+        std::mem::take(&mut *self.send_errors.lock().unwrap())
+    }
+    fn route_packet(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+        priority: Priority,
+    ) -> Result<(), NetError> {
+        self.check_size(packet.len(), reliability)?;
+        if !self.has_peer(dest) {
+            return self.unknown_peer(dest, packet, reliability);
+        }
+        let Some(packet) = self.throttle_channel(dest, packet, reliability) else {
+            return Ok(());
         };
-        data
+        if self.config.tick_rate.is_some()
+            && matches!(
+                reliability,
+                Reliability::Unreliable | Reliability::Sequenced
+            )
+        {
+            self.ticker.queue(dest, packet);
+            return Ok(());
+        }
+        if let Some(mtu) = self.config.coalesce
+            && packet.len() < mtu
+            && matches!(
+                reliability,
+                Reliability::Unreliable | Reliability::Sequenced | Reliability::Reliable
+            )
+        {
+            self.batches.coalesce(dest, packet, reliability);
+            return Ok(());
+        }
+        if self.config.batch_sends {
+            self.batches.batch(dest, packet, reliability);
+            return Ok(());
+        }
+        if let Some(queue) = self.config.send_queue {
+            match self
+                .queue
+                .push(queue, dest, packet, reliability, priority)?
+            {
+                Pushed::Queued => {}
+                Pushed::Dropped(priority) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    let mut drops = self.priority_drops.lock().unwrap();
+                    match priority {
+                        Priority::Low => drops.low += 1,
+                        Priority::Normal => drops.normal += 1,
+                        Priority::High => drops.high += 1,
+                        Priority::Critical => drops.critical += 1,
+                    }
+                }
+                Pushed::Full(pending) => {
+                    for (packet, reliability, _) in pending {
+                        self.dispatch(dest, packet, reliability)?;
+                    }
+                }
+            }
+            return Ok(());
+        }
+        self.pace(dest, packet, reliability)
     }
-    #[cfg(not(feature = "compress"))]
-    {
-        data
+    pub fn queued_sends(&self, peer: PeerId) -> usize {
+        self.queue.len(peer)
     }
-}
-pub(crate) fn unpack<T: DecodeOwned>(data: &[u8]) -> T {
-    #[cfg(feature = "compress")]
-    let data = if *data.last().unwrap() == 1 {
-        &decompress_size_prepended(&data[..data.len() - 1]).unwrap()
-    } else {
-        &data[..data.len() - 1]
-    };
-    decode(data).unwrap()
-}
-pub enum ClientTypeRef<'a> {
-    #[cfg(feature = "steam")]
-    Steam(&'a SteamClient),
-    #[cfg(feature = "tangled")]
-    Ip(&'a IpClient),
-    #[cfg(not(any(feature = "steam", feature = "tangled")))]
-    None(&'a ()),
-}
-#[cfg_attr(feature = "bevy", derive(Resource))]
-pub struct Client {
-    #[cfg(feature = "steam")]
-    steam_client: SteamClient,
-    #[cfg(feature = "tangled")]
-    ip_client: Option<IpClient>,
-}
-pub enum ClientMode {
-    Steam,
-    Ip,
-    None,
-}
-impl Client {
-    pub fn new(
-        #[cfg(feature = "steam")] app_id: u32,
-        #[cfg(feature = "steam")] peer_connected: ClientCallback,
-        #[cfg(feature = "steam")] peer_disconnected: ClientCallback,
-    ) -> Option<Self> {
-        Some(Self {
-            #[cfg(feature = "steam")]
-            steam_client: SteamClient::new(app_id, peer_connected, peer_disconnected).ok()?,
-            #[cfg(feature = "tangled")]
-            ip_client: None,
-        })
+    pub(crate) fn sealer(&self) -> seal::Seal {
+        self.seal.configure(&self.config);
+        self.seal.clone()
     }
-    pub fn recv<T, F>(&mut self, f: F)
-    where
-        F: FnMut(ClientTypeRef, Message<T>),
-        T: DecodeOwned,
-    {
-        #[cfg(feature = "tangled")]
-        if let Some(ip) = &mut self.ip_client {
-            ip.recv(f);
-            return;
-        }
+    #[allow(unused_variables)]
+    pub fn max_payload(&self, reliability: Reliability) -> usize {
         #[cfg(feature = "steam")]
-        self.steam_client.recv(f)
+        if !self.is_ip() {
+            return self.config.max_message_size.min(steam::MAX_MESSAGE_SIZE);
+        }
+        match reliability {
+            Reliability::Unreliable | Reliability::Sequenced | Reliability::BestEffort => {
+                self.config.mtu.min(self.config.max_message_size)
+            }
+            Reliability::ReliableUnordered if self.is_ip() => {
+                self.config.mtu.min(self.config.max_message_size)
+            }
+            _ => self.config.max_message_size,
+        }
     }
-    pub fn recv_raw<F>(&mut self, f: F)
-    where
-        F: FnMut(ClientTypeRef, Message<&[u8]>),
-    {
+    fn check_size(&self, size: usize, reliability: Reliability) -> Result<(), NetError> {
+        if size > self.config.max_message_size {
+            return Err(NetError::TooLarge(size));
+        }
+        let max = self.max_payload(reliability);
+        if size > max {
+            return Err(NetError::MessageTooLarge { size, max });
+        }
+        Ok(())
+    }
+    fn dispatch(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        self.counters.sent(packet.len(), 1);
+        let packet = if reliability == Reliability::BestEffort {
+            self.upgrade.stamp(dest, packet)
+        } else {
+            packet
+        };
         #[cfg(feature = "tangled")]
-        if let Some(ip) = &mut self.ip_client {
-            ip.recv_raw(f);
-            return;
+        if let Some((link, local)) = self.mesh.as_ref().and_then(|m| m.route(dest)) {
+            return link.send_packet(local, packet, reliability);
+        }
+        if self.remote_peers.contains(&dest)
+            || (self.config.relay_via_host
+                && self.is_ip()
+                && !self.is_host()
+                && dest != self.host_id())
+        {
+            self.relay(Some(dest), packet, reliability);
+            return Ok(());
+        }
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        if let Some(bridge) = &self.bridge
+            && bridge.ip.has_peer(dest)
+        {
+            return bridge.ip.send_packet(dest, packet, reliability);
+        }
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.send_packet(dest, packet, reliability);
+        }
+        let (packet, reliability) = if reliability == Reliability::ReliableUnordered {
+            (self.unordered.stamp(dest, packet), Reliability::Unreliable)
+        } else {
+            (packet, reliability)
+        };
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &self.ip_client {
+            return ip.send_packet(dest, packet, reliability);
         }
         #[cfg(feature = "steam")]
-        self.steam_client.recv_raw(f)
+        if let Some(steam) = &self.steam_client {
+            return steam.send_packet(dest, packet, reliability);
+        }
+        Ok(())
     }
-    #[allow(clippy::result_unit_err)]
-    pub fn update(&mut self) -> UResult {
-        #[cfg(feature = "tangled")]
-        if let Some(ip) = &mut self.ip_client {
-            ip.update();
+    #[allow(unused_variables)]
+    pub(crate) fn broadcast_packet(
+        &self,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        let result = self.broadcast_all(packet, reliability);
+        self.report(None, result)
+    }
+    fn broadcast_all(&self, packet: Vec<u8>, reliability: Reliability) -> Result<(), NetError> {
+        self.check_size(packet.len(), reliability)?;
+        if self.config.batch_sends
+            || self.config.coalesce.is_some()
+            || self.config.send_queue.is_some()
+            || self.config.tick_rate.is_some()
+            || self.handshakes.is_active()
+            || reliability == Reliability::BestEffort
+            || reliability == Reliability::ReliableUnordered
+        {
+            for peer in self.connected_peers() {
+                self.route_packet(peer, packet.clone(), reliability, Priority::Normal)?;
+            }
             return Ok(());
         }
+        self.counters
+            .sent(packet.len(), self.connected_peers().len());
+        if !self.remote_peers.is_empty() {
+            self.relay(None, packet.clone(), reliability);
+        }
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.broadcast_packet(packet, reliability);
+        }
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &self.ip_client {
+            return ip.broadcast_packet(packet, reliability);
+        }
+        #[cfg(all(feature = "steam", feature = "tangled"))]
+        if let Some(bridge) = &self.bridge {
+            bridge.ip.broadcast_packet(packet.clone(), reliability)?;
+        }
         #[cfg(feature = "steam")]
-        self.steam_client.update()?;
+        if let Some(steam) = &self.steam_client {
+            return steam.broadcast_packet(packet, reliability);
+        }
         Ok(())
     }
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &self.ip_client {
+            return ip.local_addr;
+        }
+        None
+    }
+    pub fn external_addr(&self) -> Option<SocketAddr> {
+        #[cfg(feature = "upnp")]
+        if let Some(ip) = &self.ip_client {
+            return ip.port_mapping.as_ref()?.external_addr();
+        }
+        None
+    }
     pub fn info(&self) -> NetworkingInfo {
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.info()
-        }
+        let mut info = match &self.steam_client {
+            Some(steam) if !self.is_ip() => steam.info(),
+            _ => NetworkingInfo::default(),
+        };
         #[cfg(not(feature = "steam"))]
-        {
-            NetworkingInfo()
+        let mut info = NetworkingInfo::default();
+        if info.peers.is_empty() {
+            info.peers = self
+                .connected_peers()
+                .into_iter()
+                .map(|peer| (peer, PeerInfo::default()))
+                .collect();
+        }
+        for (peer, p) in &mut info.peers {
+            p.rtt = self.rtt(*peer).or(p.rtt);
+            p.loss = p
+                .loss
+                .or(self.config.clock_sync.and(self.clock.loss(*peer)));
+            p.queued_bytes += self.pending_bytes(*peer);
+            p.age = self.liveness.since.get(peer).map(Instant::elapsed);
         }
+        info
+    }
+    fn pending_bytes(&self, peer: PeerId) -> usize {
+        self.batches.bytes(peer) + self.queue.bytes(peer) + self.pacer.queued_bytes(peer)
     }
 }
 #[cfg(feature = "steam")]
 type UResult = Result<(), SteamError>;
 #[cfg(not(feature = "steam"))]
 type UResult = Result<(), ()>;
-pub struct NetworkingInfo(#[cfg(feature = "steam")] pub Vec<(PeerId, NetConnectionRealTimeInfo)>);
+#[derive(Copy, Debug, Clone, Default, PartialEq)]
+pub struct PeerInfo {
+    pub rtt: Option<Duration>,
+    pub loss: Option<f32>,
+    pub queued_bytes: usize,
+    pub age: Option<Duration>,
+}
+#[derive(Default)]
+pub struct NetworkingInfo {
+    pub peers: Vec<(PeerId, PeerInfo)>,
+    #[cfg(feature = "steam")]
+    pub steam: Vec<(PeerId, NetConnectionRealTimeInfo)>,
+}
 impl ClientTrait for Client {
     fn send<T: Encode>(
         &self,
@@ -181,18 +2700,8 @@ impl ClientTrait for Client {
         reliability: Reliability,
         compression: Compression,
     ) -> Result<(), NetError> {
-        #[cfg(feature = "tangled")]
-        if let Some(ip) = &self.ip_client {
-            return ip.send(dest, data, reliability, compression);
-        }
-        #[cfg(feature = "steam")]
-        {
-            self.steam_client.send(dest, data, reliability, compression)
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            Ok(())
-        }
+        let packet = self.pack_for(Channel::DEFAULT, Some(dest), data, reliability, compression)?;
+        self.send_packet(dest, packet, reliability)
     }
     fn broadcast<T: Encode>(
         &self,
@@ -200,18 +2709,8 @@ impl ClientTrait for Client {
         reliability: Reliability,
         compression: Compression,
     ) -> Result<(), NetError> {
-        #[cfg(feature = "tangled")]
-        if let Some(ip) = &self.ip_client {
-            return ip.broadcast(data, reliability, compression);
-        }
-        #[cfg(feature = "steam")]
-        {
-            self.steam_client.broadcast(data, reliability, compression)
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            Ok(())
-        }
+        let packet = self.pack_for(Channel::DEFAULT, None, data, reliability, compression)?;
+        self.broadcast_data(packet, reliability)
     }
     fn send_raw(
         &self,
@@ -219,158 +2718,145 @@ impl ClientTrait for Client {
         data: Vec<u8>,
         reliability: Reliability,
     ) -> Result<(), NetError> {
-        #[cfg(feature = "tangled")]
-        if let Some(ip) = &self.ip_client {
-            return ip.send_raw(dest, data, reliability);
-        }
-        #[cfg(feature = "steam")]
-        {
-            self.steam_client.send_raw(dest, data, reliability)
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            Ok(())
-        }
+        self.send_raw_compressed(dest, data, reliability, Compression::Uncompressed)
     }
     fn broadcast_raw(&self, data: Vec<u8>, reliability: Reliability) -> Result<(), NetError> {
-        #[cfg(feature = "tangled")]
-        if let Some(ip) = &self.ip_client {
-            return ip.broadcast_raw(data, reliability);
-        }
-        #[cfg(feature = "steam")]
-        {
-            self.steam_client.broadcast_raw(data, reliability)
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            Ok(())
-        }
+        self.broadcast_raw_compressed(data, reliability, Compression::Uncompressed)
     }
     fn my_id(&self) -> PeerId {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.my_id();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.my_id();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.my_id()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            PeerId(0)
+        if let Some(steam) = &self.steam_client {
+            return steam.my_id();
         }
+        PeerId(0)
     }
     fn host_id(&self) -> PeerId {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.host_id();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.host_id();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.host_id()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            PeerId(0)
+        if let Some(steam) = &self.steam_client {
+            return steam.host_id();
         }
+        PeerId(0)
     }
     fn is_host(&self) -> bool {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.is_host();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.is_host();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.is_host()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            false
+        if let Some(steam) = &self.steam_client {
+            return steam.is_host();
         }
+        false
     }
     fn is_client(&self) -> bool {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.is_client();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.is_client();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.is_client()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            false
+        if let Some(steam) = &self.steam_client {
+            return steam.is_client();
         }
+        false
     }
     fn peer_len(&self) -> usize {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.peer_len();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.peer_len();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.peer_len()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            0
+        if let Some(steam) = &self.steam_client {
+            return steam.peer_len();
         }
+        0
     }
     fn is_connected(&self) -> bool {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.is_connected();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.is_connected();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.is_connected()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            false
+        if let Some(steam) = &self.steam_client {
+            return steam.is_connected();
         }
+        false
     }
     fn mode(&self) -> ClientMode {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.mode();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.mode();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.mode()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            ClientMode::None
+        if let Some(steam) = &self.steam_client {
+            return steam.mode();
         }
+        ClientMode::None
     }
     fn get_name(&self) -> Option<String> {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.get_name();
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.get_name();
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.get_name()
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            None
+        if let Some(steam) = &self.steam_client {
+            return steam.get_name();
         }
+        None
     }
     fn get_name_of(&self, id: PeerId) -> Option<String> {
+        #[cfg(any(test, feature = "testnet"))]
+        if let Some(lo) = &self.loopback {
+            return lo.get_name_of(id);
+        }
         #[cfg(feature = "tangled")]
         if let Some(ip) = &self.ip_client {
             return ip.get_name_of(id);
         }
         #[cfg(feature = "steam")]
-        {
-            self.steam_client.get_name_of(id)
-        }
-        #[cfg(not(feature = "steam"))]
-        {
-            None
+        if let Some(steam) = &self.steam_client {
+            return steam.get_name_of(id);
         }
+        None
     }
 }
 impl ClientTrait for ClientTypeRef<'_> {
@@ -388,6 +2874,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.send(dest, data, reliability, compression)?,
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.send(dest, data, reliability, compression)?,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.send(dest, data, reliability, compression)?,
         }
         Ok(())
     }
@@ -404,6 +2892,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.broadcast(data, reliability, compression)?,
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.broadcast(data, reliability, compression)?,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.broadcast(data, reliability, compression)?,
         }
         Ok(())
     }
@@ -420,6 +2910,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.send_raw(dest, data, reliability)?,
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.send_raw(dest, data, reliability)?,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.send_raw(dest, data, reliability)?,
         }
         Ok(())
     }
@@ -431,6 +2923,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.broadcast_raw(data, reliability)?,
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.broadcast_raw(data, reliability)?,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.broadcast_raw(data, reliability)?,
         }
         Ok(())
     }
@@ -440,6 +2934,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.my_id,
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.my_id(),
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.my_id(),
             #[cfg(not(any(feature = "steam", feature = "tangled")))]
             Self::None(_) => PeerId(0),
         }
@@ -452,6 +2948,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.host_id(),
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.host_id(),
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.host_id(),
         }
     }
     fn is_host(&self) -> bool {
@@ -462,6 +2960,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.is_host(),
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.is_host(),
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.is_host(),
         }
     }
     fn is_client(&self) -> bool {
@@ -472,6 +2972,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.is_client(),
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.is_client(),
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.is_client(),
         }
     }
     fn peer_len(&self) -> usize {
@@ -482,6 +2984,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.peer_len(),
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.peer_len(),
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.peer_len(),
         }
     }
     fn is_connected(&self) -> bool {
@@ -492,6 +2996,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.is_connected(),
             #[cfg(feature = "tangled")]
             Self::Ip(client) => client.is_connected(),
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(client) => client.is_connected(),
         }
     }
     fn mode(&self) -> ClientMode {
@@ -502,6 +3008,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(_) => ClientMode::Steam,
             #[cfg(feature = "tangled")]
             Self::Ip(_) => ClientMode::Ip,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(_) => ClientMode::Loopback,
         }
     }
     fn get_name(&self) -> Option<String> {
@@ -512,6 +3020,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.get_name(),
             #[cfg(feature = "tangled")]
             Self::Ip(_) => None,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(_) => None,
         }
     }
     fn get_name_of(&self, id: PeerId) -> Option<String> {
@@ -522,6 +3032,8 @@ impl ClientTrait for ClientTypeRef<'_> {
             Self::Steam(client) => client.get_name_of(id),
             #[cfg(feature = "tangled")]
             Self::Ip(_) => None,
+            #[cfg(any(test, feature = "testnet"))]
+            Self::Loopback(_) => None,
         }
     }
 }
@@ -558,16 +3070,73 @@ pub trait ClientTrait {
 }
 #[derive(Debug)]
 pub enum NetError {
+    Disconnected,
+    UnknownPeer(PeerId),
+    TooLarge(usize),
+    MessageTooLarge {
+        size: usize,
+        max: usize,
+    },
+    Malformed,
+    Unsupported,
+    Init(String),
+    Crypto,
+    ChannelBlocked(Channel),
+    UnknownMessage(u32),
+    SendQueueFull(PeerId),
+    Refused(RefuseReason),
+    Timeout,
+    VersionMismatch(u32),
+    Io(std::io::Error),
     #[cfg(feature = "tangled")]
     Tangled(tangled::NetError),
     #[cfg(feature = "steam")]
     Steam(SteamError),
 }
+impl NetError {
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::Disconnected => Self::Disconnected,
+            Self::UnknownPeer(peer) => Self::UnknownPeer(*peer),
+            Self::TooLarge(size) => Self::TooLarge(*size),
+            Self::MessageTooLarge { size, max } => Self::MessageTooLarge {
+                size: *size,
+                max: *max,
+            },
+            Self::Malformed => Self::Malformed,
+            Self::Unsupported => Self::Unsupported,
+            Self::Init(s) => Self::Init(s.clone()),
+            Self::Crypto => Self::Crypto,
+            Self::ChannelBlocked(channel) => Self::ChannelBlocked(*channel),
+            Self::UnknownMessage(id) => Self::UnknownMessage(*id),
+            Self::SendQueueFull(peer) => Self::SendQueueFull(*peer),
+            Self::Refused(reason) => Self::Refused(*reason),
+            Self::Timeout => Self::Timeout,
+            Self::VersionMismatch(version) => Self::VersionMismatch(*version),
+            Self::Io(e) => Self::Io(std::io::Error::new(e.kind(), e.to_string())),
+            #[cfg(feature = "tangled")]
+            Self::Tangled(e) => Self::Init(format!("{e:?}")),
+            #[cfg(feature = "steam")]
+            Self::Steam(e) => Self::Steam(e.clone()),
+        }
+    }
+}
+#[derive(Debug)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::message::Message))]
+pub struct NetSendError {
+    pub peer: Option<PeerId>,
+    pub error: NetError,
+}
 impl Display for NetError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{self:?}")
     }
 }
+impl From<std::io::Error> for NetError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
 #[cfg(feature = "tangled")]
 impl From<tangled::NetError> for NetError {
     fn from(value: tangled::NetError) -> Self {
@@ -588,21 +3157,64 @@ pub fn update(mut client: bevy_ecs::system::ResMut<Client>) {
         warn!("{_s}")
     }
 }
+#[cfg(feature = "bevy")]
+pub fn send_errors(
+    client: bevy_ecs::system::Res<Client>,
+    mut writer: bevy_ecs::message::MessageWriter<NetSendError>,
+) {
+    writer.write_batch(client.take_send_errors());
+}
+#[cfg(feature = "bevy")]
+pub fn update_network_time(
+    client: bevy_ecs::system::Res<Client>,
+    mut time: bevy_ecs::system::ResMut<NetworkTime>,
+) {
+    *time = client.network_time()
+}
+#[cfg(feature = "bevy")]
+pub fn update_network_tick(
+    client: bevy_ecs::system::Res<Client>,
+    mut tick: bevy_ecs::system::ResMut<NetworkTick>,
+) {
+    if let Some(t) = client.tick() {
+        *tick = NetworkTick(t)
+    }
+}
+#[cfg(feature = "bevy")]
+pub fn replicate_resource<R: Resource + Encode + DecodeOwned>(
+    mut commands: bevy_ecs::system::Commands,
+    mut client: bevy_ecs::system::ResMut<Client>,
+    resource: Option<bevy_ecs::system::ResMut<R>>,
+) {
+    let name = std::any::type_name::<R>();
+    if client.is_host() {
+        if let Some(resource) = resource.filter(|r| r.is_changed())
+            && let Err(_s) = client.sync_resource(name, &*resource)
+        {
+            #[cfg(feature = "log")]
+            warn!("{_s}")
+        }
+        return;
+    }
+    if let Some(value) = client.take_resource::<R>(name) {
+        match resource {
+            Some(mut resource) => *resource = value,
+            None => commands.insert_resource(value),
+        }
+    }
+}
 #[cfg(not(feature = "steam"))]
 #[cfg(feature = "tangled")]
 #[cfg(test)]
 #[tokio::test]
 async fn test_ip() {
     let mut host = Client::new().unwrap();
-    host.host_ip(None, None).unwrap();
+    host.host_ip(0, None, None).unwrap();
+    let addr = host.local_addr().unwrap();
     let mut peer1 = Client::new().unwrap();
-    peer1
-        .join_ip("127.0.0.1".parse().unwrap(), None, None)
-        .unwrap();
+    peer1.join_ip(addr, None, None).unwrap();
     let mut peer2 = Client::new().unwrap();
-    peer2
-        .join_ip("127.0.0.1".parse().unwrap(), None, None)
-        .unwrap();
+    peer2.join_ip(addr, None, None).unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(20)).await;
     let _ = peer1.update();
     let _ = peer2.update();
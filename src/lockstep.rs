@@ -0,0 +1,127 @@
+use crate::{Channel, Client, ClientTrait, Compression, NetError, PeerId, Reliability};
+use bitcode::{Decode, DecodeOwned, Encode, decode};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickInputs<T> {
+    pub tick: u64,
+    pub inputs: HashMap<PeerId, T>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockstepEvent<T> {
+    Ready(TickInputs<T>),
+    Stalled { tick: u64, waiting: Vec<PeerId> },
+}
+#[derive(Encode, Decode)]
+enum Wire<T> {
+    Input { tick: u64, input: T },
+    Complete { tick: u64, inputs: Vec<(PeerId, T)> },
+}
+pub struct Lockstep<T> {
+    channel: Channel,
+    timeout: Duration,
+    next: u64,
+    pending: BTreeMap<u64, (Instant, HashMap<PeerId, T>)>,
+    stalled: Option<u64>,
+}
+impl<T: Encode + DecodeOwned + Clone> Lockstep<T> {
+    pub fn new(channel: Channel, timeout: Duration) -> Self {
+        Self {
+            channel,
+            timeout,
+            next: 0,
+            pending: BTreeMap::new(),
+            stalled: None,
+        }
+    }
+    pub fn next_tick(&self) -> u64 {
+        self.next
+    }
+    pub fn reset(&mut self, tick: u64) {
+        self.next = tick;
+        self.pending.clear();
+        self.stalled = None;
+    }
+    pub fn submit(&mut self, client: &Client, tick: u64, input: T) -> Result<(), NetError> {
+        if client.is_host() {
+            self.entry(tick).insert(client.my_id(), input);
+            return Ok(());
+        }
+        self.entry(tick);
+        client.send_on(
+            self.channel,
+            client.host_id(),
+            &Wire::Input { tick, input },
+            Reliability::Reliable,
+            Compression::Uncompressed,
+        )
+    }
+    fn entry(&mut self, tick: u64) -> &mut HashMap<PeerId, T> {
+        &mut self
+            .pending
+            .entry(tick)
+            .or_insert_with(|| (Instant::now(), HashMap::new()))
+            .1
+    }
+    pub fn poll(&mut self, client: &mut Client) -> Vec<LockstepEvent<T>> {
+        let host = client.is_host();
+        for m in client.take_channel(self.channel) {
+            match decode::<Wire<T>>(&m.data) {
+                Ok(Wire::Input { tick, input }) if host && tick >= self.next => {
+                    self.entry(tick).insert(m.src, input);
+                }
+                Ok(Wire::Complete { tick, inputs }) if !host && m.src == client.host_id() => {
+                    let entry = self.entry(tick);
+                    entry.clear();
+                    entry.extend(inputs);
+                }
+                _ => {}
+            }
+        }
+        let mut events = Vec::new();
+        let mut expected = client.peers();
+        expected.push(client.my_id());
+        while let Some((started, inputs)) = self.pending.get(&self.next) {
+            let waiting: Vec<PeerId> = if host {
+                expected
+                    .iter()
+                    .filter(|p| !inputs.contains_key(p))
+                    .copied()
+                    .collect()
+            } else if inputs.is_empty() {
+                vec![client.host_id()]
+            } else {
+                Vec::new()
+            };
+            if !waiting.is_empty() {
+                if started.elapsed() >= self.timeout && self.stalled != Some(self.next) {
+                    self.stalled = Some(self.next);
+                    events.push(LockstepEvent::Stalled {
+                        tick: self.next,
+                        waiting,
+                    });
+                }
+                break;
+            }
+            let tick = self.next;
+            let Some((_, inputs)) = self.pending.remove(&tick) else {
+                break;
+            };
+            if host {
+                let complete = Wire::Complete {
+                    tick,
+                    inputs: inputs.iter().map(|(p, i)| (*p, i.clone())).collect(),
+                };
+                let _ = client.broadcast_on(
+                    self.channel,
+                    &complete,
+                    Reliability::Reliable,
+                    Compression::Uncompressed,
+                );
+            }
+            events.push(LockstepEvent::Ready(TickInputs { tick, inputs }));
+            self.next += 1;
+        }
+        events
+    }
+}
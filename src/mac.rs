@@ -0,0 +1,41 @@
+use crate::{Client, PeerId};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+const TAG: usize = 32;
+#[derive(Default)]
+pub(crate) struct Rejected {
+    per_peer: HashMap<PeerId, u64>,
+    total: u64,
+}
+impl Rejected {
+    pub(crate) fn record(&mut self, peer: PeerId) {
+        *self.per_peer.entry(peer).or_default() += 1;
+        self.total += 1;
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.per_peer.remove(&peer);
+    }
+}
+impl Client {
+    pub fn rejected_packets(&self, peer: PeerId) -> u64 {
+        self.rejected.per_peer.get(&peer).copied().unwrap_or(0)
+    }
+    pub fn rejected_packets_total(&self) -> u64 {
+        self.rejected.total
+    }
+}
+pub(crate) fn sign(key: &[u8], mut packet: Vec<u8>) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(&packet);
+    packet.extend_from_slice(&mac.finalize().into_bytes());
+    packet
+}
+pub(crate) fn verify(key: &[u8], mut data: Vec<u8>) -> Option<Vec<u8>> {
+    let len = data.len().checked_sub(TAG)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(&data[..len]);
+    mac.verify_slice(&data[len..]).ok()?;
+    data.truncate(len);
+    Some(data)
+}
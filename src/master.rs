@@ -0,0 +1,124 @@
+use crate::{Client, NetError};
+use bitcode::{Decode, Encode, decode, encode};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "log")]
+use tracing::warn;
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub struct ServerInfo {
+    pub name: String,
+    pub addr: String,
+    pub players: u32,
+    pub max_players: u32,
+    pub map: String,
+    pub metadata: Vec<(String, String)>,
+}
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerFilter {
+    pub name: Option<String>,
+    pub map: Option<String>,
+    pub not_full: bool,
+    pub not_empty: bool,
+    pub metadata: Vec<(String, String)>,
+}
+impl ServerFilter {
+    pub fn matches(&self, info: &ServerInfo) -> bool {
+        self.name
+            .as_ref()
+            .is_none_or(|n| info.name.to_lowercase().contains(&n.to_lowercase()))
+            && self.map.as_ref().is_none_or(|m| *m == info.map)
+            && (!self.not_full || info.max_players == 0 || info.players < info.max_players)
+            && (!self.not_empty || info.players > 0)
+            && self
+                .metadata
+                .iter()
+                .all(|kv| info.metadata.iter().any(|m| m == kv))
+    }
+}
+pub(crate) struct Heartbeat {
+    url: String,
+    interval: Duration,
+    pub(crate) info: ServerInfo,
+    last: Option<Instant>,
+}
+impl Client {
+    pub fn register_server(&mut self, url: &str, info: ServerInfo, interval: Duration) {
+        self.master = Some(Heartbeat {
+            url: url.to_owned(),
+            interval,
+            info,
+            last: None,
+        })
+    }
+    pub fn server_info_mut(&mut self) -> Option<&mut ServerInfo> {
+        self.master.as_mut().map(|m| &mut m.info)
+    }
+    pub fn unregister_server(&mut self) {
+        if let Some(master) = self.master.take() {
+            let body = encode(&master.info);
+            thread::spawn(move || request("DELETE", &master.url, &body));
+        }
+    }
+    pub(crate) fn pump_heartbeat(&mut self) {
+        let players = self.peers().len() as u32 + u32::from(!self.is_dedicated());
+        let addr = self.external_addr().or(self.local_addr());
+        let Some(master) = self.master.as_mut().filter(|_| self.is_host()) else {
+            return;
+        };
+        if master.last.is_some_and(|l| l.elapsed() < master.interval) {
+            return;
+        }
+        master.last = Some(Instant::now());
+        master.info.players = players;
+        if let Some(addr) = addr {
+            master.info.addr = addr.to_string();
+        }
+        let url = master.url.clone();
+        let body = encode(&master.info);
+        thread::spawn(move || {
+            if let Err(_e) = request("POST", &url, &body) {
+                #[cfg(feature = "log")]
+                warn!("failed to reach master server {url}: {_e:?}")
+            }
+        });
+    }
+}
+pub fn query_servers(url: &str, filter: &ServerFilter) -> Result<Vec<ServerInfo>, NetError> {
+    let body = request("GET", url, &[])?;
+    let servers: Vec<ServerInfo> = decode(&body).map_err(|_| NetError::Malformed)?;
+    Ok(servers.into_iter().filter(|s| filter.matches(s)).collect())
+}
+fn request(method: &str, url: &str, body: &[u8]) -> Result<Vec<u8>, NetError> {
+    let rest = url.strip_prefix("http://").ok_or(NetError::Unsupported)?;
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = if path.is_empty() { "/" } else { path };
+    let addr = if host.contains(':') {
+        host.to_owned()
+    } else {
+        format!("{host}:80")
+    };
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or(NetError::Malformed)?;
+    let ok = response[..split]
+        .split(|b| *b == b' ')
+        .nth(1)
+        .is_some_and(|code| code.starts_with(b"2"));
+    if !ok {
+        return Err(NetError::Malformed);
+    }
+    Ok(response[split + 4..].to_vec())
+}
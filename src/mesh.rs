@@ -0,0 +1,223 @@
+#[cfg(feature = "encrypt")]
+use crate::crypto;
+use crate::ip::IpClient;
+#[cfg(feature = "hmac")]
+use crate::mac;
+#[cfg(any(feature = "encrypt", feature = "hmac"))]
+use crate::replay;
+use crate::{Client, ClientTrait, Event, MsgType, PeerId, Reliability, unpack};
+use bitcode::encode;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use tokio::runtime::Handle;
+pub(crate) struct Mesh {
+    listener: IpClient,
+    outward: Option<IpAddr>,
+    announced: bool,
+    links: HashMap<PeerId, IpClient>,
+    greeted: HashSet<PeerId>,
+    incoming: HashMap<PeerId, PeerId>,
+    addrs: HashMap<PeerId, String>,
+}
+impl Mesh {
+    pub(crate) fn route(&self, dest: PeerId) -> Option<(&IpClient, PeerId)> {
+        if let Some(link) = self.links.get(&dest).filter(|l| l.is_connected()) {
+            return Some((link, link.host_id()));
+        }
+        self.incoming
+            .iter()
+            .find(|(_, p)| **p == dest)
+            .map(|(local, _)| (&self.listener, *local))
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.links.remove(&peer);
+        self.greeted.remove(&peer);
+        self.incoming.retain(|_, p| *p != peer);
+        self.addrs.remove(&peer);
+    }
+}
+fn unspecified(v4: bool) -> IpAddr {
+    if v4 {
+        Ipv4Addr::UNSPECIFIED.into()
+    } else {
+        Ipv6Addr::UNSPECIFIED.into()
+    }
+}
+fn outward_ip(remote: SocketAddr) -> Option<IpAddr> {
+    let socket = UdpSocket::bind(SocketAddr::new(unspecified(remote.is_ipv4()), 0)).ok()?;
+    socket.connect(remote).ok()?;
+    socket.local_addr().ok().map(|a| a.ip())
+}
+impl Client {
+    pub fn is_meshed(&self, peer: PeerId) -> bool {
+        self.mesh.as_ref().is_some_and(|m| m.route(peer).is_some())
+    }
+    pub(crate) fn pump_mesh(&mut self) {
+        if !self.config.mesh || !self.is_ip() || !self.is_connected() {
+            return;
+        }
+        if self.mesh.is_none() {
+            let runtime = self.ip_runtime();
+            let _guard = runtime.as_ref().map(Handle::enter);
+            let outward = self
+                .ip_client
+                .as_ref()
+                .and_then(IpClient::remote)
+                .and_then(outward_ip);
+            let bind = SocketAddr::new(unspecified(outward.is_some_and(|ip| ip.is_ipv4())), 0);
+            let Ok(listener) = IpClient::host(bind, false, self.sealer(), None, None) else {
+                return;
+            };
+            self.mesh = Some(Mesh {
+                listener,
+                outward,
+                announced: false,
+                links: HashMap::new(),
+                greeted: HashSet::new(),
+                incoming: HashMap::new(),
+                addrs: HashMap::new(),
+            });
+        }
+        if !self.is_host()
+            && let Some(mesh) = &self.mesh
+            && !mesh.announced
+            && let Some(port) = mesh.listener.local_addr.map(|a| a.port())
+            && let Some(ip) = mesh.outward
+        {
+            let packet = encode(&MsgType::MeshAddr(SocketAddr::new(ip, port).to_string()));
+            if self
+                .send_packet(self.host_id(), packet, Reliability::Reliable)
+                .is_ok()
+                && let Some(mesh) = &mut self.mesh
+            {
+                mesh.announced = true
+            }
+        }
+        let hello = encode(&MsgType::MeshHello(self.my_id()));
+        let Some(Mesh {
+            listener,
+            links,
+            greeted,
+            ..
+        }) = &mut self.mesh
+        else {
+            return;
+        };
+        listener.update();
+        for (peer, link) in links.iter_mut() {
+            link.update();
+            if link.is_connected() && greeted.insert(*peer) {
+                let _ = link.send_packet(link.host_id(), hello.clone(), Reliability::Reliable);
+            }
+        }
+    }
+    pub(crate) fn poll_mesh(&mut self, events: &mut Vec<Event>) {
+        let Some(mesh) = &mut self.mesh else {
+            return;
+        };
+        let mut raw = Vec::new();
+        mesh.listener.poll(&mut raw);
+        let mut hellos = Vec::new();
+        for event in raw {
+            match event {
+                Event::Message(local, data) => match mesh.incoming.get(&local) {
+                    Some(peer) => events.push(Event::Message(*peer, data)),
+                    None => hellos.push((local, data)),
+                },
+                Event::Disconnected(local, _) => {
+                    mesh.incoming.remove(&local);
+                }
+                Event::Connected(_) => {}
+            }
+        }
+        for (peer, link) in mesh.links.iter_mut() {
+            let mut raw = Vec::new();
+            link.poll(&mut raw);
+            for event in raw {
+                if let Event::Message(_, data) = event {
+                    events.push(Event::Message(*peer, data))
+                }
+            }
+        }
+        for (local, data) in hellos {
+            #[cfg(feature = "hmac")]
+            let data = match self.config.hmac_key.as_deref() {
+                Some(key) => match mac::verify(key, data) {
+                    Some(data) => data,
+                    None => continue,
+                },
+                None => data,
+            };
+            #[cfg(feature = "encrypt")]
+            let data = match self.config.psk {
+                Some(key) => match crypto::open(&key, &data) {
+                    Ok(data) => data,
+                    Err(_) => continue,
+                },
+                None => data,
+            };
+            #[cfg(any(feature = "encrypt", feature = "hmac"))]
+            let (nonce, data) = if self.replay_protected() {
+                match replay::strip(data) {
+                    Some((lane, nonce, data)) => (Some((lane, nonce)), data),
+                    None => continue,
+                }
+            } else {
+                (None, data)
+            };
+            let Some(MsgType::MeshHello(peer)) = unpack(&data) else {
+                continue;
+            };
+            if !self.has_peer(peer) {
+                continue;
+            }
+            #[cfg(any(feature = "encrypt", feature = "hmac"))]
+            if let Some((lane, nonce)) = nonce
+                && !self.fresh(peer, lane, nonce)
+            {
+                continue;
+            }
+            if let Some(mesh) = &mut self.mesh {
+                mesh.incoming.insert(local, peer);
+            }
+        }
+    }
+    pub(crate) fn receive_mesh_addr(&mut self, src: PeerId, addr: String) {
+        if !self.is_host() || addr.parse::<SocketAddr>().is_err() {
+            return;
+        }
+        let Some(mesh) = &mut self.mesh else {
+            return;
+        };
+        mesh.addrs.insert(src, addr);
+        let packet = encode(&MsgType::MeshPeers(
+            mesh.addrs.iter().map(|(p, a)| (*p, a.clone())).collect(),
+        ));
+        for peer in self.connected_peers() {
+            let _ = self.send_packet(peer, packet.clone(), Reliability::Reliable);
+        }
+    }
+    pub(crate) fn receive_mesh_peers(&mut self, src: PeerId, peers: Vec<(PeerId, String)>) {
+        if src != self.host_id() || self.is_host() {
+            return;
+        }
+        let me = self.my_id();
+        let seal = self.sealer();
+        let runtime = self.ip_runtime();
+        let _guard = runtime.as_ref().map(Handle::enter);
+        let Some(mesh) = &mut self.mesh else {
+            return;
+        };
+        for (peer, addr) in peers {
+            if peer >= me || peer == src || mesh.links.contains_key(&peer) {
+                continue;
+            }
+            let Ok(addr) = addr.parse() else {
+                continue;
+            };
+            if let Ok(link) = IpClient::join(addr, seal.clone(), None, None) {
+                mesh.links.insert(peer, link);
+            }
+        }
+    }
+}
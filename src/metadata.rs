@@ -0,0 +1,79 @@
+use crate::{Client, ClientTrait, MsgType, NetError, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashMap;
+#[derive(Default)]
+pub(crate) struct Metadata {
+    pub(crate) local: HashMap<String, String>,
+    pub(crate) peers: HashMap<PeerId, HashMap<String, String>>,
+}
+impl Metadata {
+    pub(crate) fn sync(&self) -> Vec<(String, Option<String>)> {
+        self.local
+            .iter()
+            .map(|(k, v)| (k.clone(), Some(v.clone())))
+            .collect()
+    }
+    pub(crate) fn apply(
+        &mut self,
+        peer: PeerId,
+        entries: Vec<(String, Option<String>)>,
+    ) -> Vec<String> {
+        let map = self.peers.entry(peer).or_default();
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let changed = match value {
+                    Some(value) => map.insert(key.clone(), value.clone()) != Some(value),
+                    None => map.remove(&key).is_some(),
+                };
+                changed.then_some(key)
+            })
+            .collect()
+    }
+}
+impl Client {
+    pub fn set_local_metadata(&mut self, key: &str, value: &str) -> Result<(), NetError> {
+        self.metadata.local.insert(key.to_owned(), value.to_owned());
+        let packet = encode(&MsgType::Metadata(vec![(
+            key.to_owned(),
+            Some(value.to_owned()),
+        )]));
+        self.broadcast_packet(packet, Reliability::Reliable)
+    }
+    pub fn remove_local_metadata(&mut self, key: &str) -> Result<(), NetError> {
+        if self.metadata.local.remove(key).is_none() {
+            return Ok(());
+        }
+        let packet = encode(&MsgType::Metadata(vec![(key.to_owned(), None)]));
+        self.broadcast_packet(packet, Reliability::Reliable)
+    }
+    pub fn local_metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.local.get(key).map(String::as_str)
+    }
+    pub fn peer_name(&self, peer: PeerId) -> Option<String> {
+        let name = if peer == self.my_id() {
+            self.get_name()
+        } else {
+            self.get_name_of(peer)
+        };
+        name.or_else(|| {
+            if peer == self.my_id() {
+                self.metadata.local.get("name").cloned()
+            } else {
+                self.metadata.peers.get(&peer)?.get("name").cloned()
+            }
+        })
+    }
+    pub fn peer_metadata(&self, peer: PeerId, key: &str) -> Option<String> {
+        let value = self
+            .metadata
+            .peers
+            .get(&peer)
+            .and_then(|m| m.get(key))
+            .cloned();
+        if value.is_none() && key == "name" {
+            return self.get_name_of(peer);
+        }
+        value
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{Client, ClientTrait, MsgType, NetError, NetEvent, PeerId, Reliability};
+use bitcode::{DecodeOwned, Encode, decode, encode};
+#[derive(Default)]
+pub(crate) struct Migration {
+    pub(crate) cohost: Option<PeerId>,
+    pub(crate) standby: Option<Vec<u8>>,
+}
+impl Client {
+    pub(crate) fn migrate(&mut self, old: PeerId) -> bool {
+        let Some(cohost) = self.migration.cohost.take() else {
+            return false;
+        };
+        if cohost == self.my_id() {
+            #[cfg(feature = "steam")]
+            if !self.is_ip()
+                && let Some(steam) = &mut self.steam_client
+            {
+                steam.promote();
+            }
+            self.net_events.push_back(NetEvent::Promoted);
+        } else {
+            #[cfg(feature = "steam")]
+            if !self.is_ip()
+                && let Some(steam) = &mut self.steam_client
+                && steam.host_id == old
+            {
+                steam.host_id = cohost;
+            }
+            self.net_events
+                .push_back(NetEvent::HostMigrated { host: cohost });
+        }
+        true
+    }
+    pub fn set_cohost(&mut self, peer: Option<PeerId>) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Ok(());
+        }
+        self.migration.cohost = peer;
+        let packet = encode(&MsgType::CoHost(peer));
+        self.broadcast_packet(packet, Reliability::Reliable)
+    }
+    pub fn cohost(&self) -> Option<PeerId> {
+        self.migration.cohost
+    }
+    pub fn sync_standby<T: Encode>(&self, state: &T) -> Result<(), NetError> {
+        let Some(cohost) = self.migration.cohost.filter(|_| self.is_host()) else {
+            return Ok(());
+        };
+        let packet = encode(&MsgType::Standby(encode(state)));
+        self.send_packet(cohost, packet, Reliability::Reliable)
+    }
+    pub fn standby_state<T: DecodeOwned>(&self) -> Option<T> {
+        decode(self.migration.standby.as_ref()?).ok()
+    }
+}
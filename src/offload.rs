@@ -0,0 +1,40 @@
+use crate::{Channel, NetError, PeerId, decompress};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+type Job = (PeerId, Channel, Option<u64>, Vec<u8>, usize);
+type Done = (PeerId, Channel, Option<u64>, Result<Vec<u8>, NetError>);
+#[derive(Default)]
+pub(crate) struct Offload(Option<(Sender<Job>, Mutex<Receiver<Done>>)>);
+impl Offload {
+    pub(crate) fn submit(
+        &mut self,
+        src: PeerId,
+        channel: Channel,
+        tick: Option<u64>,
+        data: Vec<u8>,
+        max: usize,
+    ) {
+        let (tx, _) = self.0.get_or_insert_with(|| {
+            let (job_tx, job_rx) = channel::<Job>();
+            let (done_tx, done_rx) = channel();
+            std::thread::spawn(move || {
+                for (src, channel, tick, data, max) in job_rx {
+                    if done_tx
+                        .send((src, channel, tick, decompress(true, &data, max)))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+            (job_tx, Mutex::new(done_rx))
+        });
+        let _ = tx.send((src, channel, tick, data, max));
+    }
+    pub(crate) fn done(&mut self) -> Vec<Done> {
+        match &mut self.0 {
+            Some((_, rx)) => rx.get_mut().unwrap().try_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{Client, ClientTrait, MsgType, NetError, NetEvent, PeerId, Reliability};
+use bitcode::encode;
+impl Client {
+    pub fn set_owner(&mut self, id: u64, owner: Option<PeerId>) -> Result<(), NetError> {
+        if !self.is_host() {
+            if self.owners.get(&id) != Some(&self.my_id()) {
+                return Err(NetError::Unsupported);
+            }
+            let packet = encode(&MsgType::Ownership { id, owner });
+            return self.send_packet(self.host_id(), packet, Reliability::Reliable);
+        }
+        self.apply_owner(id, owner);
+        let packet = encode(&MsgType::Ownership { id, owner });
+        self.broadcast_packet(packet, Reliability::Reliable)
+    }
+    pub(crate) fn apply_owner(&mut self, id: u64, owner: Option<PeerId>) {
+        let changed = match owner {
+            Some(owner) => self.owners.insert(id, owner) != Some(owner),
+            None => self.owners.remove(&id).is_some(),
+        };
+        if changed {
+            self.net_events
+                .push_back(NetEvent::OwnershipChanged { id, owner })
+        }
+    }
+    pub fn owner(&self, id: u64) -> Option<PeerId> {
+        self.owners.get(&id).copied()
+    }
+    pub fn is_owner(&self, id: u64) -> bool {
+        self.owner(id) == Some(self.my_id())
+    }
+    pub fn check_owner(&mut self, id: u64, peer: PeerId) -> bool {
+        let allowed = match self.owner(id) {
+            Some(owner) => owner == peer || (peer == self.host_id() && !self.is_host()),
+            None => peer == self.host_id(),
+        };
+        if !allowed {
+            self.net_events
+                .push_back(NetEvent::OwnershipViolation { peer, id })
+        }
+        allowed
+    }
+}
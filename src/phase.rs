@@ -0,0 +1,76 @@
+use crate::{Channel, Client, ClientTrait, MsgType, NetError, NetEvent, Reliability};
+use bitcode::{Decode, Encode, encode};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+#[derive(Encode, Decode, Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum Phase {
+    #[default]
+    Lobby,
+    Loading,
+    Playing,
+    Custom(u8),
+}
+#[derive(Debug, Clone, Default)]
+pub struct PhasePolicy {
+    pub disabled: HashSet<Channel>,
+    pub rates: HashMap<Channel, u32>,
+}
+#[derive(Default)]
+pub(crate) struct Phases {
+    pub(crate) current: Phase,
+    pub(crate) policies: HashMap<Phase, PhasePolicy>,
+    counters: Mutex<HashMap<Channel, (Instant, u32)>>,
+}
+impl Phases {
+    pub(crate) fn allow(&self, channel: Channel) -> Result<(), NetError> {
+        let Some(policy) = self.policies.get(&self.current) else {
+            return Ok(());
+        };
+        if policy.disabled.contains(&channel) {
+            return Err(NetError::ChannelBlocked(channel));
+        }
+        if let Some(&rate) = policy.rates.get(&channel) {
+            let mut counters = self.counters.lock().unwrap();
+            let (start, count) = counters.entry(channel).or_insert((Instant::now(), 0));
+            if start.elapsed() >= Duration::from_secs(1) {
+                *start = Instant::now();
+                *count = 0;
+            }
+            if *count >= rate {
+                return Err(NetError::ChannelBlocked(channel));
+            }
+            *count += 1;
+        }
+        Ok(())
+    }
+    pub(crate) fn receives(&self, channel: Channel) -> bool {
+        self.policies
+            .get(&self.current)
+            .is_none_or(|p| !p.disabled.contains(&channel))
+    }
+    pub(crate) fn set(&mut self, phase: Phase) -> bool {
+        let changed = self.current != phase;
+        self.current = phase;
+        self.counters.get_mut().unwrap().clear();
+        changed
+    }
+}
+impl Client {
+    pub fn set_phase_policy(&mut self, phase: Phase, policy: PhasePolicy) {
+        self.phases.policies.insert(phase, policy);
+    }
+    pub fn set_phase(&mut self, phase: Phase) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Ok(());
+        }
+        self.broadcast_packet(encode(&MsgType::Phase(phase)), Reliability::Reliable)?;
+        if self.phases.set(phase) {
+            self.net_events.push_back(NetEvent::PhaseChanged(phase))
+        }
+        Ok(())
+    }
+    pub fn phase(&self) -> Phase {
+        self.phases.current
+    }
+}
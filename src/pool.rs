@@ -0,0 +1,90 @@
+use crate::{Channel, Compression, MsgType};
+use bitcode::{Buffer, Encode};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+const MAX_POOLED: usize = 16;
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub pooled: usize,
+}
+#[derive(Default)]
+struct Scratch {
+    inner: Buffer,
+    outer: Buffer,
+    compressed: Vec<u8>,
+}
+#[derive(Default)]
+pub(crate) struct Pool {
+    free: Mutex<Vec<Scratch>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+impl Pool {
+    fn take(&self) -> Scratch {
+        match self.free.lock().unwrap().pop() {
+            Some(scratch) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                scratch
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Scratch::default()
+            }
+        }
+    }
+    fn give(&self, scratch: Scratch) {
+        let mut free = self.free.lock().unwrap();
+        if free.len() < MAX_POOLED {
+            free.push(scratch)
+        }
+    }
+    #[allow(unused_variables)]
+    pub(crate) fn frame<T: Encode + ?Sized>(
+        &self,
+        channel: Channel,
+        seq: Option<u32>,
+        data: &T,
+        compression: Compression,
+    ) -> Vec<u8> {
+        let mut scratch = self.take();
+        let Scratch {
+            inner,
+            outer,
+            compressed,
+        } = &mut scratch;
+        let data = inner.encode(data);
+        #[cfg(feature = "compress")]
+        let (is_compressed, data) = match compression {
+            Compression::Compressed => {
+                compressed.clear();
+                compressed.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                compressed.resize(4 + lz4_flex::block::get_maximum_output_size(data.len()), 0);
+                let len = lz4_flex::block::compress_into(data, &mut compressed[4..]).unwrap_or(0);
+                compressed.truncate(4 + len);
+                (true, compressed.as_slice())
+            }
+            Compression::Uncompressed => (false, data),
+        };
+        #[cfg(not(feature = "compress"))]
+        let is_compressed = false;
+        let packet = outer
+            .encode(&MsgType::Data {
+                channel,
+                seq,
+                compressed: is_compressed,
+                data,
+            })
+            .to_vec();
+        self.give(scratch);
+        packet
+    }
+    pub(crate) fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            pooled: self.free.lock().unwrap().len(),
+        }
+    }
+}
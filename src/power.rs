@@ -0,0 +1,60 @@
+use crate::{Hibernate, NetConfig};
+use std::time::Duration;
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub enum Platform {
+    SteamDeck,
+    Desktop,
+}
+impl Platform {
+    pub fn detect() -> Self {
+        if std::env::var("SteamDeck").is_ok_and(|v| v == "1") {
+            Platform::SteamDeck
+        } else {
+            Platform::Desktop
+        }
+    }
+}
+pub fn on_battery() -> bool {
+    std::fs::read_dir("/sys/class/power_supply").is_ok_and(|dir| {
+        dir.flatten().any(|e| {
+            std::fs::read_to_string(e.path().join("status"))
+                .is_ok_and(|s| s.trim() == "Discharging")
+        })
+    })
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub enum PowerProfile {
+    #[default]
+    Balanced,
+    LowPower,
+}
+impl PowerProfile {
+    pub fn detect() -> Self {
+        if Platform::detect() == Platform::SteamDeck || on_battery() {
+            PowerProfile::LowPower
+        } else {
+            PowerProfile::Balanced
+        }
+    }
+    pub(crate) fn apply(self, config: &mut NetConfig) {
+        let default = NetConfig::default();
+        match self {
+            PowerProfile::Balanced => {
+                config.hibernate = default.hibernate;
+                config.clock_sync = default.clock_sync;
+                config.prefer_compression = default.prefer_compression;
+                config.batch_sends = default.batch_sends;
+            }
+            PowerProfile::LowPower => {
+                config.hibernate = Some(Hibernate {
+                    idle_after: Duration::from_secs(3),
+                    keepalive: Duration::from_secs(10),
+                    poll: Duration::from_millis(250),
+                });
+                config.clock_sync = Some(Duration::from_secs(30));
+                config.prefer_compression = true;
+                config.batch_sends = true;
+            }
+        }
+    }
+}
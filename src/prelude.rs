@@ -0,0 +1,4 @@
+pub use crate::{
+    Channel, DisconnectReason, Message, NetClient, NetConfig, NetError, NetEvent, PeerId,
+    Reliability,
+};
@@ -0,0 +1,86 @@
+use crate::PeerId;
+use std::collections::HashMap;
+use std::hash::Hash;
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriorityDrops {
+    pub low: u64,
+    pub normal: u64,
+    pub high: u64,
+    pub critical: u64,
+}
+pub struct PriorityAccumulator<K> {
+    priorities: HashMap<(K, PeerId), f32>,
+}
+impl<K> Default for PriorityAccumulator<K> {
+    fn default() -> Self {
+        Self {
+            priorities: HashMap::new(),
+        }
+    }
+}
+impl<K: Hash + Eq + Copy> PriorityAccumulator<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn accumulate(&mut self, key: K, peer: PeerId, weight: f32) {
+        *self.priorities.entry((key, peer)).or_default() += weight
+    }
+    pub fn sent(&mut self, key: K, peer: PeerId) {
+        if let Some(p) = self.priorities.get_mut(&(key, peer)) {
+            *p = 0.0
+        }
+    }
+    pub fn priority(&self, key: K, peer: PeerId) -> f32 {
+        self.priorities.get(&(key, peer)).copied().unwrap_or(0.0)
+    }
+    pub fn schedule<F>(&mut self, peer: PeerId, budget: usize, mut size: F) -> Vec<K>
+    where
+        F: FnMut(K) -> usize,
+    {
+        let mut candidates: Vec<(K, f32)> = self
+            .priorities
+            .iter()
+            .filter(|((_, p), priority)| *p == peer && **priority > 0.0)
+            .map(|((k, _), priority)| (*k, *priority))
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mut used = 0;
+        let mut chosen = Vec::new();
+        for (key, _) in candidates {
+            let n = size(key);
+            if used + n > budget {
+                continue;
+            }
+            used += n;
+            self.sent(key, peer);
+            chosen.push(key);
+        }
+        chosen
+    }
+    pub fn remove(&mut self, key: K) {
+        self.priorities.retain(|(k, _), _| *k != key)
+    }
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.priorities.retain(|(_, p), _| *p != peer)
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_schedule() {
+    let peer = PeerId(1);
+    let mut acc = PriorityAccumulator::new();
+    acc.accumulate(0u32, peer, 1.0);
+    acc.accumulate(1, peer, 3.0);
+    acc.accumulate(2, peer, 2.0);
+    assert_eq!(acc.schedule(peer, 2, |_| 1), vec![1, 2]);
+    assert_eq!(acc.priority(1, peer), 0.0);
+    assert_eq!(acc.priority(0, peer), 1.0);
+}
@@ -0,0 +1,110 @@
+use crate::{Client, DisconnectReason, NetError, NetEvent, RefuseReason};
+#[cfg(feature = "tangled")]
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinProgress {
+    Resolving,
+    HandshakeSent,
+    Authenticating,
+    Connected,
+    Failed(JoinFailure),
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinFailure {
+    Refused(RefuseReason),
+    Timeout,
+    VersionMismatch { host: u32, local: u32 },
+    Disconnected(DisconnectReason),
+}
+impl From<JoinFailure> for NetError {
+    fn from(value: JoinFailure) -> Self {
+        match value {
+            JoinFailure::Refused(reason) => Self::Refused(reason),
+            JoinFailure::Timeout => Self::Timeout,
+            JoinFailure::VersionMismatch { host, .. } => Self::VersionMismatch(host),
+            JoinFailure::Disconnected(_) => Self::Disconnected,
+        }
+    }
+}
+#[derive(Default)]
+pub(crate) struct Joining {
+    pub(crate) started: Option<Instant>,
+    pub(crate) result: Option<Result<(), JoinFailure>>,
+}
+impl Client {
+    pub fn is_joining(&self) -> bool {
+        self.joining.started.is_some()
+    }
+    #[cfg(feature = "tangled")]
+    pub fn join_ip_timeout(
+        &mut self,
+        socket: SocketAddr,
+        timeout: Duration,
+    ) -> Result<(), NetError> {
+        self.join_ip(socket, None, None)
+            .map_err(|e| NetError::Init(format!("{e:?}")))?;
+        self.wait_for_join(timeout)
+    }
+    pub fn wait_for_join(&mut self, timeout: Duration) -> Result<(), NetError> {
+        let start = Instant::now();
+        while self.joining.started.is_some() {
+            if start.elapsed() >= timeout {
+                self.join_progress(JoinProgress::Failed(JoinFailure::Timeout));
+                self.end_session(DisconnectReason::Timeout);
+                break;
+            }
+            let _ = self.update();
+            self.poll_transport();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        match self.joining.result.clone() {
+            Some(Ok(())) => Ok(()),
+            Some(Err(failure)) => Err(failure.into()),
+            None => Err(NetError::Disconnected),
+        }
+    }
+    pub(crate) fn begin_join(&mut self) {
+        self.joining.started = Some(Instant::now());
+        self.joining.result = None;
+        self.net_events
+            .push_back(NetEvent::JoinProgress(JoinProgress::Resolving))
+    }
+    pub(crate) fn join_progress(&mut self, progress: JoinProgress) {
+        if self.joining.started.is_none() {
+            return;
+        }
+        match &progress {
+            JoinProgress::Connected => self.joining.result = Some(Ok(())),
+            JoinProgress::Failed(failure) => self.joining.result = Some(Err(failure.clone())),
+            _ => {}
+        }
+        if self.joining.result.is_some() {
+            self.joining.started = None
+        }
+        self.net_events.push_back(NetEvent::JoinProgress(progress))
+    }
+    pub(crate) fn check_join_timeout(&mut self) {
+        if let Some(timeout) = self.config.join_timeout
+            && self
+                .joining
+                .started
+                .is_some_and(|start| start.elapsed() >= timeout)
+        {
+            self.join_progress(JoinProgress::Failed(JoinFailure::Timeout));
+            self.end_session(DisconnectReason::Timeout)
+        }
+    }
+    pub(crate) fn welcome(&mut self, version: Option<u32>) {
+        match (version, self.config.version) {
+            (Some(host), Some(local)) if host != local => {
+                self.join_progress(JoinProgress::Failed(JoinFailure::VersionMismatch {
+                    host,
+                    local,
+                }));
+                self.end_session(DisconnectReason::Kicked("version mismatch".to_owned()))
+            }
+            _ => self.join_progress(JoinProgress::Connected),
+        }
+    }
+}
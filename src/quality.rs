@@ -0,0 +1,95 @@
+use crate::{Client, NetEvent, PeerId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+#[cfg(feature = "steam")]
+use steamworks::networking_types::NetworkingConnectionState;
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConnectionQuality {
+    #[default]
+    Good,
+    Degraded,
+    Bad,
+}
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub struct QualityThresholds {
+    pub degraded_rtt: Duration,
+    pub bad_rtt: Duration,
+    pub degraded_loss: f32,
+    pub bad_loss: f32,
+    pub interval: Duration,
+}
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            degraded_rtt: Duration::from_millis(150),
+            bad_rtt: Duration::from_millis(400),
+            degraded_loss: 0.05,
+            bad_loss: 0.2,
+            interval: Duration::from_millis(500),
+        }
+    }
+}
+impl QualityThresholds {
+    fn classify(&self, rtt: Option<Duration>, loss: Option<f32>) -> ConnectionQuality {
+        let by_rtt = match rtt {
+            Some(rtt) if rtt >= self.bad_rtt => ConnectionQuality::Bad,
+            Some(rtt) if rtt >= self.degraded_rtt => ConnectionQuality::Degraded,
+            _ => ConnectionQuality::Good,
+        };
+        let by_loss = match loss {
+            Some(loss) if loss >= self.bad_loss => ConnectionQuality::Bad,
+            Some(loss) if loss >= self.degraded_loss => ConnectionQuality::Degraded,
+            _ => ConnectionQuality::Good,
+        };
+        by_rtt.max(by_loss)
+    }
+}
+#[derive(Default)]
+pub(crate) struct Quality {
+    pub(crate) peers: HashMap<PeerId, ConnectionQuality>,
+    last: Option<Instant>,
+}
+impl Client {
+    pub fn connection_quality(&self, peer: PeerId) -> ConnectionQuality {
+        self.quality.peers.get(&peer).copied().unwrap_or_default()
+    }
+    pub(crate) fn pump_quality(&mut self) {
+        let Some(thresholds) = self.config.quality else {
+            return;
+        };
+        if self
+            .quality
+            .last
+            .is_some_and(|t| t.elapsed() < thresholds.interval)
+        {
+            return;
+        }
+        self.quality.last = Some(Instant::now());
+        for peer in self.connected_peers() {
+            #[allow(unused_mut)]
+            let (mut rtt, mut loss, mut broken) = (self.rtt(peer), None, false);
+            #[cfg(feature = "steam")]
+            if !self.is_ip()
+                && let Some(steam) = &self.steam_client
+                && let Some(status) = steam.status(peer)
+            {
+                rtt = rtt.or(Some(Duration::from_millis(status.ping().max(0) as u64)));
+                loss = Some(1.0 - status.connection_quality_local().clamp(0.0, 1.0));
+                broken = !matches!(
+                    status.connection_state(),
+                    Ok(NetworkingConnectionState::Connected)
+                );
+            }
+            let quality = if broken {
+                ConnectionQuality::Bad
+            } else {
+                thresholds.classify(rtt, loss)
+            };
+            if self.connection_quality(peer) != quality {
+                self.quality.peers.insert(peer, quality);
+                self.net_events
+                    .push_back(NetEvent::ConnectionQualityChanged { peer, quality })
+            }
+        }
+    }
+}
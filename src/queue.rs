@@ -0,0 +1,107 @@
+use crate::{NetError, PeerId, Priority, Reliability};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    #[default]
+    Error,
+    DropOldest,
+    Block,
+}
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct SendQueue {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+impl Default for SendQueue {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            overflow: OverflowPolicy::default(),
+        }
+    }
+}
+type Entry = (Vec<u8>, Reliability, Priority);
+pub(crate) enum Pushed {
+    Queued,
+    Dropped(Priority),
+    Full(Vec<Entry>),
+}
+#[derive(Default)]
+pub(crate) struct SendQueues(Mutex<HashMap<PeerId, VecDeque<Entry>>>);
+impl SendQueues {
+    pub(crate) fn push(
+        &self,
+        config: SendQueue,
+        peer: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+        priority: Priority,
+    ) -> Result<Pushed, NetError> {
+        let mut queues = self.0.lock().unwrap();
+        let queue = queues.entry(peer).or_default();
+        if queue.len() < config.capacity {
+            queue.push_back((packet, reliability, priority));
+            return Ok(Pushed::Queued);
+        }
+        match config.overflow {
+            OverflowPolicy::Error => Err(NetError::SendQueueFull(peer)),
+            OverflowPolicy::DropOldest => {
+                let victim = queue
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, r, p))| {
+                        *p <= priority
+                            && matches!(
+                                r,
+                                Reliability::Unreliable
+                                    | Reliability::Sequenced
+                                    | Reliability::BestEffort
+                            )
+                    })
+                    .min_by_key(|(i, (_, _, p))| (*p, *i))
+                    .map(|(i, _)| i);
+                let Some(i) = victim else {
+                    return Err(NetError::SendQueueFull(peer));
+                };
+                let (_, _, dropped) = queue.remove(i).unwrap();
+                queue.push_back((packet, reliability, priority));
+                Ok(Pushed::Dropped(dropped))
+            }
+            OverflowPolicy::Block => {
+                let mut pending: Vec<Entry> = queue.drain(..).collect();
+                pending.sort_by_key(|(_, _, p)| std::cmp::Reverse(*p));
+                queue.push_back((packet, reliability, priority));
+                Ok(Pushed::Full(pending))
+            }
+        }
+    }
+    pub(crate) fn drain(&self) -> Vec<(PeerId, Vec<u8>, Reliability)> {
+        let mut queues = self.0.lock().unwrap();
+        let mut entries: Vec<(PeerId, Entry)> = queues
+            .iter_mut()
+            .flat_map(|(peer, queue)| queue.drain(..).map(|e| (*peer, e)))
+            .collect();
+        entries.sort_by_key(|(_, (_, _, p))| std::cmp::Reverse(*p));
+        entries
+            .into_iter()
+            .map(|(peer, (packet, reliability, _))| (peer, packet, reliability))
+            .collect()
+    }
+    pub(crate) fn len(&self, peer: PeerId) -> usize {
+        self.0.lock().unwrap().get(&peer).map_or(0, |q| q.len())
+    }
+    pub(crate) fn bytes(&self, peer: PeerId) -> usize {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&peer)
+            .map_or(0, |q| q.iter().map(|(packet, _, _)| packet.len()).sum())
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().values().all(|q| q.is_empty())
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.0.get_mut().unwrap().remove(&peer);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::PeerId;
+use std::collections::HashMap;
+use std::time::Instant;
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub enum ThrottlePolicy {
+    Drop,
+    Notify,
+    Kick,
+}
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    pub messages: u32,
+    pub bytes: u64,
+    pub policy: ThrottlePolicy,
+}
+struct Bucket {
+    messages: f64,
+    bytes: f64,
+    last: Instant,
+    throttled: bool,
+}
+#[derive(Default)]
+pub(crate) struct Limiter {
+    buckets: HashMap<PeerId, Bucket>,
+    pub(crate) overrides: HashMap<PeerId, Option<RateLimit>>,
+}
+impl Limiter {
+    pub(crate) fn limit(&self, peer: PeerId, default: Option<RateLimit>) -> Option<RateLimit> {
+        self.overrides.get(&peer).copied().unwrap_or(default)
+    }
+    pub(crate) fn allow(&mut self, peer: PeerId, size: usize, limit: RateLimit) -> Option<bool> {
+        let (messages, bytes) = (limit.messages as f64, limit.bytes as f64);
+        let b = self.buckets.entry(peer).or_insert_with(|| Bucket {
+            messages,
+            bytes,
+            last: Instant::now(),
+            throttled: false,
+        });
+        let dt = b.last.elapsed().as_secs_f64();
+        b.last = Instant::now();
+        b.messages = (b.messages + dt * messages).min(messages);
+        b.bytes = (b.bytes + dt * bytes).min(bytes);
+        if b.messages >= 1.0 && b.bytes >= size as f64 {
+            b.messages -= 1.0;
+            b.bytes -= size as f64;
+            b.throttled = false;
+            return None;
+        }
+        let first = !b.throttled;
+        b.throttled = true;
+        Some(first)
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.buckets.remove(&peer);
+        self.overrides.remove(&peer);
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_rate_limit() {
+    let limit = RateLimit {
+        messages: 2,
+        bytes: 100,
+        policy: ThrottlePolicy::Drop,
+    };
+    let mut limiter = Limiter::default();
+    assert_eq!(limiter.allow(PeerId(1), 10, limit), None);
+    assert_eq!(limiter.allow(PeerId(1), 10, limit), None);
+    assert_eq!(limiter.allow(PeerId(1), 10, limit), Some(true));
+    assert_eq!(limiter.allow(PeerId(1), 10, limit), Some(false));
+    assert_eq!(limiter.allow(PeerId(2), 200, limit), Some(true));
+    assert_eq!(limiter.allow(PeerId(2), 50, limit), None);
+    limiter.overrides.insert(PeerId(2), None);
+    assert_eq!(limiter.limit(PeerId(1), Some(limit)), Some(limit));
+    assert_eq!(limiter.limit(PeerId(2), Some(limit)), None);
+    limiter.remove(PeerId(1));
+    assert_eq!(limiter.allow(PeerId(1), 10, limit), None);
+}
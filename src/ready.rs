@@ -0,0 +1,111 @@
+use crate::{Client, ClientTrait, MsgType, NetEvent, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+#[derive(Default)]
+pub(crate) struct Ready {
+    pub(crate) peers: HashMap<PeerId, bool>,
+    countdown: Option<Duration>,
+    deadline: Option<Instant>,
+    fired: bool,
+}
+impl Client {
+    pub fn set_ready(&mut self, ready: bool) {
+        if self.is_host() {
+            self.apply_ready(self.my_id(), ready)
+        } else {
+            let packet = encode(&MsgType::SetReady(ready));
+            let _ = self.send_packet(self.host_id(), packet, Reliability::Reliable);
+        }
+    }
+    pub fn is_ready(&self, peer: PeerId) -> bool {
+        self.ready.peers.get(&peer).copied().unwrap_or(false)
+    }
+    pub fn readiness(&self) -> HashMap<PeerId, bool> {
+        let mut peers: HashMap<PeerId, bool> = self
+            .peers()
+            .into_iter()
+            .map(|p| (p, self.is_ready(p)))
+            .collect();
+        if !self.is_dedicated() || !self.is_host() {
+            peers.insert(self.my_id(), self.is_ready(self.my_id()));
+        }
+        peers
+    }
+    pub fn all_ready(&self) -> bool {
+        let readiness = self.readiness();
+        !readiness.is_empty() && readiness.values().all(|r| *r)
+    }
+    pub fn set_ready_countdown(&mut self, countdown: Option<Duration>) {
+        self.ready.countdown = countdown
+    }
+    pub fn clear_ready(&mut self) {
+        if !self.is_host() {
+            return;
+        }
+        for peer in self.ready.peers.keys().copied().collect::<Vec<_>>() {
+            self.apply_ready(peer, false)
+        }
+    }
+    pub(crate) fn receive_set_ready(&mut self, src: PeerId, ready: bool) {
+        if self.is_host() {
+            self.apply_ready(src, ready)
+        }
+    }
+    pub(crate) fn receive_ready(&mut self, src: PeerId, peer: PeerId, ready: bool) {
+        if src == self.host_id() && !self.is_host() {
+            self.set_readiness(peer, ready)
+        }
+    }
+    pub(crate) fn sync_ready(&self, peer: PeerId) {
+        for (p, ready) in &self.ready.peers {
+            let packet = encode(&MsgType::Ready {
+                peer: *p,
+                ready: *ready,
+            });
+            let _ = self.send_packet(peer, packet, Reliability::Reliable);
+        }
+    }
+    pub(crate) fn pump_ready(&mut self) {
+        if (self.ready.peers.is_empty() && self.ready.deadline.is_none())
+            || !self.is_connected()
+            || !self.is_host()
+        {
+            return;
+        }
+        if !self.all_ready() {
+            self.ready.fired = false;
+            if self.ready.deadline.take().is_some() {
+                self.net_events.push_back(NetEvent::ReadyCountdown(None))
+            }
+            return;
+        }
+        if self.ready.fired {
+            return;
+        }
+        match (self.ready.countdown, self.ready.deadline) {
+            (Some(countdown), None) => {
+                self.ready.deadline = Some(Instant::now() + countdown);
+                self.net_events
+                    .push_back(NetEvent::ReadyCountdown(Some(countdown)))
+            }
+            (Some(_), Some(deadline)) if Instant::now() < deadline => {}
+            _ => {
+                self.ready.deadline = None;
+                self.ready.fired = true;
+                self.net_events.push_back(NetEvent::AllReady)
+            }
+        }
+    }
+    fn apply_ready(&mut self, peer: PeerId, ready: bool) {
+        let packet = encode(&MsgType::Ready { peer, ready });
+        let _ = self.broadcast_packet(packet, Reliability::Reliable);
+        self.set_readiness(peer, ready)
+    }
+    fn set_readiness(&mut self, peer: PeerId, ready: bool) {
+        if self.ready.peers.insert(peer, ready).unwrap_or(false) != ready {
+            self.net_events
+                .push_back(NetEvent::ReadyChanged { peer, ready })
+        }
+    }
+}
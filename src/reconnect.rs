@@ -0,0 +1,90 @@
+use crate::{Client, ClientTrait, MsgType, NetEvent, PeerId, Reliability};
+use bitcode::encode;
+use std::time::{Duration, Instant};
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub max_attempts: Option<u32>,
+}
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(10),
+            max_attempts: Some(10),
+        }
+    }
+}
+impl Backoff {
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max)
+    }
+}
+pub(crate) struct Reconnect {
+    pub(crate) attempt: u32,
+    pub(crate) next: Instant,
+    pub(crate) old_id: PeerId,
+    pub(crate) host: PeerId,
+}
+impl Client {
+    pub(crate) fn reconnect(&mut self) {
+        let Some(mut r) = self.reconnect.take() else {
+            return;
+        };
+        let Some(backoff) = self.config.reconnect else {
+            return;
+        };
+        if r.attempt > 0 && self.is_client() {
+            let packet = encode(&MsgType::Rejoin(r.old_id));
+            let _ = self.send_packet(r.host, packet, Reliability::Reliable);
+            self.net_events.push_back(NetEvent::Reconnected);
+            return;
+        }
+        if Instant::now() < r.next {
+            self.reconnect = Some(r);
+            return;
+        }
+        if backoff.max_attempts.is_some_and(|m| r.attempt >= m) {
+            self.net_events.push_back(NetEvent::ReconnectFailed);
+            return;
+        }
+        r.attempt += 1;
+        r.next = Instant::now() + backoff.delay(r.attempt);
+        self.net_events
+            .push_back(NetEvent::Reconnecting { attempt: r.attempt });
+        #[cfg(feature = "tangled")]
+        let runtime = self.ip_runtime();
+        #[cfg(feature = "tangled")]
+        let _guard = runtime.as_ref().map(tokio::runtime::Handle::enter);
+        #[cfg(feature = "tangled")]
+        if let Some(ip) = &mut self.ip_client {
+            let _ = ip.rejoin();
+            self.reconnect = Some(r);
+            return;
+        }
+        #[cfg(feature = "steam")]
+        if let Some(steam) = &mut self.steam_client {
+            steam.connect(r.host.into());
+        }
+        self.reconnect = Some(r);
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_backoff() {
+    let backoff = Backoff {
+        initial: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+        max_attempts: None,
+    };
+    let delays: Vec<_> = (1..=6).map(|attempt| backoff.delay(attempt)).collect();
+    assert_eq!(
+        delays,
+        [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis)
+    );
+    assert_eq!(backoff.delay(0), backoff.initial);
+    assert_eq!(backoff.delay(u32::MAX), backoff.max);
+}
@@ -0,0 +1,101 @@
+use crate::{Channel, Client, ClientTypeRef, Compression, Message, NetError, PeerId, Reliability};
+use bitcode::{Decode, DecodeOwned, Encode, decode, encode};
+use std::collections::HashMap;
+pub trait NetMessage: Encode + DecodeOwned {
+    const ID: u32;
+}
+#[derive(Encode, Decode)]
+struct Tagged {
+    id: u32,
+    data: Vec<u8>,
+}
+type Handler = Box<dyn FnMut(ClientTypeRef, Message<&[u8]>) -> Result<(), NetError> + Send + Sync>;
+#[derive(Default)]
+pub struct MessageRegistry {
+    handlers: HashMap<u32, Handler>,
+}
+impl MessageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn register<T, F>(&mut self, mut f: F) -> &mut Self
+    where
+        T: NetMessage,
+        F: FnMut(ClientTypeRef, Message<T>) + Send + Sync + 'static,
+    {
+        self.register_id(T::ID, move |c, m| {
+            let data = decode(m.data).map_err(|_| NetError::Malformed)?;
+            f(
+                c,
+                Message {
+                    src: m.src,
+                    channel: m.channel,
+                    tick: m.tick,
+                    data,
+                },
+            );
+            Ok(())
+        })
+    }
+    pub fn register_id<F>(&mut self, id: u32, f: F) -> &mut Self
+    where
+        F: FnMut(ClientTypeRef, Message<&[u8]>) -> Result<(), NetError> + Send + Sync + 'static,
+    {
+        self.handlers.insert(id, Box::new(f));
+        self
+    }
+    pub fn is_registered(&self, id: u32) -> bool {
+        self.handlers.contains_key(&id)
+    }
+    pub fn dispatch(&mut self, client: &mut Client) -> Result<(), NetError> {
+        let mut result = Ok(());
+        client.recv_raw(|c, m| {
+            let outcome = match decode::<Tagged>(m.data) {
+                Ok(tagged) => match self.handlers.get_mut(&tagged.id) {
+                    Some(handler) => handler(
+                        c,
+                        Message {
+                            src: m.src,
+                            channel: m.channel,
+                            tick: m.tick,
+                            data: &tagged.data,
+                        },
+                    ),
+                    None => Err(NetError::UnknownMessage(tagged.id)),
+                },
+                Err(_) => Err(NetError::Malformed),
+            };
+            if result.is_ok() {
+                result = outcome
+            }
+        });
+        result
+    }
+}
+impl Client {
+    pub fn send_message<T: NetMessage>(
+        &self,
+        dest: PeerId,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let tagged = Tagged {
+            id: T::ID,
+            data: encode(data),
+        };
+        self.send_on(Channel::DEFAULT, dest, &tagged, reliability, compression)
+    }
+    pub fn broadcast_message<T: NetMessage>(
+        &self,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let tagged = Tagged {
+            id: T::ID,
+            data: encode(data),
+        };
+        self.broadcast_on(Channel::DEFAULT, &tagged, reliability, compression)
+    }
+}
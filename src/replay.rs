@@ -0,0 +1,114 @@
+use crate::{Client, NetEvent, PeerId, Reliability};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+const WINDOW: u64 = 128;
+const HEADER: usize = 9;
+#[derive(Default)]
+struct Window {
+    last: u64,
+    seen: u128,
+}
+impl Window {
+    fn accept(&mut self, nonce: u64) -> bool {
+        if nonce > self.last {
+            let delta = nonce - self.last;
+            self.seen = if delta >= WINDOW {
+                0
+            } else {
+                self.seen << delta
+            } | 1;
+            self.last = nonce;
+            return true;
+        }
+        let back = self.last - nonce;
+        if back >= WINDOW {
+            return false;
+        }
+        let bit = 1 << back;
+        let fresh = self.seen & bit == 0;
+        self.seen |= bit;
+        fresh
+    }
+}
+#[derive(Default)]
+pub(crate) struct Replay {
+    windows: HashMap<(PeerId, u8), Window>,
+    detected: HashMap<PeerId, u64>,
+}
+impl Replay {
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.windows.retain(|(p, _), _| *p != peer);
+    }
+}
+pub(crate) fn stamp(nonces: &[AtomicU64; 3], reliability: Reliability, packet: Vec<u8>) -> Vec<u8> {
+    let lane = match reliability {
+        Reliability::Reliable => 0,
+        Reliability::ReliableUnordered => 1,
+        _ => 2,
+    };
+    let nonce = nonces[lane as usize].fetch_add(1, Ordering::Relaxed) + 1;
+    let mut out = Vec::with_capacity(HEADER + packet.len());
+    out.push(lane);
+    out.extend_from_slice(&nonce.to_le_bytes());
+    out.extend(packet);
+    out
+}
+pub(crate) fn strip(mut data: Vec<u8>) -> Option<(u8, u64, Vec<u8>)> {
+    if data.len() < HEADER || data[0] > 2 {
+        return None;
+    }
+    let nonce = u64::from_le_bytes(data[1..HEADER].try_into().unwrap());
+    let lane = data[0];
+    data.drain(..HEADER);
+    Some((lane, nonce, data))
+}
+impl Client {
+    pub(crate) fn replay_protected(&self) -> bool {
+        #[cfg(feature = "encrypt")]
+        if self.config.psk.is_some() {
+            return true;
+        }
+        #[cfg(feature = "hmac")]
+        if self.config.hmac_key.is_some() {
+            return true;
+        }
+        false
+    }
+    pub(crate) fn fresh(&mut self, src: PeerId, lane: u8, nonce: u64) -> bool {
+        if self
+            .replay
+            .windows
+            .entry((src, lane))
+            .or_default()
+            .accept(nonce)
+        {
+            return true;
+        }
+        *self.replay.detected.entry(src).or_default() += 1;
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.net_events
+            .push_back(NetEvent::ReplayDetected { peer: src });
+        false
+    }
+    pub(crate) fn check_replay(&mut self, src: PeerId, data: Vec<u8>) -> Option<Vec<u8>> {
+        let (lane, nonce, data) = strip(data)?;
+        self.fresh(src, lane, nonce).then_some(data)
+    }
+    pub fn replays_detected(&self, peer: PeerId) -> u64 {
+        self.replay.detected.get(&peer).copied().unwrap_or(0)
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_replay_window() {
+    let mut window = Window::default();
+    assert!(window.accept(1));
+    assert!(!window.accept(1));
+    assert!(window.accept(5));
+    assert!(window.accept(3));
+    assert!(!window.accept(3));
+    assert!(window.accept(5 + WINDOW));
+    assert!(!window.accept(5));
+    assert!(window.accept(6));
+    assert!(!window.accept(6));
+}
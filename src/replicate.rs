@@ -0,0 +1,196 @@
+use crate::{Channel, Client, ClientTrait, Compression, NetEntity, Relevancy, Reliability};
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::{With, Without};
+use bevy_ecs::reflect::{AppTypeRegistry, ReflectComponent};
+use bevy_ecs::resource::Resource;
+use bevy_ecs::world::World;
+use bevy_reflect::TypeRegistry;
+use bevy_reflect::serde::{TypedReflectDeserializer, TypedReflectSerializer};
+use bitcode::{Decode, Encode, decode};
+use serde::de::DeserializeSeed;
+use std::collections::HashMap;
+#[derive(Component, Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replicated;
+#[derive(Encode, Decode)]
+struct Update {
+    entity: u64,
+    components: Vec<(String, Vec<u8>)>,
+}
+#[derive(Resource)]
+pub struct ReplicationRegistry {
+    pub channel: Channel,
+    paths: Vec<String>,
+    entities: HashMap<u64, Entity>,
+    next: u64,
+}
+impl Default for ReplicationRegistry {
+    fn default() -> Self {
+        Self {
+            channel: Channel(u8::MAX),
+            paths: Vec::new(),
+            entities: HashMap::new(),
+            next: 0,
+        }
+    }
+}
+impl ReplicationRegistry {
+    pub fn register(&mut self, type_path: &str) {
+        if !self.paths.iter().any(|p| p == type_path) {
+            self.paths.push(type_path.to_owned())
+        }
+    }
+    pub fn unregister(&mut self, type_path: &str) {
+        self.paths.retain(|p| p != type_path)
+    }
+    pub fn registered(&self) -> impl Iterator<Item = &str> {
+        self.paths.iter().map(|p| p.as_str())
+    }
+    pub fn entity(&self, id: NetEntity) -> Option<Entity> {
+        self.entities.get(&id.0).copied()
+    }
+}
+fn serialize(
+    registry: &TypeRegistry,
+    path: &str,
+    world: &World,
+    entity: Entity,
+) -> Option<Vec<u8>> {
+    let reflect = registry
+        .get_with_type_path(path)?
+        .data::<ReflectComponent>()?
+        .reflect(world.entity(entity))?;
+    postcard::to_allocvec(&TypedReflectSerializer::new(
+        reflect.as_partial_reflect(),
+        registry,
+    ))
+    .ok()
+}
+fn apply(registry: &TypeRegistry, path: &str, world: &mut World, entity: Entity, data: &[u8]) {
+    let Some(registration) = registry.get_with_type_path(path) else {
+        return;
+    };
+    let Some(component) = registration.data::<ReflectComponent>() else {
+        return;
+    };
+    let mut de = postcard::Deserializer::from_bytes(data);
+    let Ok(value) = TypedReflectDeserializer::new(registration, registry).deserialize(&mut de)
+    else {
+        return;
+    };
+    let mut entity = world.entity_mut(entity);
+    if component.contains(&entity) {
+        component.apply(&mut entity, value.as_ref())
+    } else {
+        component.insert(&mut entity, value.as_ref(), registry)
+    }
+}
+pub fn send_replicated(world: &mut World) {
+    let Some(client) = world.get_resource::<Client>() else {
+        return;
+    };
+    if !client.is_host() {
+        return;
+    }
+    let unassigned: Vec<Entity> = world
+        .query_filtered::<Entity, (With<Replicated>, Without<NetEntity>)>()
+        .iter(world)
+        .collect();
+    let mut replication = world.resource_mut::<ReplicationRegistry>();
+    let mut assigned = Vec::new();
+    for entity in unassigned {
+        let id = replication.next;
+        replication.next += 1;
+        replication.entities.insert(id, entity);
+        assigned.push((entity, NetEntity(id)));
+    }
+    for (entity, id) in assigned {
+        world.entity_mut(entity).insert(id);
+    }
+    let mut query =
+        world.query_filtered::<(Entity, &NetEntity, Option<&Relevancy>), With<Replicated>>();
+    let types = world.resource::<AppTypeRegistry>().clone();
+    let types = types.read();
+    let replication = world.resource::<ReplicationRegistry>();
+    let updates: Vec<(Update, Option<Relevancy>)> = query
+        .iter(world)
+        .map(|(entity, id, relevancy)| {
+            let update = Update {
+                entity: id.0,
+                components: replication
+                    .paths
+                    .iter()
+                    .filter_map(|path| {
+                        Some((path.clone(), serialize(&types, path, world, entity)?))
+                    })
+                    .collect(),
+            };
+            (update, relevancy.copied())
+        })
+        .collect();
+    let client = world.resource::<Client>();
+    for (update, relevancy) in updates {
+        let _ = match relevancy {
+            Some(relevancy) => client.broadcast_relevant(
+                replication.channel,
+                &relevancy,
+                &update,
+                Reliability::Reliable,
+                Compression::Uncompressed,
+            ),
+            None => client.broadcast_on(
+                replication.channel,
+                &update,
+                Reliability::Reliable,
+                Compression::Uncompressed,
+            ),
+        };
+    }
+}
+pub fn receive_replicated(world: &mut World) {
+    let channel = world.resource::<ReplicationRegistry>().channel;
+    let Some(mut client) = world.get_resource_mut::<Client>() else {
+        return;
+    };
+    if client.is_host() {
+        return;
+    }
+    let host = client.host_id();
+    let messages = client.take_channel(channel);
+    let types = world.resource::<AppTypeRegistry>().clone();
+    let types = types.read();
+    for m in messages {
+        let Ok(update) = decode::<Update>(&m.data) else {
+            continue;
+        };
+        if m.src != host {
+            continue;
+        }
+        let known = world
+            .resource::<ReplicationRegistry>()
+            .entities
+            .get(&update.entity)
+            .copied()
+            .filter(|entity| world.get_entity(*entity).is_ok());
+        let entity = match known {
+            Some(entity) => entity,
+            None => {
+                let entity = world.spawn((Replicated, NetEntity(update.entity))).id();
+                world
+                    .resource_mut::<ReplicationRegistry>()
+                    .entities
+                    .insert(update.entity, entity);
+                entity
+            }
+        };
+        let replication = world.resource::<ReplicationRegistry>();
+        let allowed: Vec<(String, Vec<u8>)> = update
+            .components
+            .into_iter()
+            .filter(|(path, _)| replication.paths.contains(path))
+            .collect();
+        for (path, data) in allowed {
+            apply(&types, &path, world, entity, &data)
+        }
+    }
+}
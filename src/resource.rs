@@ -0,0 +1,31 @@
+use crate::{Client, ClientTrait, MsgType, NetError, Reliability};
+use bitcode::{DecodeOwned, Encode, decode, encode};
+use std::collections::{HashMap, HashSet};
+#[derive(Default)]
+pub(crate) struct Resources {
+    pub(crate) data: HashMap<String, Vec<u8>>,
+    pub(crate) updated: HashSet<String>,
+}
+impl Client {
+    pub fn sync_resource<T: Encode>(&mut self, name: &str, value: &T) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Ok(());
+        }
+        let data = encode(value);
+        if self.resources.data.get(name) == Some(&data) {
+            return Ok(());
+        }
+        self.resources.data.insert(name.to_owned(), data.clone());
+        let packet = encode(&MsgType::Resource {
+            name: name.to_owned(),
+            data,
+        });
+        self.broadcast_packet(packet, Reliability::Reliable)
+    }
+    pub fn take_resource<T: DecodeOwned>(&mut self, name: &str) -> Option<T> {
+        if !self.resources.updated.remove(name) {
+            return None;
+        }
+        decode(self.resources.data.get(name)?).ok()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{Channel, Client, ClientTrait, MsgType, NetError, NetEvent, PeerId, Reliability};
+use bitcode::{Decode, Encode, encode};
+use std::collections::HashMap;
+use std::ops::{BitAnd, BitOr};
+#[derive(Encode, Decode, Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct Role(pub u32);
+impl Role {
+    pub const NONE: Self = Self(0);
+    pub const PLAYER: Self = Self(1);
+    pub const ADMIN: Self = Self(1 << 1);
+    pub const MUTED: Self = Self(1 << 2);
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+impl BitOr for Role {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+impl BitAnd for Role {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+#[derive(Default)]
+pub(crate) struct Roles {
+    pub(crate) peers: HashMap<PeerId, Role>,
+    channels: HashMap<Channel, Role>,
+}
+impl Roles {
+    fn get(&self, peer: PeerId) -> Role {
+        self.peers.get(&peer).copied().unwrap_or_default()
+    }
+    fn permits(&self, peer: PeerId, channel: Channel) -> bool {
+        self.channels
+            .get(&channel)
+            .is_none_or(|required| self.get(peer).contains(*required))
+    }
+}
+impl Client {
+    pub fn set_role(&mut self, peer: PeerId, role: Role) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Ok(());
+        }
+        self.broadcast_packet(encode(&MsgType::Role { peer, role }), Reliability::Reliable)?;
+        self.apply_role(peer, role);
+        Ok(())
+    }
+    pub fn role(&self, peer: PeerId) -> Role {
+        self.roles.get(peer)
+    }
+    pub fn my_role(&self) -> Role {
+        self.role(self.my_id())
+    }
+    pub fn has_role(&self, peer: PeerId, role: Role) -> bool {
+        self.role(peer).contains(role)
+    }
+    pub fn require_role(&mut self, channel: Channel, role: Role) {
+        self.roles.channels.insert(channel, role);
+    }
+    pub fn clear_required_role(&mut self, channel: Channel) {
+        self.roles.channels.remove(&channel);
+    }
+    pub(crate) fn may_send(&self, channel: Channel) -> Result<(), NetError> {
+        if self.is_host() || self.roles.permits(self.my_id(), channel) {
+            Ok(())
+        } else {
+            Err(NetError::ChannelBlocked(channel))
+        }
+    }
+    pub(crate) fn may_receive(&self, src: PeerId, channel: Channel) -> bool {
+        src == self.host_id() || self.roles.permits(src, channel)
+    }
+    pub(crate) fn sync_roles(&self, peer: PeerId) {
+        for (p, role) in &self.roles.peers {
+            let packet = encode(&MsgType::Role {
+                peer: *p,
+                role: *role,
+            });
+            let _ = self.send_packet(peer, packet, Reliability::Reliable);
+        }
+    }
+    pub(crate) fn receive_role(&mut self, src: PeerId, peer: PeerId, role: Role) {
+        if src == self.host_id() && !self.is_host() {
+            self.apply_role(peer, role)
+        }
+    }
+    fn apply_role(&mut self, peer: PeerId, role: Role) {
+        let previous = if role == Role::NONE {
+            self.roles.peers.remove(&peer)
+        } else {
+            self.roles.peers.insert(peer, role)
+        };
+        if previous.unwrap_or_default() != role {
+            self.net_events
+                .push_back(NetEvent::RoleChanged { peer, role })
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Channel, Client, ClientTrait, Compression, PeerId, Reliability, TickInputs};
+use bitcode::{Decode, DecodeOwned, Encode, decode};
+use std::collections::{BTreeMap, HashMap};
+#[derive(Encode, Decode)]
+struct Input<T> {
+    peer: PeerId,
+    tick: u64,
+    input: T,
+}
+pub struct Rollback<T> {
+    channel: Channel,
+    pub input_delay: u64,
+    confirmed: BTreeMap<u64, HashMap<PeerId, T>>,
+    predicted: HashMap<(u64, PeerId), T>,
+    last: HashMap<PeerId, (u64, T)>,
+    simulated: Option<u64>,
+}
+impl<T: Encode + DecodeOwned + Clone + Default + PartialEq> Rollback<T> {
+    pub fn new(channel: Channel, input_delay: u64) -> Self {
+        Self {
+            channel,
+            input_delay,
+            confirmed: BTreeMap::new(),
+            predicted: HashMap::new(),
+            last: HashMap::new(),
+            simulated: None,
+        }
+    }
+    pub fn submit(&mut self, client: &Client, tick: u64, input: T) -> u64 {
+        let tick = tick + self.input_delay;
+        let input = Input {
+            peer: client.my_id(),
+            tick,
+            input,
+        };
+        let _ = client.broadcast_on(
+            self.channel,
+            &input,
+            Reliability::Reliable,
+            Compression::Uncompressed,
+        );
+        self.confirm(input.peer, tick, input.input);
+        tick
+    }
+    fn confirm(&mut self, peer: PeerId, tick: u64, input: T) -> Option<u64> {
+        if self.last.get(&peer).is_none_or(|(t, _)| *t <= tick) {
+            self.last.insert(peer, (tick, input.clone()));
+        }
+        let mispredicted = self
+            .predicted
+            .remove(&(tick, peer))
+            .is_some_and(|predicted| predicted != input);
+        self.confirmed.entry(tick).or_default().insert(peer, input);
+        mispredicted.then_some(tick)
+    }
+    pub fn poll(&mut self, client: &mut Client) -> Option<u64> {
+        let host = client.is_host();
+        let mut rollback = None;
+        for m in client.take_channel(self.channel) {
+            let Ok(input) = decode::<Input<T>>(&m.data) else {
+                continue;
+            };
+            if host {
+                if input.peer != m.src {
+                    continue;
+                }
+                for peer in client.peers().into_iter().filter(|p| *p != m.src) {
+                    let _ = client.send_on(
+                        self.channel,
+                        peer,
+                        &input,
+                        Reliability::Reliable,
+                        Compression::Uncompressed,
+                    );
+                }
+            } else if m.src != client.host_id() {
+                continue;
+            }
+            if let Some(tick) = self.confirm(input.peer, input.tick, input.input) {
+                rollback = Some(rollback.map_or(tick, |r: u64| r.min(tick)))
+            }
+        }
+        rollback.filter(|tick| self.simulated.is_some_and(|s| *tick <= s))
+    }
+    pub fn inputs(&mut self, client: &Client, tick: u64) -> TickInputs<T> {
+        self.simulated = Some(self.simulated.map_or(tick, |s| s.max(tick)));
+        let mut peers = client.peers();
+        peers.push(client.my_id());
+        let mut inputs = HashMap::new();
+        for peer in peers {
+            let input = match self.confirmed.get(&tick).and_then(|c| c.get(&peer)) {
+                Some(input) => input.clone(),
+                None => {
+                    let input = self
+                        .last
+                        .get(&peer)
+                        .map(|(_, input)| input.clone())
+                        .unwrap_or_default();
+                    self.predicted.insert((tick, peer), input.clone());
+                    input
+                }
+            };
+            inputs.insert(peer, input);
+        }
+        TickInputs { tick, inputs }
+    }
+    pub fn is_predicted(&self, tick: u64) -> bool {
+        self.predicted.keys().any(|(t, _)| *t == tick)
+    }
+    pub fn confirmed_tick(&self) -> Option<u64> {
+        let first = self.predicted.keys().map(|(t, _)| *t).min();
+        match first {
+            Some(0) => None,
+            Some(t) => Some(t - 1),
+            None => self.simulated,
+        }
+    }
+    pub fn discard_before(&mut self, tick: u64) {
+        self.confirmed = self.confirmed.split_off(&tick);
+        self.predicted.retain(|(t, _), _| *t >= tick);
+    }
+}
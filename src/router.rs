@@ -0,0 +1,480 @@
+use crate::{
+    Channel, Client, ClientTrait, ClientTypeRef, Compression, Message, NetError, PeerId,
+    RecvBudget, Reliability, UResult,
+};
+use bitcode::{Decode, DecodeOwned, Encode, decode};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+const UNASSIGNED: usize = 1 << 20;
+#[derive(Encode, Decode, Copy, Debug, Clone, Hash, PartialEq, PartialOrd, Ord, Eq)]
+pub struct SessionId(pub u32);
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Quota {
+    pub max_peers: Option<usize>,
+    pub max_bandwidth: Option<usize>,
+    pub max_queued: Option<usize>,
+}
+#[derive(Copy, Debug, Clone, Hash, PartialEq, Eq)]
+pub enum QuotaKind {
+    Peers,
+    Bandwidth,
+    Queue,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouterEvent {
+    QuotaExceeded {
+        session: SessionId,
+        peer: PeerId,
+        kind: QuotaKind,
+    },
+    Evicted {
+        session: SessionId,
+        peer: PeerId,
+        kind: QuotaKind,
+    },
+}
+fn take(
+    queue: &mut VecDeque<Message<Vec<u8>>>,
+    queued: &mut usize,
+    budget: RecvBudget,
+) -> Vec<Message<Vec<u8>>> {
+    let (mut taken, mut bytes) = (Vec::new(), 0);
+    while let Some(len) = queue.front().map(|m| m.data.len()) {
+        if budget.messages.is_some_and(|max| taken.len() >= max)
+            || budget
+                .bytes
+                .is_some_and(|max| !taken.is_empty() && bytes + len > max)
+        {
+            break;
+        }
+        bytes += len;
+        taken.extend(queue.pop_front());
+    }
+    *queued -= bytes;
+    taken
+}
+struct Session {
+    peers: HashSet<PeerId>,
+    quota: Quota,
+    window: Instant,
+    bytes: usize,
+    throttled: bool,
+    queue: VecDeque<Message<Vec<u8>>>,
+    queued: usize,
+}
+impl Session {
+    fn new(quota: Quota) -> Self {
+        Self {
+            peers: HashSet::new(),
+            quota,
+            window: Instant::now(),
+            bytes: 0,
+            throttled: false,
+            queue: VecDeque::new(),
+            queued: 0,
+        }
+    }
+    fn take(&mut self, budget: RecvBudget) -> Vec<Message<Vec<u8>>> {
+        take(&mut self.queue, &mut self.queued, budget)
+    }
+    fn push(&mut self, session: SessionId, m: Message<Vec<u8>>) -> Option<RouterEvent> {
+        if self.window.elapsed() >= Duration::from_secs(1) {
+            self.window = Instant::now();
+            self.bytes = 0;
+            self.throttled = false;
+        }
+        self.bytes += m.data.len();
+        if self.quota.max_bandwidth.is_some_and(|max| self.bytes > max) {
+            if self.throttled {
+                return None;
+            }
+            self.throttled = true;
+            return Some(RouterEvent::QuotaExceeded {
+                session,
+                peer: m.src,
+                kind: QuotaKind::Bandwidth,
+            });
+        }
+        self.queued += m.data.len();
+        self.queue.push_back(m);
+        if !self.quota.max_queued.is_some_and(|max| self.queued > max) {
+            return None;
+        }
+        let mut usage: HashMap<PeerId, usize> = HashMap::new();
+        for m in &self.queue {
+            *usage.entry(m.src).or_default() += m.data.len();
+        }
+        let (peer, _) = usage.into_iter().max_by_key(|(_, n)| *n)?;
+        self.queue.retain(|m| m.src != peer);
+        self.queued = self.queue.iter().map(|m| m.data.len()).sum();
+        self.peers.remove(&peer);
+        Some(RouterEvent::Evicted {
+            session,
+            peer,
+            kind: QuotaKind::Queue,
+        })
+    }
+}
+struct Unassigned {
+    queue: VecDeque<Message<Vec<u8>>>,
+    queued: usize,
+    max_queued: usize,
+    dropped: usize,
+}
+impl Unassigned {
+    fn push(&mut self, m: Message<Vec<u8>>) {
+        if self.queued + m.data.len() > self.max_queued {
+            self.dropped += 1;
+            return;
+        }
+        self.queued += m.data.len();
+        self.queue.push_back(m);
+    }
+    fn take(&mut self, budget: RecvBudget) -> Vec<Message<Vec<u8>>> {
+        take(&mut self.queue, &mut self.queued, budget)
+    }
+}
+impl Default for Unassigned {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            queued: 0,
+            max_queued: UNASSIGNED,
+            dropped: 0,
+        }
+    }
+}
+pub struct Router {
+    client: Client,
+    sessions: HashMap<SessionId, Session>,
+    peers: HashMap<PeerId, SessionId>,
+    unassigned: Unassigned,
+    events: VecDeque<RouterEvent>,
+    budget: RecvBudget,
+    next: u32,
+}
+impl Router {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            sessions: HashMap::new(),
+            peers: HashMap::new(),
+            unassigned: Unassigned::default(),
+            events: VecDeque::new(),
+            budget: RecvBudget::default(),
+            next: 0,
+        }
+    }
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+    pub fn events(&mut self) -> impl Iterator<Item = RouterEvent> + '_ {
+        self.events.drain(..)
+    }
+    pub fn create_session(&mut self) -> SessionId {
+        self.create_session_with(Quota::default())
+    }
+    pub fn create_session_with(&mut self, quota: Quota) -> SessionId {
+        let id = SessionId(self.next);
+        self.next += 1;
+        self.sessions.insert(id, Session::new(quota));
+        id
+    }
+    pub fn set_quota(&mut self, session: SessionId, quota: Quota) {
+        if let Some(s) = self.sessions.get_mut(&session) {
+            s.quota = quota
+        }
+    }
+    pub fn close_session(&mut self, session: SessionId) -> Vec<PeerId> {
+        let peers: Vec<PeerId> = self
+            .sessions
+            .remove(&session)
+            .into_iter()
+            .flat_map(|s| s.peers)
+            .collect();
+        for peer in &peers {
+            self.peers.remove(peer);
+        }
+        peers
+    }
+    pub fn assign(&mut self, peer: PeerId, session: SessionId) -> bool {
+        let Some(s) = self.sessions.get(&session) else {
+            return false;
+        };
+        if s.quota.max_peers.is_some_and(|max| s.peers.len() >= max) {
+            self.events.push_back(RouterEvent::QuotaExceeded {
+                session,
+                peer,
+                kind: QuotaKind::Peers,
+            });
+            return false;
+        }
+        self.unassign(peer);
+        self.sessions.get_mut(&session).unwrap().peers.insert(peer);
+        self.peers.insert(peer, session);
+        true
+    }
+    pub fn unassign(&mut self, peer: PeerId) -> Option<SessionId> {
+        let session = self.peers.remove(&peer)?;
+        if let Some(s) = self.sessions.get_mut(&session) {
+            s.peers.remove(&peer);
+        }
+        Some(session)
+    }
+    pub fn session_of(&self, peer: PeerId) -> Option<SessionId> {
+        self.peers.get(&peer).copied()
+    }
+    pub fn sessions(&self) -> impl Iterator<Item = SessionId> + '_ {
+        self.sessions.keys().copied()
+    }
+    pub fn session_peers(&self, session: SessionId) -> impl Iterator<Item = PeerId> + '_ {
+        self.sessions
+            .get(&session)
+            .into_iter()
+            .flat_map(|s| s.peers.iter())
+            .copied()
+    }
+    pub fn queued(&self, session: SessionId) -> usize {
+        self.sessions.get(&session).map_or(0, |s| s.queued)
+    }
+    pub fn unassigned_queued(&self) -> usize {
+        self.unassigned.queued
+    }
+    pub fn unassigned_dropped(&self) -> usize {
+        self.unassigned.dropped
+    }
+    pub fn set_unassigned_limit(&mut self, bytes: usize) {
+        self.unassigned.max_queued = bytes
+    }
+    pub fn set_session_budget(&mut self, budget: RecvBudget) {
+        self.budget = budget
+    }
+    pub fn broadcast<T: Encode>(
+        &self,
+        session: SessionId,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        self.broadcast_on(session, Channel::DEFAULT, data, reliability, compression)
+    }
+    pub fn broadcast_on<T: Encode>(
+        &self,
+        session: SessionId,
+        channel: Channel,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self
+            .client
+            .pack_for(channel, None, data, reliability, compression)?;
+        for peer in self.session_peers(session) {
+            self.client.send_packet(peer, packet.clone(), reliability)?;
+        }
+        Ok(())
+    }
+    pub fn send<T: Encode>(
+        &self,
+        dest: PeerId,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        self.client.send(dest, data, reliability, compression)
+    }
+    pub fn update(&mut self) -> UResult {
+        let result = self.client.update();
+        let gone: Vec<PeerId> = self
+            .peers
+            .keys()
+            .filter(|p| !self.client.has_peer(**p))
+            .copied()
+            .collect();
+        for peer in gone {
+            self.unassign(peer);
+        }
+        result
+    }
+    fn poll(&mut self) {
+        let mut evicted = Vec::new();
+        let sessions = &mut self.sessions;
+        let peers = &self.peers;
+        let unassigned = &mut self.unassigned;
+        let events = &mut self.events;
+        self.client.recv_raw(|_, m| {
+            let m = Message {
+                src: m.src,
+                channel: m.channel,
+                tick: m.tick,
+                data: m.data.to_vec(),
+            };
+            let Some((id, session)) = peers
+                .get(&m.src)
+                .and_then(|id| Some((*id, sessions.get_mut(id)?)))
+            else {
+                unassigned.push(m);
+                return;
+            };
+            if let Some(event) = session.push(id, m) {
+                if let RouterEvent::Evicted { peer, .. } = event {
+                    evicted.push(peer)
+                }
+                events.push_back(event)
+            }
+        });
+        for peer in evicted {
+            self.peers.remove(&peer);
+            let _ = self.client.kick(peer, "quota exceeded");
+        }
+    }
+    pub fn recv<T, F>(&mut self, mut f: F)
+    where
+        F: FnMut(Option<SessionId>, ClientTypeRef, Message<T>),
+        T: DecodeOwned,
+    {
+        self.poll();
+        let Some(c) = self.client.client_ref() else {
+            return;
+        };
+        let mut malformed = Vec::new();
+        for m in self.unassigned.take(self.budget) {
+            match decoded(m) {
+                Ok(m) => f(None, c, m),
+                Err(peer) => malformed.push(peer),
+            }
+        }
+        for (id, session) in &mut self.sessions {
+            for m in session.take(self.budget) {
+                match decoded(m) {
+                    Ok(m) => f(Some(*id), c, m),
+                    Err(peer) => malformed.push(peer),
+                }
+            }
+        }
+        for peer in malformed {
+            self.client.malformed(peer)
+        }
+    }
+    pub fn recv_session<T, F>(&mut self, session: SessionId, mut f: F)
+    where
+        F: FnMut(ClientTypeRef, Message<T>),
+        T: DecodeOwned,
+    {
+        self.poll();
+        let Some(c) = self.client.client_ref() else {
+            return;
+        };
+        let Some(s) = self.sessions.get_mut(&session) else {
+            return;
+        };
+        let mut malformed = Vec::new();
+        for m in s.take(self.budget) {
+            match decoded(m) {
+                Ok(m) => f(c, m),
+                Err(peer) => malformed.push(peer),
+            }
+        }
+        for peer in malformed {
+            self.client.malformed(peer)
+        }
+    }
+}
+fn decoded<T: DecodeOwned>(m: Message<Vec<u8>>) -> Result<Message<T>, PeerId> {
+    Ok(Message {
+        src: m.src,
+        channel: m.channel,
+        tick: m.tick,
+        data: decode(&m.data).map_err(|_| m.src)?,
+    })
+}
+#[cfg(test)]
+#[test]
+fn test_unassigned_cap() {
+    let message = |len| Message {
+        src: PeerId(1),
+        channel: Channel::DEFAULT,
+        tick: None,
+        data: vec![0; len],
+    };
+    let mut unassigned = Unassigned {
+        max_queued: 10,
+        ..Default::default()
+    };
+    unassigned.push(message(6));
+    unassigned.push(message(6));
+    unassigned.push(message(4));
+    assert_eq!((unassigned.queued, unassigned.dropped), (10, 1));
+    assert_eq!(unassigned.take(RecvBudget::default()).len(), 2);
+    unassigned.push(message(6));
+    assert_eq!((unassigned.queued, unassigned.dropped), (6, 1));
+}
+#[cfg(test)]
+#[test]
+fn test_session_quotas() {
+    let message = |src, len| Message {
+        src: PeerId(src),
+        channel: Channel::DEFAULT,
+        tick: None,
+        data: vec![0; len],
+    };
+    let id = SessionId(0);
+    let mut session = Session::new(Quota {
+        max_bandwidth: Some(20),
+        ..Default::default()
+    });
+    assert_eq!(session.push(id, message(1, 15)), None);
+    assert_eq!(
+        session.push(id, message(2, 10)),
+        Some(RouterEvent::QuotaExceeded {
+            session: id,
+            peer: PeerId(2),
+            kind: QuotaKind::Bandwidth
+        })
+    );
+    assert_eq!(session.push(id, message(2, 10)), None);
+    assert_eq!(session.queued, 15);
+    let mut session = Session::new(Quota {
+        max_queued: Some(20),
+        ..Default::default()
+    });
+    session.peers.extend([PeerId(1), PeerId(2)]);
+    assert_eq!(session.push(id, message(1, 5)), None);
+    assert_eq!(session.push(id, message(2, 8)), None);
+    assert_eq!(
+        session.push(id, message(2, 8)),
+        Some(RouterEvent::Evicted {
+            session: id,
+            peer: PeerId(2),
+            kind: QuotaKind::Queue
+        })
+    );
+    assert_eq!(session.queued, 5);
+    assert_eq!(session.peers, HashSet::from([PeerId(1)]));
+}
+#[cfg(test)]
+#[test]
+fn test_session_budget() {
+    let message = |len| Message {
+        src: PeerId(1),
+        channel: Channel::DEFAULT,
+        tick: None,
+        data: vec![0; len],
+    };
+    let mut session = Session::new(Quota::default());
+    for len in [4, 4, 4, 30] {
+        session.push(SessionId(0), message(len));
+    }
+    let budget = |messages, bytes| RecvBudget { messages, bytes };
+    assert_eq!(session.take(budget(Some(2), None)).len(), 2);
+    assert_eq!(session.queued, 34);
+    assert_eq!(session.take(budget(None, Some(10))).len(), 1);
+    assert_eq!(session.take(budget(None, Some(10))).len(), 1);
+    assert_eq!(session.queued, 0);
+}
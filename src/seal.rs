@@ -0,0 +1,97 @@
+#[cfg(feature = "encrypt")]
+use crate::crypto;
+#[cfg(feature = "hmac")]
+use crate::mac;
+#[cfg(any(feature = "encrypt", feature = "hmac"))]
+use crate::replay;
+use crate::{NetConfig, NetError, Reliability};
+#[cfg(any(feature = "encrypt", feature = "hmac"))]
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, RwLock};
+#[derive(Default)]
+struct Keys {
+    #[cfg(feature = "encrypt")]
+    psk: Option<[u8; 32]>,
+    #[cfg(feature = "hmac")]
+    hmac_key: Option<Vec<u8>>,
+}
+impl Keys {
+    #[cfg(any(feature = "encrypt", feature = "hmac"))]
+    fn protected(&self) -> bool {
+        #[cfg(feature = "encrypt")]
+        if self.psk.is_some() {
+            return true;
+        }
+        #[cfg(feature = "hmac")]
+        if self.hmac_key.is_some() {
+            return true;
+        }
+        false
+    }
+}
+#[derive(Default)]
+struct Shared {
+    keys: RwLock<Keys>,
+    #[cfg(any(feature = "encrypt", feature = "hmac"))]
+    nonces: [AtomicU64; 3],
+}
+#[derive(Clone, Default)]
+pub(crate) struct Seal(Arc<Shared>);
+impl Seal {
+    #[allow(unused_variables, unused_mut)]
+    pub(crate) fn configure(&self, config: &NetConfig) {
+        let mut keys = self.0.keys.write().unwrap();
+        #[cfg(feature = "encrypt")]
+        {
+            keys.psk = config.psk;
+        }
+        #[cfg(feature = "hmac")]
+        if keys.hmac_key != config.hmac_key {
+            keys.hmac_key = config.hmac_key.clone()
+        }
+    }
+    #[allow(unused_variables)]
+    pub(crate) fn apply(
+        &self,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<Vec<u8>, NetError> {
+        let keys = self.0.keys.read().unwrap();
+        #[cfg(any(feature = "encrypt", feature = "hmac"))]
+        let packet = if keys.protected() {
+            replay::stamp(&self.0.nonces, reliability, packet)
+        } else {
+            packet
+        };
+        #[cfg(feature = "encrypt")]
+        let packet = match &keys.psk {
+            Some(key) => crypto::seal(key, &packet)?,
+            None => packet,
+        };
+        #[cfg(feature = "hmac")]
+        if let Some(key) = &keys.hmac_key {
+            return Ok(mac::sign(key, packet));
+        }
+        Ok(packet)
+    }
+}
+#[cfg(feature = "hmac")]
+#[cfg(test)]
+#[test]
+fn test_shared_nonces() {
+    let seal = Seal::default();
+    seal.configure(&NetConfig {
+        hmac_key: Some(b"shared".to_vec()),
+        ..Default::default()
+    });
+    let nonce = |seal: &Seal, reliability| {
+        let packet = mac::verify(b"shared", seal.apply(vec![7], reliability).unwrap()).unwrap();
+        let (lane, nonce, packet) = replay::strip(packet).unwrap();
+        assert_eq!(packet, vec![7]);
+        (lane, nonce)
+    };
+    assert_eq!(nonce(&seal, Reliability::Reliable), (0, 1));
+    assert_eq!(nonce(&seal.clone(), Reliability::Reliable), (0, 2));
+    assert_eq!(nonce(&seal, Reliability::Unreliable), (2, 1));
+    assert_eq!(nonce(&seal.clone(), Reliability::ReliableUnordered), (1, 1));
+}
@@ -0,0 +1,53 @@
+use crate::{Client, ClientTrait, MsgType, NetError, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, RandomState};
+pub(crate) fn random() -> u64 {
+    RandomState::new().hash_one(std::time::Instant::now())
+}
+pub fn mix(seed: u64, tick: u64) -> u64 {
+    let mut z = seed ^ tick.wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+#[derive(Default)]
+pub(crate) struct Seeds {
+    pub(crate) current: Option<(u32, u64)>,
+    pub(crate) pending: HashSet<PeerId>,
+    next: u32,
+}
+impl Seeds {
+    pub(crate) fn generate(&mut self, peers: Vec<PeerId>) -> (u32, u64) {
+        let id = self.next;
+        self.next += 1;
+        let seed = random();
+        self.current = Some((id, seed));
+        self.pending = peers.into_iter().collect();
+        (id, seed)
+    }
+    pub(crate) fn ack(&mut self, peer: PeerId, id: u32) -> Option<u64> {
+        let (current, seed) = self.current?;
+        (current == id && self.pending.remove(&peer) && self.pending.is_empty()).then_some(seed)
+    }
+}
+impl Client {
+    pub fn new_match_seed(&mut self) -> Result<Option<u64>, NetError> {
+        if !self.is_host() {
+            return Ok(None);
+        }
+        let (id, seed) = self.seeds.generate(self.peers());
+        let packet = encode(&MsgType::Seed { id, seed });
+        self.broadcast_packet(packet, Reliability::Reliable)?;
+        Ok(Some(seed))
+    }
+    pub fn match_seed(&self) -> Option<u64> {
+        self.seeds.current.map(|(_, seed)| seed)
+    }
+    pub fn tick_seed(&self, tick: u64) -> Option<u64> {
+        self.match_seed().map(|seed| mix(seed, tick))
+    }
+    pub fn seed_acknowledged(&self) -> bool {
+        self.seeds.current.is_some() && self.seeds.pending.is_empty()
+    }
+}
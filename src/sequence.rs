@@ -0,0 +1,27 @@
+use crate::{Channel, PeerId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+#[derive(Default)]
+pub(crate) struct Sequences {
+    outgoing: Mutex<HashMap<Channel, u32>>,
+    incoming: HashMap<(PeerId, Channel), u32>,
+}
+impl Sequences {
+    pub(crate) fn next(&self, channel: Channel) -> u32 {
+        let mut outgoing = self.outgoing.lock().unwrap();
+        let seq = outgoing.entry(channel).or_default();
+        *seq = seq.wrapping_add(1);
+        *seq
+    }
+    pub(crate) fn accept(&mut self, peer: PeerId, channel: Channel, seq: u32) -> bool {
+        let last = self.incoming.entry((peer, channel)).or_default();
+        if seq.wrapping_sub(*last) as i32 <= 0 {
+            return false;
+        }
+        *last = seq;
+        true
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.incoming.retain(|(p, _), _| *p != peer);
+    }
+}
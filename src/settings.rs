@@ -0,0 +1,38 @@
+use crate::{Client, ClientTrait, MsgType, NetError, NetEvent, PeerId, Reliability};
+use bitcode::{DecodeOwned, Encode, decode, encode};
+impl Client {
+    pub fn set_settings<T: Encode>(&mut self, value: &T) -> Result<(), NetError> {
+        if !self.is_host() {
+            return Ok(());
+        }
+        let data = encode(value);
+        if self.settings.as_ref() == Some(&data) {
+            return Ok(());
+        }
+        self.broadcast_packet(
+            encode(&MsgType::Settings(data.clone())),
+            Reliability::Reliable,
+        )?;
+        self.settings = Some(data);
+        Ok(())
+    }
+    pub fn settings<T: DecodeOwned>(&self) -> Option<T> {
+        decode(self.settings.as_ref()?).ok()
+    }
+    pub fn settings_raw(&self) -> Option<&[u8]> {
+        self.settings.as_deref()
+    }
+    pub(crate) fn sync_settings(&self, peer: PeerId) {
+        if let Some(data) = &self.settings {
+            let packet = encode(&MsgType::Settings(data.clone()));
+            let _ = self.send_packet(peer, packet, Reliability::Reliable);
+        }
+    }
+    pub(crate) fn receive_settings(&mut self, src: PeerId, data: Vec<u8>) {
+        if src != self.host_id() || self.is_host() || self.settings.as_ref() == Some(&data) {
+            return;
+        }
+        self.settings = Some(data);
+        self.net_events.push_back(NetEvent::SettingsChanged)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{Client, ClientTrait, DisconnectReason, MsgType, Reliability};
+use bitcode::encode;
+use std::time::{Duration, Instant};
+impl Client {
+    pub fn shutdown(&mut self, timeout: Duration) {
+        if self.is_connected() {
+            let packet = encode(&MsgType::Goodbye(DisconnectReason::Quit));
+            let _ = self.broadcast_packet(packet, Reliability::Reliable);
+            let deadline = Instant::now() + timeout;
+            self.flush();
+            while self.has_pending_sends() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(1));
+                self.flush();
+            }
+            #[cfg(feature = "tangled")]
+            if self.is_ip() {
+                std::thread::sleep(
+                    deadline
+                        .saturating_duration_since(Instant::now())
+                        .min(Duration::from_millis(20)),
+                );
+            }
+        }
+        self.end_session(DisconnectReason::Quit)
+    }
+    fn has_pending_sends(&self) -> bool {
+        !self.batches.is_empty()
+            || !self.queue.is_empty()
+            || !self.pacer.is_empty()
+            || !self.channel_caps.is_empty()
+    }
+}
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self.is_connected() {
+            self.shutdown(self.config.shutdown_timeout)
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{Channel, Client, ClientTrait, Compression, PeerId, Reliability};
+use bitcode::{Decode, DecodeOwned, Encode, decode, encode};
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+const HISTORY: usize = 32;
+#[derive(Encode, Decode)]
+enum Wire {
+    Snapshot {
+        id: u32,
+        base: Option<u32>,
+        data: Vec<u8>,
+    },
+    Ack(u32),
+}
+pub struct Snapshots<T> {
+    channel: Channel,
+    pub keyframe_interval: u32,
+    next: u32,
+    sent: VecDeque<(u32, Vec<u8>)>,
+    acked: HashMap<PeerId, u32>,
+    received: HashMap<PeerId, VecDeque<(u32, Vec<u8>)>>,
+    _marker: PhantomData<T>,
+}
+fn xor(data: &[u8], base: &[u8]) -> Vec<u8> {
+    data.iter()
+        .enumerate()
+        .map(|(i, b)| b ^ base.get(i).copied().unwrap_or(0))
+        .collect()
+}
+fn find(history: &VecDeque<(u32, Vec<u8>)>, id: u32) -> Option<&[u8]> {
+    history
+        .iter()
+        .find(|(i, _)| *i == id)
+        .map(|(_, d)| d.as_slice())
+}
+impl<T: Encode + DecodeOwned> Snapshots<T> {
+    pub fn new(channel: Channel, keyframe_interval: u32) -> Self {
+        Self {
+            channel,
+            keyframe_interval,
+            next: 0,
+            sent: VecDeque::with_capacity(HISTORY),
+            acked: HashMap::new(),
+            received: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+    pub fn send(&mut self, client: &Client, state: &T) {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        let data = encode(state);
+        let keyframe = self.keyframe_interval == 0 || id % self.keyframe_interval == 0;
+        for peer in client.peers() {
+            let base = self
+                .acked
+                .get(&peer)
+                .copied()
+                .filter(|_| !keyframe)
+                .and_then(|b| Some((b, find(&self.sent, b)?)));
+            let packet = match base {
+                Some((b, base)) => Wire::Snapshot {
+                    id,
+                    base: Some(b),
+                    data: xor(&data, base),
+                },
+                None => Wire::Snapshot {
+                    id,
+                    base: None,
+                    data: data.clone(),
+                },
+            };
+            let _ = client.send_on(
+                self.channel,
+                peer,
+                &packet,
+                Reliability::Unreliable,
+                Compression::Compressed,
+            );
+        }
+        if self.sent.len() == HISTORY {
+            self.sent.pop_front();
+        }
+        self.sent.push_back((id, data));
+    }
+    pub fn poll(&mut self, client: &mut Client) -> Vec<(PeerId, T)> {
+        let mut states = Vec::new();
+        for m in client.take_channel(self.channel) {
+            match decode::<Wire>(&m.data) {
+                Ok(Wire::Ack(id)) => {
+                    let newer = self
+                        .acked
+                        .get(&m.src)
+                        .is_none_or(|a| id.wrapping_sub(*a) < u32::MAX / 2);
+                    if newer {
+                        self.acked.insert(m.src, id);
+                    }
+                }
+                Ok(Wire::Snapshot { id, base, data }) => {
+                    let history = self.received.entry(m.src).or_default();
+                    if history
+                        .back()
+                        .is_some_and(|(last, _)| id.wrapping_sub(*last) >= u32::MAX / 2)
+                    {
+                        continue;
+                    }
+                    let data = match base {
+                        Some(b) => match find(history, b) {
+                            Some(base) => xor(&data, base),
+                            None => continue,
+                        },
+                        None => data,
+                    };
+                    let Ok(state) = decode::<T>(&data) else {
+                        continue;
+                    };
+                    if history.len() == HISTORY {
+                        history.pop_front();
+                    }
+                    history.push_back((id, data));
+                    let _ = client.send_on(
+                        self.channel,
+                        m.src,
+                        &Wire::Ack(id),
+                        Reliability::Unreliable,
+                        Compression::Uncompressed,
+                    );
+                    states.push((m.src, state));
+                }
+                Err(_) => {}
+            }
+        }
+        states
+    }
+    pub fn remove(&mut self, peer: PeerId) {
+        self.acked.remove(&peer);
+        self.received.remove(&peer);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::ConnectionStats;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetStats {
+    pub experiment: Option<String>,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub dropped: u64,
+    pub connections: ConnectionStats,
+}
+#[derive(Default)]
+pub(crate) struct Counters {
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+impl Counters {
+    pub(crate) fn sent(&self, bytes: usize, count: usize) {
+        self.messages_sent
+            .fetch_add(count as u64, Ordering::Relaxed);
+        self.bytes_sent
+            .fetch_add((bytes * count) as u64, Ordering::Relaxed);
+    }
+    pub(crate) fn received(&self, bytes: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+    pub(crate) fn snapshot(&self) -> NetStats {
+        NetStats {
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            ..Default::default()
+        }
+    }
+}
@@ -1,22 +1,112 @@
+use crate::access::Access;
 use crate::{
-    Client, ClientCallback, ClientMode, ClientTrait, ClientTypeRef, Compression, Message, NetError,
-    NetworkingInfo, PeerId, Reliability, pack, unpack,
+    Channel, Client, ClientCallback, ClientMode, ClientTrait, ClientTypeRef, Compression,
+    DisconnectReason, Event, NetError, NetEvent, NetworkingInfo, PeerId, PeerInfo, PendingJoin,
+    Reliability, frame, pack,
 };
-use bitcode::{DecodeOwned, Encode};
-use std::collections::HashMap;
+#[cfg(feature = "image")]
+use bevy_asset::RenderAssetUsages;
+use bitcode::Encode;
+use std::collections::{HashMap, VecDeque};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use steamworks::networking_sockets::{InvalidHandle, ListenSocket, NetConnection, NetPollGroup};
 use steamworks::networking_types::{
-    ListenSocketEvent, NetConnectionEnd, NetConnectionStatusChanged, NetworkingConnectionState,
-    NetworkingIdentity, NetworkingMessage, SendFlags,
+    AppNetConnectionEnd, ListenSocketEvent, NetConnectionEnd, NetConnectionRealTimeInfo,
+    NetConnectionStatusChanged, NetworkingConfigEntry, NetworkingConfigValue,
+    NetworkingConnectionState, NetworkingIdentity, NetworkingMessage, SendFlags,
 };
 use steamworks::{
-    CallbackResult, DistanceFilter, GameLobbyJoinRequested, LobbyId, LobbyType, SteamAPIInitError,
-    SteamError, SteamId,
+    CallbackResult, ComparisonFilter, DistanceFilter, GameLobbyJoinRequested,
+    GameRichPresenceJoinRequested, LobbyChatMsg, LobbyId, LobbyKey, LobbyType, NumberFilter,
+    SteamAPIInitError, SteamError, SteamId, StringFilter, StringFilterKind,
+    ValidateAuthTicketResponse,
 };
 #[cfg(feature = "log")]
 use tracing::info;
+#[cfg(feature = "image")]
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+pub(crate) const MAX_MESSAGE_SIZE: usize = 512 * 1024;
+const ICE_PRIVATE_PUBLIC: i32 = 2 | 4;
+const SDR_PENALTY_MS: i32 = 10_000;
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum SteamPath {
+    #[default]
+    Auto,
+    Relay,
+    Direct,
+}
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SteamConfig {
+    pub send_buffer_size: Option<u32>,
+    pub timeout_initial: Option<Duration>,
+    pub timeout_connected: Option<Duration>,
+    pub nagle: Option<Duration>,
+    pub send_rate_min: Option<u32>,
+    pub send_rate_max: Option<u32>,
+}
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum AvatarSize {
+    #[default]
+    Small,
+    Medium,
+    Large,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Avatar {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+#[cfg(feature = "image")]
+impl From<Avatar> for bevy_image::Image {
+    fn from(value: Avatar) -> Self {
+        bevy_image::Image::new(
+            Extent3d {
+                width: value.width,
+                height: value.height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            value.rgba,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+}
+#[derive(Copy, Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum LobbyDistance {
+    Close,
+    Default,
+    Far,
+    #[default]
+    Worldwide,
+}
+#[derive(Copy, Debug, Clone, Hash, PartialEq, Eq)]
+pub enum LobbyComparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LobbyFilter {
+    pub distance: LobbyDistance,
+    pub open_slots: Option<u8>,
+    pub max_results: Option<u64>,
+    pub strings: Vec<(String, String)>,
+    pub numbers: Vec<(String, i32, LobbyComparison)>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LobbySummary {
+    pub id: LobbyId,
+    pub members: usize,
+    pub max_members: Option<usize>,
+    pub data: HashMap<String, String>,
+}
 pub(crate) struct Connection {
     pub(crate) net: NetConnection,
     pub(crate) connected: bool,
@@ -34,31 +124,149 @@ pub struct SteamClient {
     pub(crate) buffer: Vec<NetworkingMessage>,
     #[allow(clippy::type_complexity)]
     pub(crate) lobby_list: Arc<Mutex<Option<Result<Vec<LobbyId>, SteamError>>>>,
+    pub(crate) lobby_summaries: Arc<Mutex<Option<Result<Vec<LobbySummary>, SteamError>>>>,
     pub(crate) ban_list: Vec<PeerId>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) path: SteamPath,
+    pub(crate) settings: SteamConfig,
+    pub(crate) online: bool,
+    pub(crate) access: Access,
+    pub(crate) chat: VecDeque<(PeerId, String)>,
+    pub(crate) launch: VecDeque<String>,
+    pub(crate) auth: VecDeque<ValidateAuthTicketResponse>,
     rx: Arc<Mutex<Receiver<Result<LobbyId, SteamError>>>>,
     tx: Arc<Mutex<Sender<Result<LobbyId, SteamError>>>>,
 }
 unsafe impl Send for SteamClient {}
 unsafe impl Sync for SteamClient {}
 impl SteamClient {
+    pub fn steam(&self) -> &steamworks::Client {
+        &self.steamworks
+    }
     pub fn info(&self) -> NetworkingInfo {
-        let mut v = Vec::new();
-        let sockets = self.steamworks.networking_sockets();
-        for (peer, con) in &self.connections {
-            v.push((
+        let mut info = NetworkingInfo::default();
+        for peer in self.connections.keys() {
+            let Some(status) = self.status(*peer) else {
+                continue;
+            };
+            info.peers.push((
                 *peer,
-                sockets
-                    .get_realtime_connection_status(&con.net, 0)
-                    .unwrap()
-                    .0,
+                PeerInfo {
+                    rtt: Some(Duration::from_millis(status.ping().max(0) as u64)),
+                    loss: Some(1.0 - status.connection_quality_local().clamp(0.0, 1.0)),
+                    queued_bytes: (status.pending_reliable().max(0)
+                        + status.pending_unreliable().max(0))
+                        as usize,
+                    age: None,
+                },
             ));
+            info.steam.push((*peer, status));
         }
-        NetworkingInfo(v)
+        info
+    }
+    pub(crate) fn status(&self, peer: PeerId) -> Option<NetConnectionRealTimeInfo> {
+        let con = self.connections.get(&peer)?;
+        self.steamworks
+            .networking_sockets()
+            .get_realtime_connection_status(&con.net, 0)
+            .ok()
+            .map(|s| s.0)
+    }
+    pub(crate) fn flush(&self) {
+        self.connections.values().for_each(|c| {
+            if c.connected {
+                c.net.flush_messages().unwrap();
+            }
+        })
+    }
+    fn options(&self) -> Vec<NetworkingConfigEntry> {
+        let mut options = Vec::new();
+        let ms = |d: Duration| d.as_millis().min(i32::MAX as u128) as i32;
+        let int = |n: u32| n.min(i32::MAX as u32) as i32;
+        let settings = self.settings;
+        let values = [
+            (
+                NetworkingConfigValue::TimeoutInitial,
+                settings.timeout_initial.or(self.timeout).map(ms),
+            ),
+            (
+                NetworkingConfigValue::TimeoutConnected,
+                settings.timeout_connected.or(self.timeout).map(ms),
+            ),
+            (
+                NetworkingConfigValue::SendBufferSize,
+                settings.send_buffer_size.map(int),
+            ),
+            (
+                NetworkingConfigValue::NagleTime,
+                settings
+                    .nagle
+                    .map(|d| d.as_micros().min(i32::MAX as u128) as i32),
+            ),
+            (
+                NetworkingConfigValue::SendRateMin,
+                settings.send_rate_min.map(int),
+            ),
+            (
+                NetworkingConfigValue::SendRateMax,
+                settings.send_rate_max.map(int),
+            ),
+        ];
+        for (key, value) in values.into_iter().filter_map(|(k, v)| Some((k, v?))) {
+            options.push(NetworkingConfigEntry::new_int32(key, value));
+        }
+        match self.path {
+            SteamPath::Auto => {}
+            SteamPath::Relay => options.push(NetworkingConfigEntry::new_int32(
+                NetworkingConfigValue::P2PTransportICEEnable,
+                0,
+            )),
+            SteamPath::Direct => {
+                options.push(NetworkingConfigEntry::new_int32(
+                    NetworkingConfigValue::P2PTransportICEEnable,
+                    ICE_PRIVATE_PUBLIC,
+                ));
+                options.push(NetworkingConfigEntry::new_int32(
+                    NetworkingConfigValue::P2PTransportSDRPenalty,
+                    SDR_PENALTY_MS,
+                ));
+            }
+        }
+        options
+    }
+    pub(crate) fn route(&self, peer: PeerId) -> Option<SteamPath> {
+        let con = self.connections.get(&peer).filter(|c| c.connected)?;
+        let status = self
+            .steamworks
+            .networking_sockets()
+            .get_detailed_connection_status(&con.net)
+            .ok()?;
+        path_of(&status)
+    }
+    pub(crate) fn promote(&mut self) {
+        self.host_id = self.my_id;
+        self.listen_socket = self
+            .steamworks
+            .networking_sockets()
+            .create_listen_socket_p2p(0, self.options())
+            .ok();
+    }
+    pub(crate) fn leave(&mut self) {
+        if self.lobby_id.raw() != 0 {
+            self.steamworks.matchmaking().leave_lobby(self.lobby_id);
+        }
+        self.reset();
     }
     fn reset(&mut self) {
         self.host_id = PeerId(0);
         self.lobby_id = LobbyId::from_raw(0);
-        self.connections = Default::default();
+        for (_, con) in self.connections.drain() {
+            con.net.close(
+                NetConnectionEnd::App(AppNetConnectionEnd::generic_normal()),
+                Some("quit"),
+                true,
+            );
+        }
         self.listen_socket = None;
     }
     pub(crate) fn new(
@@ -67,6 +275,17 @@ impl SteamClient {
         peer_disconnected: ClientCallback,
     ) -> Result<Self, SteamAPIInitError> {
         let steam_client = steamworks::Client::init_app(app_id)?;
+        Ok(Self::from_client(
+            steam_client,
+            peer_connected,
+            peer_disconnected,
+        ))
+    }
+    pub(crate) fn from_client(
+        steam_client: steamworks::Client,
+        peer_connected: ClientCallback,
+        peer_disconnected: ClientCallback,
+    ) -> Self {
         steam_client.networking_utils().init_relay_network_access();
         steam_client
             .matchmaking()
@@ -74,7 +293,7 @@ impl SteamClient {
         let poll_group = steam_client.networking_sockets().create_poll_group();
         let my_id = steam_client.user().steam_id().into();
         let (tx, rx) = channel();
-        Ok(Self {
+        Self {
             steamworks: steam_client,
             my_id,
             host_id: PeerId(0),
@@ -84,12 +303,21 @@ impl SteamClient {
             peer_connected,
             peer_disconnected,
             lobby_list: Default::default(),
+            lobby_summaries: Default::default(),
             buffer: Vec::with_capacity(64),
             listen_socket: None,
             ban_list: Vec::with_capacity(32),
+            timeout: None,
+            path: SteamPath::Auto,
+            settings: SteamConfig::default(),
+            online: true,
+            access: Default::default(),
+            chat: VecDeque::new(),
+            launch: VecDeque::new(),
+            auth: VecDeque::new(),
             rx: Arc::new(rx.into()),
             tx: Arc::new(tx.into()),
-        })
+        }
     }
     pub(crate) fn host(&mut self) -> Result<(), InvalidHandle> {
         self.reset();
@@ -97,7 +325,7 @@ impl SteamClient {
         self.listen_socket = Some(
             self.steamworks
                 .networking_sockets()
-                .create_listen_socket_p2p(0, None)?,
+                .create_listen_socket_p2p(0, self.options())?,
         );
         let tx = self.tx.clone();
         self.steamworks
@@ -114,11 +342,7 @@ impl SteamClient {
             let _ = tx.lock().unwrap().send(s.map_err(|_| SteamError::Generic));
         })
     }
-    pub(crate) fn recv<T, F>(&mut self, mut f: F)
-    where
-        F: FnMut(ClientTypeRef, Message<T>),
-        T: DecodeOwned,
-    {
+    pub(crate) fn poll(&mut self, events: &mut Vec<Event>) {
         let cap = self.buffer.capacity();
         self.poll_group
             .receive_messages_into(&mut self.buffer, cap)
@@ -126,8 +350,7 @@ impl SteamClient {
         while !self.buffer.is_empty() {
             for m in &self.buffer {
                 let src = m.identity_peer().steam_id().unwrap().into();
-                let data = unpack(m.data());
-                f(ClientTypeRef::Steam(self), Message { src, data })
+                events.push(Event::Message(src, m.data().to_vec()))
             }
             self.buffer.clear();
             self.poll_group
@@ -135,32 +358,47 @@ impl SteamClient {
                 .unwrap();
         }
     }
-    pub(crate) fn recv_raw<F>(&mut self, mut f: F)
-    where
-        F: FnMut(ClientTypeRef, Message<&[u8]>),
-    {
-        let cap = self.buffer.capacity();
-        self.poll_group
-            .receive_messages_into(&mut self.buffer, cap)
-            .unwrap();
-        while !self.buffer.is_empty() {
-            for m in &self.buffer {
-                let src = m.identity_peer().steam_id().unwrap().into();
-                let data = &m.data();
-                f(ClientTypeRef::Steam(self), Message { src, data })
+    pub(crate) fn peers(&self) -> Vec<PeerId> {
+        self.connections
+            .iter()
+            .filter(|(_, c)| c.connected)
+            .map(|(p, _)| *p)
+            .collect()
+    }
+    pub(crate) fn has_peer(&self, peer: PeerId) -> bool {
+        self.connections.get(&peer).is_some_and(|c| c.connected)
+    }
+    pub(crate) fn send_packet(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        if let Some(con) = self.connections.get(&dest)
+            && con.connected
+        {
+            con.net.send_message(&packet, reliability.into())?;
+        }
+        Ok(())
+    }
+    pub(crate) fn broadcast_packet(
+        &self,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        for (_, con) in self.connections.iter() {
+            if con.connected {
+                con.net.send_message(&packet, reliability.into())?;
             }
-            self.buffer.clear();
-            self.poll_group
-                .receive_messages_into(&mut self.buffer, cap)
-                .unwrap();
         }
+        Ok(())
     }
-    fn connect(&mut self, id: SteamId) {
+    pub(crate) fn connect(&mut self, id: SteamId) {
         let peer_identity = NetworkingIdentity::new_steam_id(id);
         let connection = self
             .steamworks
             .networking_sockets()
-            .connect_p2p(peer_identity, 0, None)
+            .connect_p2p(peer_identity, 0, self.options())
             .unwrap();
         connection.set_poll_group(&self.poll_group);
         self.connections.insert(
@@ -171,7 +409,7 @@ impl SteamClient {
             },
         );
     }
-    pub(crate) fn update(&mut self) -> Result<(), SteamError> {
+    pub(crate) fn update(&mut self, events: &mut Vec<Event>) -> Result<(), SteamError> {
         while let Ok(event) = self.rx.clone().lock().unwrap().try_recv() {
             if event.is_err() {
                 self.reset();
@@ -189,13 +427,44 @@ impl SteamClient {
                 }
             }
         }
+        let before = self.connections.len();
+        let mut lost = 0;
         self.steamworks
             .clone()
             .process_callbacks(|callback| match callback {
+                CallbackResult::SteamServersDisconnected(_) => self.online = false,
+                CallbackResult::SteamServersConnected(_) => self.online = true,
+                CallbackResult::LobbyChatMsg(LobbyChatMsg {
+                    lobby,
+                    user,
+                    chat_id,
+                    ..
+                }) => {
+                    let mut buffer = [0; 4096];
+                    let entry = self.steamworks.matchmaking().get_lobby_chat_entry(
+                        lobby,
+                        chat_id,
+                        &mut buffer,
+                    );
+                    if lobby == self.lobby_id {
+                        self.chat
+                            .push_back((user.into(), String::from_utf8_lossy(entry).into_owned()))
+                    }
+                }
+                CallbackResult::GameRichPresenceJoinRequested(GameRichPresenceJoinRequested {
+                    connect,
+                    ..
+                }) => self.launch.push_back(connect),
+                CallbackResult::NewUrlLaunchParameters(_) => self
+                    .launch
+                    .push_back(self.steamworks.apps().launch_command_line()),
                 CallbackResult::GameLobbyJoinRequested(GameLobbyJoinRequested {
                     lobby_steam_id,
                     ..
                 }) => self.join(lobby_steam_id),
+                CallbackResult::ValidateAuthTicketResponse(response) => {
+                    self.auth.push_back(response)
+                }
                 CallbackResult::NetConnectionStatusChanged(NetConnectionStatusChanged {
                     connection_info,
                     ..
@@ -211,13 +480,14 @@ impl SteamClient {
                             #[cfg(feature = "log")]
                             info!("connected to {peer:?}");
                             con.connected = true;
-                            if let Some(mut c) = self.peer_connected.take() {
-                                c(ClientTypeRef::Steam(self), peer);
-                                self.peer_connected = Some(c);
-                            }
+                            self.online = true;
+                            events.push(Event::Connected(peer))
                         }
                     }
-                    Ok(NetworkingConnectionState::ClosedByPeer) => {
+                    Ok(
+                        state @ (NetworkingConnectionState::ClosedByPeer
+                        | NetworkingConnectionState::ProblemDetectedLocally),
+                    ) => {
                         let peer = connection_info
                             .identity_remote()
                             .unwrap()
@@ -226,25 +496,32 @@ impl SteamClient {
                         self.connections.remove(&peer.into());
                         #[cfg(feature = "log")]
                         info!("disconnected from {peer:?}");
-                        if let Some(mut d) = self.peer_disconnected.take() {
-                            d(ClientTypeRef::Steam(self), peer.into());
-                            self.peer_disconnected = Some(d);
+                        if state == NetworkingConnectionState::ProblemDetectedLocally {
+                            lost += 1;
                         }
+                        let reason = match connection_info.end_reason() {
+                            None if state == NetworkingConnectionState::ClosedByPeer => {
+                                DisconnectReason::Quit
+                            }
+                            end => end.into(),
+                        };
+                        events.push(Event::Disconnected(peer.into(), reason))
                     }
                     _ => {}
                 },
                 _ => {}
             });
+        if before > 0 && lost == before {
+            self.online = false;
+        }
         if let Some(listen) = &self.listen_socket {
             while let Some(event) = listen.try_receive_event() {
                 match event {
                     ListenSocketEvent::Connecting(event) => {
                         #[cfg(feature = "log")]
                         info!("connecting to someone");
-                        if self
-                            .ban_list
-                            .contains(&event.remote().steam_id().unwrap().into())
-                        {
+                        let peer = event.remote().steam_id().unwrap().into();
+                        if self.ban_list.contains(&peer) || !self.access.permits(peer) {
                             event.reject(NetConnectionEnd::Invalid, None);
                         } else {
                             event.accept()?;
@@ -261,18 +538,13 @@ impl SteamClient {
                             connected: true,
                         };
                         self.connections.insert(id.into(), connection);
-                        if let Some(mut c) = self.peer_connected.take() {
-                            c(ClientTypeRef::Steam(self), id.into());
-                            self.peer_connected = Some(c);
-                        }
+                        events.push(Event::Connected(id.into()))
                     }
                     ListenSocketEvent::Disconnected(event) => {
                         let id = event.remote().steam_id().unwrap();
                         self.connections.remove(&id.into());
-                        if let Some(mut d) = self.peer_disconnected.take() {
-                            d(ClientTypeRef::Steam(self), id.into());
-                            self.peer_disconnected = Some(d);
-                        }
+                        let reason = Some(event.end_reason()).into();
+                        events.push(Event::Disconnected(id.into(), reason));
                         #[cfg(feature = "log")]
                         info!("disconnected from {id:?}");
                     }
@@ -290,7 +562,7 @@ impl ClientTrait for SteamClient {
         reliability: Reliability,
         compression: Compression,
     ) -> Result<(), NetError> {
-        self.send_raw(dest, pack(data, compression), reliability)
+        self.send_packet(dest, pack(data, compression), reliability)
     }
     fn broadcast<T: Encode>(
         &self,
@@ -298,7 +570,7 @@ impl ClientTrait for SteamClient {
         reliability: Reliability,
         compression: Compression,
     ) -> Result<(), NetError> {
-        self.broadcast_raw(pack(data, compression), reliability)
+        self.broadcast_packet(pack(data, compression), reliability)
     }
     fn send_raw(
         &self,
@@ -306,20 +578,12 @@ impl ClientTrait for SteamClient {
         data: Vec<u8>,
         reliability: Reliability,
     ) -> Result<(), NetError> {
-        if let Some(con) = self.connections.get(&dest)
-            && con.connected
-        {
-            con.net.send_message(&data, reliability.into())?;
-        }
-        Ok(())
+        let packet = frame(Channel::DEFAULT, None, data, Compression::Uncompressed);
+        self.send_packet(dest, packet, reliability)
     }
     fn broadcast_raw(&self, data: Vec<u8>, reliability: Reliability) -> Result<(), NetError> {
-        for (_, con) in self.connections.iter() {
-            if con.connected {
-                con.net.send_message(&data, reliability.into())?;
-            }
-        }
-        Ok(())
+        let packet = frame(Channel::DEFAULT, None, data, Compression::Uncompressed);
+        self.broadcast_packet(packet, reliability)
     }
     fn my_id(&self) -> PeerId {
         self.my_id
@@ -351,14 +615,51 @@ impl ClientTrait for SteamClient {
         Some(self.steamworks.friends().get_friend(id.into()).name())
     }
 }
+impl ClientTypeRef<'_> {
+    pub fn steam(&self) -> Option<&steamworks::Client> {
+        match self {
+            ClientTypeRef::Steam(c) => Some(c.steam()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
 impl From<Reliability> for SendFlags {
     fn from(value: Reliability) -> Self {
         match value {
             Reliability::Reliable => SendFlags::RELIABLE,
-            Reliability::Unreliable => SendFlags::UNRELIABLE,
+            Reliability::ReliableUnordered => SendFlags::RELIABLE_NO_NAGLE,
+            Reliability::Unreliable | Reliability::BestEffort | Reliability::Sequenced => {
+                SendFlags::UNRELIABLE
+            }
+        }
+    }
+}
+impl From<Option<NetConnectionEnd>> for DisconnectReason {
+    fn from(value: Option<NetConnectionEnd>) -> Self {
+        match value {
+            Some(NetConnectionEnd::RemoteTimeout | NetConnectionEnd::MiscTimeout) => {
+                DisconnectReason::Timeout
+            }
+            Some(NetConnectionEnd::App(_)) => DisconnectReason::Quit,
+            _ => DisconnectReason::TransportError,
         }
     }
 }
+fn path_of(status: &str) -> Option<SteamPath> {
+    let words: Vec<String> = status
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let has = |names: &[&str]| words.iter().any(|w| names.contains(&w.as_str()));
+    if has(&["relay", "relayed", "sdr"]) {
+        Some(SteamPath::Relay)
+    } else if has(&["ice", "direct"]) {
+        Some(SteamPath::Direct)
+    } else {
+        None
+    }
+}
 impl From<SteamId> for PeerId {
     fn from(value: SteamId) -> Self {
         Self(value.raw())
@@ -371,72 +672,274 @@ impl From<PeerId> for SteamId {
 }
 impl Client {
     pub fn host_steam(&mut self) -> Result<(), InvalidHandle> {
-        self.steam_client.host()
+        self.steam_client.as_mut().ok_or(InvalidHandle)?.host()
     }
     pub fn join_steam(&mut self, lobby: LobbyId) {
-        self.steam_client.join(lobby);
-    }
-    pub fn flush(&self) {
-        self.steam_client.connections.values().for_each(|c| {
-            if c.connected {
-                c.net.flush_messages().unwrap();
-            }
-        })
+        if let Some(steam) = &mut self.steam_client {
+            steam.join(lobby);
+            self.begin_join();
+        }
     }
     pub fn set_rich_presence(&self, key: &str, value: Option<&str>) -> bool {
         self.steam_client
-            .steamworks
-            .friends()
-            .set_rich_presence(key, value)
+            .as_ref()
+            .is_some_and(|steam| steam.steamworks.friends().set_rich_presence(key, value))
     }
     pub fn clear_rich_presence(&self) {
-        self.steam_client.steamworks.friends().clear_rich_presence()
+        if let Some(steam) = &self.steam_client {
+            steam.steamworks.friends().clear_rich_presence()
+        }
     }
     pub fn get_lobby_my_data(&self, key: &str) -> Option<String> {
-        if self.steam_client.lobby_id.raw() != 0 {
-            self.get_lobby_data(self.steam_client.lobby_id, key)
+        let lobby = self.steam_client.as_ref()?.lobby_id;
+        if lobby.raw() != 0 {
+            self.get_lobby_data(lobby, key)
         } else {
             None
         }
     }
     pub fn get_lobby_data(&self, id: LobbyId, key: &str) -> Option<String> {
         self.steam_client
+            .as_ref()?
             .steamworks
             .matchmaking()
             .lobby_data(id, key)
     }
     pub fn set_lobby_my_data(&self, key: &str, value: &str) {
-        if self.steam_client.lobby_id.raw() != 0 {
-            self.set_lobby_data(self.steam_client.lobby_id, key, value);
+        if let Some(steam) = &self.steam_client
+            && steam.lobby_id.raw() != 0
+        {
+            self.set_lobby_data(steam.lobby_id, key, value);
         }
     }
     pub fn set_lobby_data(&self, id: LobbyId, key: &str, value: &str) {
-        self.steam_client
-            .steamworks
-            .matchmaking()
-            .set_lobby_data(id, key, value);
+        if let Some(steam) = &self.steam_client {
+            steam
+                .steamworks
+                .matchmaking()
+                .set_lobby_data(id, key, value);
+        }
     }
     pub fn update_lobby_list(&mut self) {
-        self.steam_client.lobby_list = Default::default();
-        let list = self.steam_client.lobby_list.clone();
-        self.steam_client
+        let Some(steam) = &mut self.steam_client else {
+            return;
+        };
+        steam.lobby_list = Default::default();
+        let list = steam.lobby_list.clone();
+        steam
             .steamworks
             .matchmaking()
             .request_lobby_list(move |data| {
                 *list.lock().unwrap() = Some(data);
             });
     }
+    pub fn request_lobby_list(&mut self, filter: &LobbyFilter) {
+        let Some(client) = &mut self.steam_client else {
+            return;
+        };
+        client.lobby_summaries = Default::default();
+        let summaries = client.lobby_summaries.clone();
+        let steam = client.steamworks.clone();
+        let matchmaking = client.steamworks.matchmaking();
+        matchmaking.set_request_lobby_list_distance_filter(match filter.distance {
+            LobbyDistance::Close => DistanceFilter::Close,
+            LobbyDistance::Default => DistanceFilter::Default,
+            LobbyDistance::Far => DistanceFilter::Far,
+            LobbyDistance::Worldwide => DistanceFilter::Worldwide,
+        });
+        if let Some(slots) = filter.open_slots {
+            matchmaking.set_request_lobby_list_slots_available_filter(slots);
+        }
+        if let Some(count) = filter.max_results {
+            matchmaking.set_request_lobby_list_result_count_filter(count);
+        }
+        for (key, value) in &filter.strings {
+            matchmaking.set_request_lobby_list_string_filter(StringFilter(
+                LobbyKey::new(key),
+                value,
+                StringFilterKind::Equal,
+            ));
+        }
+        for (key, value, comparison) in &filter.numbers {
+            matchmaking.set_request_lobby_list_numerical_filter(NumberFilter(
+                LobbyKey::new(key),
+                *value,
+                match comparison {
+                    LobbyComparison::Equal => ComparisonFilter::Equal,
+                    LobbyComparison::NotEqual => ComparisonFilter::NotEqual,
+                    LobbyComparison::Less => ComparisonFilter::LessThan,
+                    LobbyComparison::LessOrEqual => ComparisonFilter::EqualToOrLessThan,
+                    LobbyComparison::Greater => ComparisonFilter::GreaterThan,
+                    LobbyComparison::GreaterOrEqual => ComparisonFilter::EqualToOrGreaterThan,
+                },
+            ));
+        }
+        matchmaking.request_lobby_list(move |data| {
+            let matchmaking = steam.matchmaking();
+            let data = data.map(|ids| {
+                ids.into_iter()
+                    .map(|id| LobbySummary {
+                        id,
+                        members: matchmaking.lobby_member_count(id),
+                        max_members: matchmaking.lobby_member_limit(id),
+                        data: (0..matchmaking.lobby_data_count(id))
+                            .filter_map(|i| matchmaking.lobby_data_by_index(id, i))
+                            .collect(),
+                    })
+                    .collect()
+            });
+            *summaries.lock().unwrap() = Some(data);
+        });
+        matchmaking.set_request_lobby_list_distance_filter(DistanceFilter::Worldwide);
+    }
+    pub fn lobby_summaries(&self) -> Option<Result<Vec<LobbySummary>, SteamError>> {
+        self.steam_client
+            .as_ref()?
+            .lobby_summaries
+            .lock()
+            .unwrap()
+            .clone()
+    }
     pub fn lobby_list(&self) -> Option<Result<Vec<LobbyId>, SteamError>> {
-        self.steam_client.lobby_list.lock().unwrap().clone()
+        self.steam_client
+            .as_ref()?
+            .lobby_list
+            .lock()
+            .unwrap()
+            .clone()
     }
     pub fn disconnect(&mut self, peer: PeerId) {
-        self.steam_client.connections.remove(&peer);
+        if let Some(steam) = &mut self.steam_client {
+            steam.connections.remove(&peer);
+        }
     }
     pub fn ban(&mut self, peer: PeerId) {
         self.disconnect(peer);
-        self.steam_client.ban_list.push(peer)
+        if let Some(steam) = &mut self.steam_client {
+            steam.ban_list.push(peer)
+        }
     }
     pub fn unban(&mut self, peer: PeerId) {
-        self.steam_client.ban_list.retain(|p| *p != peer)
+        if let Some(steam) = &mut self.steam_client {
+            steam.ban_list.retain(|p| *p != peer)
+        }
+    }
+    pub fn steam(&self) -> Option<&steamworks::Client> {
+        self.steam_client.as_ref().map(SteamClient::steam)
+    }
+    pub fn peer_avatar(&self, peer: PeerId, size: AvatarSize) -> Option<Avatar> {
+        if self.is_ip() {
+            return None;
+        }
+        let friend = self
+            .steam_client
+            .as_ref()?
+            .steamworks
+            .friends()
+            .get_friend(peer.into());
+        let (rgba, side) = match size {
+            AvatarSize::Small => (friend.small_avatar()?, 32),
+            AvatarSize::Medium => (friend.medium_avatar()?, 64),
+            AvatarSize::Large => (friend.large_avatar()?, 184),
+        };
+        Some(Avatar {
+            width: side,
+            height: side,
+            rgba,
+        })
     }
+    /// Best effort: read from Steam's detailed status text, `None` when it names neither path.
+    pub fn steam_route(&self, peer: PeerId) -> Option<SteamPath> {
+        if self.is_ip() {
+            return None;
+        }
+        self.steam_client.as_ref()?.route(peer)
+    }
+    pub fn send_lobby_chat(&self, message: &str) -> Result<(), NetError> {
+        let Some(steam) = &self.steam_client else {
+            return Err(NetError::Disconnected);
+        };
+        let lobby = steam.lobby_id;
+        if self.is_ip() || lobby.raw() == 0 {
+            return Err(NetError::Disconnected);
+        }
+        steam
+            .steamworks
+            .matchmaking()
+            .send_lobby_chat_message(lobby, message.as_bytes())?;
+        Ok(())
+    }
+    pub fn steam_online(&self) -> bool {
+        self.steam_client.as_ref().is_some_and(|steam| steam.online)
+    }
+    pub(crate) fn lobby_chat(&mut self) {
+        let Some(steam) = &mut self.steam_client else {
+            return;
+        };
+        for (peer, message) in steam.chat.drain(..) {
+            self.net_events
+                .push_back(NetEvent::LobbyChat { peer, message })
+        }
+    }
+    pub(crate) fn steam_launch(&mut self) {
+        while let Some(line) = self
+            .steam_client
+            .as_mut()
+            .and_then(|steam| steam.launch.pop_front())
+        {
+            if let Some(join) = PendingJoin::parse_str(&line) {
+                self.set_pending_join(join)
+            }
+        }
+    }
+    pub(crate) fn steam_status(&mut self) {
+        let Some(online) = self.steam_client.as_ref().map(|steam| steam.online) else {
+            return;
+        };
+        if self.is_ip() || online == self.steam_online {
+            return;
+        }
+        self.steam_online = online;
+        if self.steam_online {
+            self.net_events.push_back(NetEvent::SteamRestored);
+            return;
+        }
+        self.net_events.push_back(NetEvent::SteamUnavailable);
+        #[cfg(feature = "tangled")]
+        self.fallback_to_ip();
+    }
+    #[cfg(feature = "tangled")]
+    fn fallback_to_ip(&mut self) {
+        let host = self.is_host();
+        let Some(addr) = (if host {
+            self.config.steam_fallback
+        } else {
+            self.fallback
+        }) else {
+            return;
+        };
+        self.end_session(DisconnectReason::TransportError);
+        let (connected, disconnected) = self.steam_client.as_mut().map_or((None, None), |steam| {
+            (steam.peer_connected.take(), steam.peer_disconnected.take())
+        });
+        let result = if host {
+            self.host_ip(addr.port(), connected, disconnected)
+        } else {
+            self.join_ip(addr, connected, disconnected)
+        };
+        if result.is_ok() {
+            self.net_events.push_back(NetEvent::IpFallback { addr })
+        }
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_path_of() {
+    assert_eq!(
+        path_of("Transport: SDR relay via iad"),
+        Some(SteamPath::Relay)
+    );
+    assert_eq!(path_of("Transport: ICE (public)"), Some(SteamPath::Direct));
+    assert_eq!(path_of("device price"), None);
+    assert_eq!(path_of(""), None);
 }
@@ -0,0 +1,61 @@
+use crate::{Channel, Reliability};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+pub(crate) struct History {
+    keep: usize,
+    ttl: Option<Duration>,
+    messages: VecDeque<(Instant, Vec<u8>, Reliability)>,
+}
+impl History {
+    fn trim(&mut self) {
+        while self.messages.len() > self.keep {
+            self.messages.pop_front();
+        }
+        if let Some(ttl) = self.ttl {
+            while self
+                .messages
+                .front()
+                .is_some_and(|(t, _, _)| t.elapsed() > ttl)
+            {
+                self.messages.pop_front();
+            }
+        }
+    }
+}
+#[derive(Default)]
+pub(crate) struct Sticky(Mutex<HashMap<Channel, History>>);
+impl Sticky {
+    pub(crate) fn set(&self, channel: Channel, keep: usize, ttl: Option<Duration>) {
+        let mut channels = self.0.lock().unwrap();
+        if keep == 0 {
+            channels.remove(&channel);
+            return;
+        }
+        let history = channels.entry(channel).or_insert_with(|| History {
+            keep,
+            ttl,
+            messages: VecDeque::with_capacity(keep),
+        });
+        history.keep = keep;
+        history.ttl = ttl;
+        history.trim();
+    }
+    pub(crate) fn retain(&self, channel: Channel, packet: &[u8], reliability: Reliability) {
+        if let Some(history) = self.0.lock().unwrap().get_mut(&channel) {
+            history
+                .messages
+                .push_back((Instant::now(), packet.to_vec(), reliability));
+            history.trim();
+        }
+    }
+    pub(crate) fn history(&self) -> Vec<(Vec<u8>, Reliability)> {
+        let mut channels = self.0.lock().unwrap();
+        let mut v = Vec::new();
+        for history in channels.values_mut() {
+            history.trim();
+            v.extend(history.messages.iter().map(|(_, p, r)| (p.clone(), *r)));
+        }
+        v
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{Client, MsgType, NetError, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+const CHUNK: usize = 16 * 1024;
+const WINDOW: u64 = 256 * 1024;
+#[derive(Default)]
+struct Shared {
+    buf: VecDeque<u8>,
+    closed: bool,
+    failed: bool,
+    total: u64,
+}
+pub struct NetStreamWriter {
+    id: u64,
+    peer: PeerId,
+    shared: Arc<Mutex<Shared>>,
+}
+impl NetStreamWriter {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn peer(&self) -> PeerId {
+        self.peer
+    }
+    pub fn buffered(&self) -> usize {
+        self.shared.lock().unwrap().buf.len()
+    }
+    pub fn finish(self) {
+        self.shared.lock().unwrap().closed = true
+    }
+}
+impl Write for NetStreamWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.failed {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        let n = (WINDOW as usize - shared.buf.len()).min(data.len());
+        if n == 0 && !data.is_empty() {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+        shared.buf.extend(&data[..n]);
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+pub struct NetStreamReader {
+    id: u64,
+    peer: PeerId,
+    shared: Arc<Mutex<Shared>>,
+}
+impl NetStreamReader {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn peer(&self) -> PeerId {
+        self.peer
+    }
+    pub fn is_finished(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.closed && shared.buf.is_empty()
+    }
+}
+impl Read for NetStreamReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.buf.is_empty() {
+            return if shared.failed {
+                Err(ErrorKind::ConnectionAborted.into())
+            } else if shared.closed {
+                Ok(0)
+            } else {
+                Err(ErrorKind::WouldBlock.into())
+            };
+        }
+        let n = shared.buf.read(out)?;
+        shared.total += n as u64;
+        Ok(n)
+    }
+}
+struct Outgoing {
+    shared: Arc<Mutex<Shared>>,
+    sent: u64,
+    acked: u64,
+}
+struct Incoming {
+    shared: Arc<Mutex<Shared>>,
+    acked: u64,
+}
+#[derive(Default)]
+pub(crate) struct Streams {
+    next: u64,
+    outgoing: HashMap<(PeerId, u64), Outgoing>,
+    incoming: HashMap<(PeerId, u64), Incoming>,
+    pub(crate) readers: HashMap<(PeerId, u64), NetStreamReader>,
+}
+impl Streams {
+    pub(crate) fn open(&mut self, peer: PeerId) -> NetStreamWriter {
+        let id = self.next;
+        self.next += 1;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        self.outgoing.insert(
+            (peer, id),
+            Outgoing {
+                shared: shared.clone(),
+                sent: 0,
+                acked: 0,
+            },
+        );
+        NetStreamWriter { id, peer, shared }
+    }
+    pub(crate) fn opened(&mut self, peer: PeerId, id: u64) {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        self.incoming.insert(
+            (peer, id),
+            Incoming {
+                shared: shared.clone(),
+                acked: 0,
+            },
+        );
+        self.readers
+            .insert((peer, id), NetStreamReader { id, peer, shared });
+    }
+    pub(crate) fn data(&mut self, peer: PeerId, id: u64, data: &[u8]) {
+        if let Some(i) = self.incoming.get(&(peer, id)) {
+            i.shared.lock().unwrap().buf.extend(data)
+        }
+    }
+    pub(crate) fn ack(&mut self, peer: PeerId, id: u64, consumed: u64) {
+        if let Some(o) = self.outgoing.get_mut(&(peer, id)) {
+            o.acked = consumed.min(o.sent)
+        }
+    }
+    pub(crate) fn end(&mut self, peer: PeerId, id: u64) {
+        if let Some(i) = self.incoming.remove(&(peer, id)) {
+            i.shared.lock().unwrap().closed = true
+        }
+    }
+    pub(crate) fn reset(&mut self, peer: PeerId, id: u64) {
+        if let Some(o) = self.outgoing.remove(&(peer, id)) {
+            o.shared.lock().unwrap().failed = true
+        }
+    }
+    pub(crate) fn pump(&mut self) -> Vec<(PeerId, MsgType<'static>)> {
+        let mut packets = Vec::new();
+        self.outgoing.retain(|&(peer, id), o| {
+            let detached = Arc::strong_count(&o.shared) == 1;
+            let mut shared = o.shared.lock().unwrap();
+            while o.sent - o.acked < WINDOW && !shared.buf.is_empty() {
+                let n = CHUNK.min(shared.buf.len());
+                let data: Vec<u8> = shared.buf.drain(..n).collect();
+                o.sent += n as u64;
+                packets.push((peer, MsgType::StreamData { id, data }));
+            }
+            if (shared.closed || detached) && shared.buf.is_empty() {
+                packets.push((peer, MsgType::StreamEnd(id)));
+                return false;
+            }
+            true
+        });
+        self.incoming.retain(|&(peer, id), i| {
+            if Arc::strong_count(&i.shared) == 1 {
+                packets.push((peer, MsgType::StreamReset(id)));
+                return false;
+            }
+            let total = i.shared.lock().unwrap().total;
+            if total > i.acked {
+                i.acked = total;
+                packets.push((
+                    peer,
+                    MsgType::StreamAck {
+                        id,
+                        consumed: total,
+                    },
+                ));
+            }
+            true
+        });
+        packets
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        for (_, i) in self.incoming.extract_if(|(p, _), _| *p == peer) {
+            let mut shared = i.shared.lock().unwrap();
+            shared.closed = true;
+            shared.failed = true;
+        }
+        for (_, o) in self.outgoing.extract_if(|(p, _), _| *p == peer) {
+            o.shared.lock().unwrap().failed = true
+        }
+        self.readers.retain(|(p, _), _| *p != peer);
+    }
+}
+impl Client {
+    pub(crate) fn pump_streams(&mut self) {
+        for (peer, msg) in self.streams.pump() {
+            let _ = self.send_packet(peer, encode(&msg), Reliability::Reliable);
+        }
+    }
+    pub fn open_stream(&mut self, peer: PeerId) -> Result<NetStreamWriter, NetError> {
+        let writer = self.streams.open(peer);
+        let packet = encode(&MsgType::StreamOpen(writer.id()));
+        self.send_packet(peer, packet, Reliability::Reliable)?;
+        Ok(writer)
+    }
+    pub fn stream_reader(&mut self, peer: PeerId, id: u64) -> Option<NetStreamReader> {
+        self.streams.readers.remove(&(peer, id))
+    }
+}
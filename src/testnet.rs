@@ -0,0 +1,534 @@
+use crate::NetConfig;
+use crate::seal::Seal;
+use crate::{
+    Channel, Client, ClientMode, ClientTrait, Compression, DisconnectReason, Event, NetError,
+    PeerId, Reliability, frame, pack,
+};
+use bitcode::Encode;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[derive(Default)]
+struct Switch {
+    now: Duration,
+    latency: Duration,
+    members: Vec<PeerId>,
+    in_flight: VecDeque<(Duration, PeerId, PeerId, Vec<u8>)>,
+    delivered: HashMap<PeerId, Vec<Event>>,
+}
+impl Switch {
+    fn connect(&mut self, peer: PeerId) {
+        for member in self.members.clone() {
+            self.delivered
+                .entry(member)
+                .or_default()
+                .push(Event::Connected(peer));
+            self.delivered
+                .entry(peer)
+                .or_default()
+                .push(Event::Connected(member));
+        }
+        self.members.push(peer);
+    }
+    fn disconnect(&mut self, peer: PeerId) {
+        self.members.retain(|p| *p != peer);
+        self.delivered.remove(&peer);
+        self.in_flight
+            .retain(|(_, src, dest, _)| *src != peer && *dest != peer);
+        for member in &self.members {
+            self.delivered
+                .entry(*member)
+                .or_default()
+                .push(Event::Disconnected(peer, DisconnectReason::Quit));
+        }
+    }
+    fn step(&mut self, dt: Duration) {
+        self.now += dt;
+        while self
+            .in_flight
+            .front()
+            .is_some_and(|(due, ..)| *due <= self.now)
+        {
+            let (_, src, dest, packet) = self.in_flight.pop_front().unwrap();
+            self.delivered
+                .entry(dest)
+                .or_default()
+                .push(Event::Message(src, packet));
+        }
+    }
+}
+pub struct Loopback {
+    id: PeerId,
+    switch: Arc<Mutex<Switch>>,
+    seal: Seal,
+}
+impl Loopback {
+    pub(crate) fn send_packet(
+        &self,
+        dest: PeerId,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        let packet = self.seal.apply(packet, reliability)?;
+        let mut switch = self.switch.lock().unwrap();
+        if !switch.members.contains(&dest) {
+            return Err(NetError::UnknownPeer(dest));
+        }
+        let due = switch.now + switch.latency;
+        switch.in_flight.push_back((due, self.id, dest, packet));
+        Ok(())
+    }
+    pub(crate) fn broadcast_packet(
+        &self,
+        packet: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        for peer in self.peers() {
+            self.send_packet(peer, packet.clone(), reliability)?;
+        }
+        Ok(())
+    }
+    pub(crate) fn peers(&self) -> Vec<PeerId> {
+        let switch = self.switch.lock().unwrap();
+        switch
+            .members
+            .iter()
+            .copied()
+            .filter(|p| *p != self.id)
+            .collect()
+    }
+    pub(crate) fn has_peer(&self, peer: PeerId) -> bool {
+        peer != self.id && self.switch.lock().unwrap().members.contains(&peer)
+    }
+    pub(crate) fn leave(&self) {
+        self.switch.lock().unwrap().disconnect(self.id)
+    }
+    pub(crate) fn poll(&self, events: &mut Vec<Event>) {
+        if let Some(delivered) = self.switch.lock().unwrap().delivered.remove(&self.id) {
+            events.extend(delivered)
+        }
+    }
+}
+impl ClientTrait for Loopback {
+    fn send<T: Encode>(
+        &self,
+        dest: PeerId,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        self.send_packet(dest, pack(data, compression), reliability)
+    }
+    fn broadcast<T: Encode>(
+        &self,
+        data: &T,
+        reliability: Reliability,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        self.broadcast_packet(pack(data, compression), reliability)
+    }
+    fn send_raw(
+        &self,
+        dest: PeerId,
+        data: Vec<u8>,
+        reliability: Reliability,
+    ) -> Result<(), NetError> {
+        let packet = frame(Channel::DEFAULT, None, data, Compression::Uncompressed);
+        self.send_packet(dest, packet, reliability)
+    }
+    fn broadcast_raw(&self, data: Vec<u8>, reliability: Reliability) -> Result<(), NetError> {
+        let packet = frame(Channel::DEFAULT, None, data, Compression::Uncompressed);
+        self.broadcast_packet(packet, reliability)
+    }
+    fn my_id(&self) -> PeerId {
+        self.id
+    }
+    fn host_id(&self) -> PeerId {
+        PeerId(0)
+    }
+    fn is_host(&self) -> bool {
+        self.id == PeerId(0)
+    }
+    fn is_client(&self) -> bool {
+        self.id != PeerId(0)
+    }
+    fn peer_len(&self) -> usize {
+        self.peers().len()
+    }
+    fn is_connected(&self) -> bool {
+        self.switch.lock().unwrap().members.contains(&self.id)
+    }
+    fn mode(&self) -> ClientMode {
+        ClientMode::Loopback
+    }
+    fn get_name(&self) -> Option<String> {
+        None
+    }
+    fn get_name_of(&self, _: PeerId) -> Option<String> {
+        None
+    }
+}
+pub struct TestNet {
+    switch: Arc<Mutex<Switch>>,
+    clients: Vec<Client>,
+    next: u64,
+}
+fn offline() -> Client {
+    #[cfg(feature = "steam")]
+    {
+        Client::without_steam()
+    }
+    #[cfg(not(feature = "steam"))]
+    {
+        Client::new().unwrap()
+    }
+}
+impl TestNet {
+    pub fn new(peers: usize) -> Self {
+        Self::with_config(peers, NetConfig::default())
+    }
+    pub fn with_config(peers: usize, config: NetConfig) -> Self {
+        Self::from_clients(
+            (0..peers)
+                .map(|_| offline())
+                .map(|mut client| {
+                    *client.config_mut() = config.clone();
+                    client
+                })
+                .collect(),
+        )
+    }
+    pub fn from_clients(clients: Vec<Client>) -> Self {
+        let mut net = Self {
+            switch: Default::default(),
+            clients: Vec::new(),
+            next: 0,
+        };
+        for client in clients {
+            net.add(client);
+        }
+        net
+    }
+    pub fn add(&mut self, mut client: Client) -> PeerId {
+        let id = PeerId(self.next);
+        self.next += 1;
+        client.loopback = Some(Loopback {
+            id,
+            switch: self.switch.clone(),
+            seal: client.sealer(),
+        });
+        self.switch.lock().unwrap().connect(id);
+        self.clients.push(client);
+        id
+    }
+    pub fn remove(&mut self, peer: PeerId) -> Option<Client> {
+        let i = self.clients.iter().position(|c| c.my_id() == peer)?;
+        self.switch.lock().unwrap().disconnect(peer);
+        let mut client = self.clients.remove(i);
+        client.loopback = None;
+        Some(client)
+    }
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.switch.lock().unwrap().latency = latency
+    }
+    pub fn now(&self) -> Duration {
+        self.switch.lock().unwrap().now
+    }
+    pub fn in_flight(&self) -> usize {
+        self.switch.lock().unwrap().in_flight.len()
+    }
+    pub fn step(&mut self, dt: Duration) {
+        self.switch.lock().unwrap().step(dt);
+        for client in &mut self.clients {
+            client.poll_transport();
+            let _ = client.update();
+        }
+    }
+    pub fn run(&mut self, steps: usize, dt: Duration) {
+        for _ in 0..steps {
+            self.step(dt)
+        }
+    }
+    pub fn settle(&mut self, max_steps: usize) {
+        let latency = self.switch.lock().unwrap().latency;
+        for _ in 0..max_steps {
+            self.step(latency);
+            if self.in_flight() == 0 {
+                break;
+            }
+        }
+    }
+    pub fn host(&mut self) -> Option<&mut Client> {
+        self.client_mut(PeerId(0))
+    }
+    pub fn client(&self, peer: PeerId) -> Option<&Client> {
+        self.clients.iter().find(|c| c.my_id() == peer)
+    }
+    pub fn client_mut(&mut self, peer: PeerId) -> Option<&mut Client> {
+        self.clients.iter_mut().find(|c| c.my_id() == peer)
+    }
+    pub fn clients(&self) -> impl Iterator<Item = &Client> {
+        self.clients.iter()
+    }
+    pub fn clients_mut(&mut self) -> impl Iterator<Item = &mut Client> {
+        self.clients.iter_mut()
+    }
+}
+#[cfg(test)]
+fn joined(configs: Vec<NetConfig>) -> TestNet {
+    let mut net = TestNet::from_clients(
+        configs
+            .into_iter()
+            .map(|config| {
+                let mut client = offline();
+                *client.config_mut() = config;
+                client
+            })
+            .collect(),
+    );
+    net.settle(64);
+    net
+}
+#[cfg(test)]
+fn received(client: &mut Client) -> Vec<u32> {
+    let mut received = Vec::new();
+    client.recv::<u32, _>(|_, m| received.push(m.data));
+    received.sort();
+    received
+}
+#[cfg(test)]
+#[test]
+fn test_file_and_stream() {
+    use crate::NetEvent;
+    use std::io::{Read, Write};
+    let mut net = joined(vec![NetConfig::default(); 2]);
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let host = net.host().unwrap();
+    host.send_file(PeerId(1), 7, data.clone()).unwrap();
+    let mut writer = host.open_stream(PeerId(1)).unwrap();
+    writer.write_all(&data[..100_000]).unwrap();
+    let id = writer.id();
+    writer.finish();
+    net.run(256, Duration::from_millis(1));
+    let peer = net.client_mut(PeerId(1)).unwrap();
+    let events: Vec<_> = peer.events().collect();
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, NetEvent::FileReceived { id: 7, .. }))
+    );
+    assert_eq!(peer.take_file(PeerId(0), 7), Some(data.clone()));
+    let mut reader = peer.stream_reader(PeerId(0), id).unwrap();
+    let mut streamed = Vec::new();
+    reader.read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, data[..100_000]);
+    assert!(reader.is_finished());
+}
+#[cfg(test)]
+#[test]
+fn test_best_effort_nack() {
+    use crate::{MsgType, unpack};
+    let config = NetConfig {
+        clock_sync: None,
+        ..Default::default()
+    };
+    let mut net = joined(vec![config; 2]);
+    net.set_latency(Duration::from_millis(5));
+    let peer = net.client_mut(PeerId(1)).unwrap();
+    for i in 0..6u32 {
+        peer.send(
+            PeerId(0),
+            &i,
+            Reliability::BestEffort,
+            Compression::Uncompressed,
+        )
+        .unwrap();
+    }
+    net.step(Duration::ZERO);
+    let mut switch = net.switch.lock().unwrap();
+    let before = switch.in_flight.len();
+    switch.in_flight.retain(|(_, _, _, packet)| {
+        !matches!(unpack(packet), Some(MsgType::Upgradable { seq: 1, .. }))
+    });
+    assert_eq!(switch.in_flight.len(), before - 1);
+    drop(switch);
+    net.settle(64);
+    assert_eq!(received(net.host().unwrap()), (0..6).collect::<Vec<_>>());
+}
+#[cfg(test)]
+#[test]
+fn test_wrong_game() {
+    use crate::{NetEvent, RefuseReason};
+    let game = |id| NetConfig {
+        game_id: Some(id),
+        ..Default::default()
+    };
+    let mut net = joined(vec![game(1), game(2)]);
+    let host = net.host().unwrap();
+    assert!(host.events().any(|e| matches!(
+        e,
+        NetEvent::ConnectionRefused {
+            peer: PeerId(1),
+            reason: RefuseReason::WrongGame
+        }
+    )));
+    assert!(host.peers().is_empty());
+}
+#[cfg(feature = "hmac")]
+#[cfg(test)]
+#[test]
+fn test_hmac() {
+    let keyed = |key: &[u8]| NetConfig {
+        hmac_key: Some(key.to_vec()),
+        clock_sync: None,
+        ..Default::default()
+    };
+    let mut net = joined(vec![keyed(b"shared"), keyed(b"shared"), keyed(b"forged")]);
+    for peer in [PeerId(1), PeerId(2)] {
+        net.client(peer)
+            .unwrap()
+            .send(
+                PeerId(0),
+                &(peer.0 as u32),
+                Reliability::Reliable,
+                Compression::Uncompressed,
+            )
+            .unwrap();
+    }
+    net.settle(64);
+    let host = net.host().unwrap();
+    assert_eq!(received(host), vec![1]);
+    assert_eq!(host.rejected_packets(PeerId(1)), 0);
+    assert!(host.rejected_packets(PeerId(2)) > 0);
+    assert_eq!(
+        host.rejected_packets_total(),
+        host.rejected_packets(PeerId(2))
+    );
+}
+#[cfg(feature = "hmac")]
+#[cfg(test)]
+#[test]
+fn test_hmac_callback_send() {
+    let config = NetConfig {
+        hmac_key: Some(b"shared".to_vec()),
+        clock_sync: None,
+        ..Default::default()
+    };
+    let mut net = joined(vec![config; 2]);
+    net.host()
+        .unwrap()
+        .send(
+            PeerId(1),
+            &1u32,
+            Reliability::Reliable,
+            Compression::Uncompressed,
+        )
+        .unwrap();
+    net.settle(64);
+    net.client(PeerId(1)).unwrap().recv::<u32, _>(|client, m| {
+        client
+            .send(
+                m.src,
+                &(m.data + 1),
+                Reliability::Reliable,
+                Compression::Uncompressed,
+            )
+            .unwrap()
+    });
+    net.settle(64);
+    let host = net.host().unwrap();
+    assert_eq!(received(host), vec![2]);
+    assert_eq!(host.rejected_packets_total(), 0);
+}
+#[cfg(feature = "hmac")]
+#[cfg(test)]
+#[test]
+fn test_replay_rejected() {
+    let config = NetConfig {
+        hmac_key: Some(b"shared".to_vec()),
+        clock_sync: None,
+        ..Default::default()
+    };
+    let mut net = joined(vec![config; 2]);
+    net.set_latency(Duration::from_millis(5));
+    net.client(PeerId(1))
+        .unwrap()
+        .send(
+            PeerId(0),
+            &3u32,
+            Reliability::Reliable,
+            Compression::Uncompressed,
+        )
+        .unwrap();
+    net.step(Duration::ZERO);
+    let mut switch = net.switch.lock().unwrap();
+    let copies: Vec<_> = switch
+        .in_flight
+        .iter()
+        .filter(|(_, src, _, _)| *src == PeerId(1))
+        .cloned()
+        .collect();
+    assert!(!copies.is_empty());
+    switch.in_flight.extend(copies.iter().cloned());
+    drop(switch);
+    net.settle(64);
+    let host = net.host().unwrap();
+    assert_eq!(received(host), vec![3]);
+    assert_eq!(host.replays_detected(PeerId(1)), copies.len() as u64);
+}
+#[cfg(feature = "encrypt")]
+#[cfg(test)]
+#[test]
+fn test_encrypt() {
+    use crate::NetEvent;
+    let keyed = |key| NetConfig {
+        psk: Some([key; 32]),
+        clock_sync: None,
+        ..Default::default()
+    };
+    let mut net = joined(vec![keyed(1), keyed(1), keyed(2)]);
+    for peer in [PeerId(1), PeerId(2)] {
+        net.client(peer)
+            .unwrap()
+            .send(
+                PeerId(0),
+                &(peer.0 as u32),
+                Reliability::Reliable,
+                Compression::Uncompressed,
+            )
+            .unwrap();
+    }
+    net.settle(64);
+    let host = net.host().unwrap();
+    assert_eq!(received(host), vec![1]);
+    assert!(
+        host.events()
+            .any(|e| matches!(e, NetEvent::CryptoFailed { peer: PeerId(2) }))
+    );
+}
+#[cfg(test)]
+#[test]
+fn test_access_rules() {
+    use crate::{AccessRule, NetEvent, RefuseReason};
+    let mut host = offline();
+    host.allow_only(vec![PeerId(1).into(), PeerId(2).into()]);
+    host.deny(PeerId(2));
+    let mut net = TestNet::from_clients(vec![host]);
+    for _ in 0..3 {
+        net.add(offline());
+    }
+    net.settle(64);
+    let host = net.host().unwrap();
+    let refused: Vec<_> = host
+        .events()
+        .filter_map(|e| match e {
+            NetEvent::ConnectionRefused {
+                peer,
+                reason: RefuseReason::Denied,
+            } => Some(peer),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(refused, vec![PeerId(2), PeerId(3)]);
+    assert_eq!(host.peers(), vec![PeerId(1)]);
+}
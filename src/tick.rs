@@ -0,0 +1,69 @@
+use crate::{Client, MsgType, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashMap;
+use std::sync::Mutex;
+const BATCH: usize = 1100;
+#[derive(Copy, Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct NetworkTick(pub u64);
+#[derive(Default)]
+pub(crate) struct Ticker {
+    pub(crate) last: Option<u64>,
+    queued: Mutex<HashMap<PeerId, Vec<Vec<u8>>>>,
+}
+impl Ticker {
+    pub(crate) fn queue(&self, peer: PeerId, packet: Vec<u8>) {
+        self.queued
+            .lock()
+            .unwrap()
+            .entry(peer)
+            .or_default()
+            .push(packet)
+    }
+    pub(crate) fn drain(&mut self) -> Vec<(PeerId, Vec<Vec<u8>>)> {
+        let mut batches = Vec::new();
+        for (peer, packets) in self.queued.get_mut().unwrap().drain() {
+            batches.extend(split(packets, BATCH).into_iter().map(|b| (peer, b)));
+        }
+        batches
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.queued.get_mut().unwrap().remove(&peer);
+    }
+}
+pub(crate) fn split(packets: Vec<Vec<u8>>, max: usize) -> Vec<Vec<Vec<u8>>> {
+    let mut batches = Vec::new();
+    let mut batch = Vec::new();
+    let mut size = 0;
+    for packet in packets {
+        if size + packet.len() > max && !batch.is_empty() {
+            batches.push(std::mem::take(&mut batch));
+            size = 0;
+        }
+        size += packet.len();
+        batch.push(packet);
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    batches
+}
+impl Client {
+    pub fn tick(&self) -> Option<u64> {
+        let rate = self.config.tick_rate?;
+        Some(self.network_time().now.as_micros() as u64 * rate as u64 / 1_000_000)
+    }
+    pub(crate) fn send_tick(&mut self) {
+        let Some(tick) = self.tick() else {
+            return;
+        };
+        if self.ticker.last.is_some_and(|last| last >= tick) {
+            return;
+        }
+        self.ticker.last = Some(tick);
+        for (peer, packets) in self.ticker.drain() {
+            let packet = encode(&MsgType::Tick { tick, packets });
+            let _ = self.dispatch(peer, packet, Reliability::Unreliable);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{NetError, PeerId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub enum SendStatus {
+    Pending,
+    Acked,
+    Failed,
+}
+type Callback = Box<dyn FnOnce(SendStatus) + Send>;
+struct TicketState {
+    status: SendStatus,
+    sent: Instant,
+    waker: Option<Waker>,
+    callback: Option<Callback>,
+}
+pub struct SendTicket(Arc<Mutex<TicketState>>);
+impl Future for SendTicket {
+    type Output = Result<(), NetError>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+        match state.status {
+            SendStatus::Acked => Poll::Ready(Ok(())),
+            SendStatus::Failed => Poll::Ready(Err(NetError::Disconnected)),
+            SendStatus::Pending => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+impl SendTicket {
+    pub fn status(&self) -> SendStatus {
+        self.0.lock().unwrap().status
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap().sent.elapsed()
+    }
+    pub fn on_complete<F: FnOnce(SendStatus) + Send + 'static>(&self, f: F) {
+        let mut state = self.0.lock().unwrap();
+        if state.status == SendStatus::Pending {
+            state.callback = Some(Box::new(f));
+        } else {
+            let status = state.status;
+            drop(state);
+            f(status)
+        }
+    }
+}
+fn finish(state: &Mutex<TicketState>, status: SendStatus) {
+    let mut state = state.lock().unwrap();
+    state.status = status;
+    if let Some(waker) = state.waker.take() {
+        waker.wake()
+    }
+    let callback = state.callback.take();
+    drop(state);
+    if let Some(f) = callback {
+        f(status)
+    }
+}
+#[derive(Default)]
+pub(crate) struct Tickets {
+    next: u64,
+    pending: HashMap<u64, (PeerId, Arc<Mutex<TicketState>>)>,
+}
+impl Tickets {
+    pub(crate) fn create(&mut self, peer: PeerId) -> (u64, SendTicket) {
+        let id = self.next;
+        self.next += 1;
+        let state = Arc::new(Mutex::new(TicketState {
+            status: SendStatus::Pending,
+            sent: Instant::now(),
+            waker: None,
+            callback: None,
+        }));
+        self.pending.insert(id, (peer, state.clone()));
+        (id, SendTicket(state))
+    }
+    pub(crate) fn complete(&mut self, peer: PeerId, id: u64) {
+        if self.pending.get(&id).is_some_and(|(p, _)| *p == peer)
+            && let Some((_, state)) = self.pending.remove(&id)
+        {
+            finish(&state, SendStatus::Acked)
+        }
+    }
+    pub(crate) fn fail(&mut self, peer: PeerId) {
+        for (_, state) in self.pending.extract_if(|_, (p, _)| *p == peer) {
+            finish(&state, SendStatus::Failed)
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{Client, MsgType, NetError, NetEvent, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+const CHUNK: u64 = 16 * 1024;
+const WINDOW: u64 = 256 * 1024;
+pub(crate) type FileFilter = Option<Box<dyn FnMut(PeerId, u64, u64) -> bool + Send + Sync>>;
+pub struct FileSource {
+    reader: Box<dyn Read + Send + Sync>,
+    size: u64,
+}
+impl FileSource {
+    pub fn new<R: Read + Send + Sync + 'static>(reader: R, size: u64) -> Self {
+        Self {
+            reader: Box::new(reader),
+            size,
+        }
+    }
+}
+impl From<Vec<u8>> for FileSource {
+    fn from(value: Vec<u8>) -> Self {
+        let size = value.len() as u64;
+        Self::new(Cursor::new(value), size)
+    }
+}
+struct Outgoing {
+    source: FileSource,
+    sent: u64,
+    acked: u64,
+    accepted: bool,
+}
+struct Incoming {
+    size: u64,
+    data: Vec<u8>,
+}
+pub(crate) enum Chunk {
+    Data(u64, Vec<u8>),
+    Failed,
+}
+#[derive(Default)]
+pub(crate) struct Transfers {
+    outgoing: HashMap<(PeerId, u64), Outgoing>,
+    incoming: HashMap<(PeerId, u64), Incoming>,
+    pub(crate) received: HashMap<(PeerId, u64), Vec<u8>>,
+    pub(crate) filter: FileFilter,
+}
+impl Transfers {
+    pub(crate) fn offer(&mut self, peer: PeerId, id: u64, source: FileSource) -> u64 {
+        let size = source.size;
+        self.outgoing.insert(
+            (peer, id),
+            Outgoing {
+                source,
+                sent: 0,
+                acked: 0,
+                accepted: false,
+            },
+        );
+        size
+    }
+    pub(crate) fn accept(&mut self, peer: PeerId, id: u64) {
+        if let Some(o) = self.outgoing.get_mut(&(peer, id)) {
+            o.accepted = true
+        }
+    }
+    pub(crate) fn ack(&mut self, peer: PeerId, id: u64, received: u64) -> Option<(u64, u64)> {
+        let o = self.outgoing.get_mut(&(peer, id))?;
+        o.acked = received.min(o.sent);
+        let progress = (o.acked, o.source.size);
+        if o.acked == o.source.size {
+            self.outgoing.remove(&(peer, id));
+        }
+        Some(progress)
+    }
+    pub(crate) fn pump(&mut self) -> Vec<(PeerId, u64, Chunk)> {
+        let mut chunks = Vec::new();
+        for (&(peer, id), o) in self.outgoing.iter_mut().filter(|(_, o)| o.accepted) {
+            while o.sent < o.source.size && o.sent - o.acked < WINDOW {
+                let mut buf = vec![0; CHUNK.min(o.source.size - o.sent) as usize];
+                match o.source.reader.read(&mut buf) {
+                    Ok(n) if n > 0 => {
+                        buf.truncate(n);
+                        chunks.push((peer, id, Chunk::Data(o.sent, buf)));
+                        o.sent += n as u64;
+                    }
+                    _ => {
+                        chunks.push((peer, id, Chunk::Failed));
+                        break;
+                    }
+                }
+            }
+        }
+        for (peer, id, chunk) in &chunks {
+            if matches!(chunk, Chunk::Failed) {
+                self.outgoing.remove(&(*peer, *id));
+            }
+        }
+        chunks
+    }
+    pub(crate) fn incoming(&mut self, peer: PeerId, id: u64, size: u64, max: u64) -> bool {
+        let accept = size <= max
+            && !self.incoming.contains_key(&(peer, id))
+            && self.filter.as_mut().is_none_or(|f| f(peer, id, size));
+        if accept {
+            self.incoming.insert(
+                (peer, id),
+                Incoming {
+                    size,
+                    data: Vec::with_capacity(size.min(WINDOW) as usize),
+                },
+            );
+        }
+        accept
+    }
+    pub(crate) fn chunk(
+        &mut self,
+        peer: PeerId,
+        id: u64,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Option<Result<(u64, u64), ()>> {
+        let i = self.incoming.get_mut(&(peer, id))?;
+        let done = offset + data.len() as u64;
+        if offset != i.data.len() as u64 || done > i.size {
+            self.incoming.remove(&(peer, id));
+            return Some(Err(()));
+        }
+        i.data.extend(data);
+        let size = i.size;
+        if done == size
+            && let Some(i) = self.incoming.remove(&(peer, id))
+        {
+            self.received.insert((peer, id), i.data);
+        }
+        Some(Ok((done, size)))
+    }
+    pub(crate) fn cancel(&mut self, peer: PeerId, id: u64) -> bool {
+        let o = self.outgoing.remove(&(peer, id)).is_some();
+        self.incoming.remove(&(peer, id)).is_some() || o
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) -> Vec<u64> {
+        let mut ids: Vec<u64> = self
+            .outgoing
+            .extract_if(|(p, _), _| *p == peer)
+            .map(|((_, id), _)| id)
+            .collect();
+        ids.extend(
+            self.incoming
+                .extract_if(|(p, _), _| *p == peer)
+                .map(|((_, id), _)| id),
+        );
+        self.received.retain(|(p, _), _| *p != peer);
+        ids
+    }
+}
+impl Client {
+    pub(crate) fn pump_files(&mut self) {
+        for (peer, id, chunk) in self.transfers.pump() {
+            match chunk {
+                Chunk::Data(offset, data) => {
+                    let packet = encode(&MsgType::FileChunk { id, offset, data });
+                    let _ = self.send_packet(peer, packet, Reliability::Reliable);
+                }
+                Chunk::Failed => {
+                    let packet = encode(&MsgType::FileCancel(id));
+                    let _ = self.send_packet(peer, packet, Reliability::Reliable);
+                    self.net_events.push_back(NetEvent::FileFailed { peer, id })
+                }
+            }
+        }
+    }
+    pub fn send_file(
+        &mut self,
+        peer: PeerId,
+        id: u64,
+        source: impl Into<FileSource>,
+    ) -> Result<(), NetError> {
+        let size = self.transfers.offer(peer, id, source.into());
+        let packet = encode(&MsgType::FileOffer { id, size });
+        let result = self.send_packet(peer, packet, Reliability::Reliable);
+        if result.is_err() {
+            self.transfers.cancel(peer, id);
+        }
+        result
+    }
+    pub fn cancel_file(&mut self, peer: PeerId, id: u64) -> Result<(), NetError> {
+        if !self.transfers.cancel(peer, id) {
+            return Ok(());
+        }
+        let packet = encode(&MsgType::FileCancel(id));
+        self.send_packet(peer, packet, Reliability::Reliable)
+    }
+    pub fn set_file_filter<F>(&mut self, filter: F)
+    where
+        F: FnMut(PeerId, u64, u64) -> bool + Send + Sync + 'static,
+    {
+        self.transfers.filter = Some(Box::new(filter))
+    }
+    pub fn take_file(&mut self, peer: PeerId, id: u64) -> Option<Vec<u8>> {
+        self.transfers.received.remove(&(peer, id))
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_windowed_transfer() {
+    let peer = PeerId(1);
+    let data: Vec<u8> = (0..WINDOW * 3 / 2).map(|i| i as u8).collect();
+    let size = data.len() as u64;
+    let (mut tx, mut rx) = (Transfers::default(), Transfers::default());
+    assert_eq!(tx.offer(peer, 7, data.clone().into()), size);
+    assert!(tx.pump().is_empty());
+    tx.accept(peer, 7);
+    assert!(rx.incoming(peer, 7, size, u64::MAX));
+    assert!(!rx.incoming(peer, 7, size, u64::MAX));
+    let mut received = 0;
+    loop {
+        let chunks = tx.pump();
+        if chunks.is_empty() {
+            break;
+        }
+        assert!(chunks.len() as u64 <= WINDOW / CHUNK);
+        for (_, _, chunk) in chunks {
+            let Chunk::Data(offset, chunk) = chunk else {
+                panic!()
+            };
+            received = rx.chunk(peer, 7, offset, chunk).unwrap().unwrap().0;
+        }
+        assert_eq!(tx.ack(peer, 7, received), Some((received, size)));
+    }
+    assert_eq!(received, size);
+    assert_eq!(rx.received.remove(&(peer, 7)), Some(data));
+    assert!(!rx.incoming(peer, 8, size, size - 1));
+    assert!(rx.incoming(peer, 8, size, size));
+    assert_eq!(rx.chunk(peer, 8, CHUNK, vec![0]), Some(Err(())));
+    assert_eq!(rx.chunk(peer, 8, 0, vec![0]), None);
+}
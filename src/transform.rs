@@ -0,0 +1,193 @@
+use crate::{Channel, Client, ClientTrait, Compression, NetEntity, Reliability};
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::{With, Without};
+use bevy_ecs::resource::Resource;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Local, Query, Res, ResMut};
+use bevy_math::{Quat, Vec3};
+use bevy_transform::components::Transform;
+use bitcode::{Decode, Encode, decode};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+#[derive(Component, Copy, Debug, Clone, PartialEq)]
+pub struct SyncTransform {
+    pub send_rate: f32,
+    pub interpolate: bool,
+}
+impl Default for SyncTransform {
+    fn default() -> Self {
+        Self {
+            send_rate: 20.0,
+            interpolate: true,
+        }
+    }
+}
+#[derive(Component, Debug, Clone)]
+pub struct RemoteTransform {
+    from: (Transform, Instant),
+    to: (Transform, Instant),
+}
+impl RemoteTransform {
+    fn new(transform: Transform) -> Self {
+        let now = Instant::now();
+        Self {
+            from: (transform, now),
+            to: (transform, now),
+        }
+    }
+    fn push(&mut self, transform: Transform) {
+        self.from = self.to;
+        self.to = (transform, Instant::now());
+    }
+    pub fn target(&self) -> Transform {
+        self.to.0
+    }
+    pub fn sample(&self, now: Instant) -> Transform {
+        let interval = self.to.1 - self.from.1;
+        if interval.is_zero() {
+            return self.to.0;
+        }
+        let t = ((now - self.to.1).as_secs_f32() / interval.as_secs_f32()).min(2.0);
+        let (a, b) = (self.from.0, self.to.0);
+        Transform {
+            translation: a.translation.lerp(b.translation, t),
+            rotation: a.rotation.slerp(b.rotation, t),
+            scale: a.scale.lerp(b.scale, t),
+        }
+    }
+}
+#[derive(Encode, Decode)]
+struct TransformUpdate {
+    entity: u64,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+}
+impl TransformUpdate {
+    fn new(entity: u64, transform: &Transform) -> Self {
+        Self {
+            entity,
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            scale: transform.scale.to_array(),
+        }
+    }
+    fn transform(&self) -> Transform {
+        Transform {
+            translation: Vec3::from_array(self.translation),
+            rotation: Quat::from_array(self.rotation).normalize(),
+            scale: Vec3::from_array(self.scale),
+        }
+    }
+}
+#[derive(Resource, Copy, Debug, Clone)]
+pub struct TransformSync {
+    pub channel: Channel,
+}
+pub struct TransformSyncPlugin {
+    pub channel: Channel,
+}
+impl Default for TransformSyncPlugin {
+    fn default() -> Self {
+        Self {
+            channel: Channel(u8::MAX - 1),
+        }
+    }
+}
+impl Plugin for TransformSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TransformSync {
+            channel: self.channel,
+        })
+        .add_systems(
+            Update,
+            (send_transforms, receive_transforms, interpolate_transforms).chain(),
+        );
+    }
+}
+fn send_transforms(
+    client: Res<Client>,
+    sync: Res<TransformSync>,
+    mut last: Local<HashMap<Entity, Instant>>,
+    query: Query<(Entity, &NetEntity, &SyncTransform, &Transform), Without<RemoteTransform>>,
+) {
+    let now = Instant::now();
+    last.retain(|entity, _| query.contains(*entity));
+    for (entity, id, settings, transform) in &query {
+        let authority = client
+            .owner(id.0)
+            .map_or(client.is_host(), |o| o == client.my_id());
+        if settings.send_rate <= 0.0 || !authority {
+            continue;
+        }
+        let interval = Duration::from_secs_f32(1.0 / settings.send_rate);
+        if last.get(&entity).is_some_and(|t| now - *t < interval) {
+            continue;
+        }
+        last.insert(entity, now);
+        let _ = client.broadcast_on(
+            sync.channel,
+            &TransformUpdate::new(id.0, transform),
+            Reliability::Sequenced,
+            Compression::Uncompressed,
+        );
+    }
+}
+fn receive_transforms(
+    mut commands: Commands,
+    mut client: ResMut<Client>,
+    sync: Res<TransformSync>,
+    mut query: Query<(Entity, &NetEntity, Option<&mut RemoteTransform>), With<SyncTransform>>,
+) {
+    let messages = client.take_channel(sync.channel);
+    if messages.is_empty() {
+        return;
+    }
+    let host = client.is_host();
+    let mut latest = HashMap::new();
+    for m in messages {
+        let Ok(update) = decode::<TransformUpdate>(&m.data) else {
+            continue;
+        };
+        if !client.check_owner(update.entity, m.src) {
+            continue;
+        }
+        if host {
+            for peer in client.peers().into_iter().filter(|p| *p != m.src) {
+                let _ = client.send_on(
+                    sync.channel,
+                    peer,
+                    &update,
+                    Reliability::Sequenced,
+                    Compression::Uncompressed,
+                );
+            }
+        }
+        latest.insert(update.entity, update.transform());
+    }
+    for (entity, id, remote) in &mut query {
+        let Some(transform) = latest.remove(&id.0) else {
+            continue;
+        };
+        match remote {
+            Some(mut remote) => remote.push(transform),
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(RemoteTransform::new(transform));
+            }
+        }
+    }
+}
+fn interpolate_transforms(mut query: Query<(&SyncTransform, &RemoteTransform, &mut Transform)>) {
+    let now = Instant::now();
+    for (settings, remote, mut transform) in &mut query {
+        *transform = if settings.interpolate {
+            remote.sample(now)
+        } else {
+            remote.target()
+        };
+    }
+}
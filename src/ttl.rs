@@ -0,0 +1,97 @@
+use crate::{Channel, Client, Compression, MsgType, NetError, PeerId, Reliability};
+use bitcode::{Encode, encode};
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+#[derive(Default)]
+pub(crate) struct Expiring(Mutex<Vec<(Instant, Option<PeerId>, Vec<u8>)>>);
+impl Client {
+    pub fn send_with_ttl<T: Encode>(
+        &self,
+        channel: Channel,
+        dest: PeerId,
+        data: &T,
+        ttl: Duration,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(
+            channel,
+            Some(dest),
+            data,
+            Reliability::Unreliable,
+            compression,
+        )?;
+        self.send_expiring(Some(dest), packet, ttl)
+    }
+    pub fn broadcast_with_ttl<T: Encode>(
+        &self,
+        channel: Channel,
+        data: &T,
+        ttl: Duration,
+        compression: Compression,
+    ) -> Result<(), NetError> {
+        let packet = self.pack_for(channel, None, data, Reliability::Unreliable, compression)?;
+        self.send_expiring(None, packet, ttl)
+    }
+    fn send_expiring(
+        &self,
+        dest: Option<PeerId>,
+        packet: Vec<u8>,
+        ttl: Duration,
+    ) -> Result<(), NetError> {
+        let packet = encode(&MsgType::Expiring {
+            sent: self.clock.now(),
+            ttl: ttl.as_micros() as u64,
+            packet,
+        });
+        if self.config.batch_sends
+            || self.config.coalesce.is_some()
+            || self.config.send_queue.is_some()
+            || self.config.tick_rate.is_some()
+        {
+            self.expiring
+                .0
+                .lock()
+                .unwrap()
+                .push((Instant::now() + ttl, dest, packet));
+            return Ok(());
+        }
+        match dest {
+            Some(dest) => self.send_packet(dest, packet, Reliability::Unreliable),
+            None => self.broadcast_data(packet, Reliability::Unreliable),
+        }
+    }
+    pub(crate) fn send_expired(&self) {
+        let now = Instant::now();
+        for (deadline, dest, packet) in std::mem::take(&mut *self.expiring.0.lock().unwrap()) {
+            if deadline < now {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let peers = match dest {
+                Some(dest) => vec![dest],
+                None => self.peers(),
+            };
+            for peer in peers {
+                let _ = self.report(
+                    Some(peer),
+                    self.dispatch(peer, packet.clone(), Reliability::Unreliable),
+                );
+            }
+        }
+    }
+    pub(crate) fn expired(&self, src: PeerId, sent: u64, ttl: u64) -> bool {
+        if !self.config.discard_expired {
+            return false;
+        }
+        let Some(skew) = self.clock.skew(src) else {
+            return false;
+        };
+        let sent = sent as i64 - skew.offset_micros;
+        let expired = self.clock.now() as i64 - sent > ttl as i64;
+        if expired {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        expired
+    }
+}
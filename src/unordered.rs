@@ -0,0 +1,102 @@
+use crate::{MsgType, PeerId};
+use bitcode::encode;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+const RESEND: Duration = Duration::from_millis(200);
+const WINDOW: usize = 1024;
+#[derive(Default)]
+pub(crate) struct Unordered {
+    next: AtomicU32,
+    pending: Mutex<HashMap<(PeerId, u32), (Instant, u32, Vec<u8>)>>,
+    received: HashMap<PeerId, (HashSet<u32>, VecDeque<u32>)>,
+}
+impl Unordered {
+    pub(crate) fn stamp(&self, peer: PeerId, packet: Vec<u8>) -> Vec<u8> {
+        let id = self.next.fetch_add(1, Ordering::Relaxed);
+        let stamped = encode(&MsgType::Unordered { id, packet });
+        self.pending
+            .lock()
+            .unwrap()
+            .insert((peer, id), (Instant::now(), 0, stamped.clone()));
+        stamped
+    }
+    pub(crate) fn ack(&self, peer: PeerId, id: u32) {
+        self.pending.lock().unwrap().remove(&(peer, id));
+    }
+    pub(crate) fn due(&self, max_attempts: u32) -> (Vec<(PeerId, Vec<u8>)>, Vec<PeerId>) {
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        let mut expired = Vec::new();
+        pending.retain(|(peer, _), (t, attempts, _)| {
+            let keep = now - *t < RESEND || *attempts < max_attempts;
+            if !keep {
+                expired.push(*peer)
+            }
+            keep
+        });
+        let due = pending
+            .iter_mut()
+            .filter(|(_, (t, _, _))| now - *t >= RESEND)
+            .map(|((peer, _), (t, attempts, packet))| {
+                *t = now;
+                *attempts += 1;
+                (*peer, packet.clone())
+            })
+            .collect();
+        (due, expired)
+    }
+    pub(crate) fn receive(&mut self, peer: PeerId, id: u32) -> bool {
+        let (seen, order) = self.received.entry(peer).or_default();
+        if !seen.insert(id) {
+            return false;
+        }
+        order.push_back(id);
+        if order.len() > WINDOW
+            && let Some(old) = order.pop_front()
+        {
+            seen.remove(&old);
+        }
+        true
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.pending
+            .get_mut()
+            .unwrap()
+            .retain(|(p, _), _| *p != peer);
+        self.received.remove(&peer);
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_dedup_and_expiry() {
+    let peer = PeerId(1);
+    let mut unordered = Unordered::default();
+    assert!(unordered.receive(peer, 7));
+    assert!(!unordered.receive(peer, 7));
+    assert!(unordered.receive(PeerId(2), 7));
+    for id in 8..8 + WINDOW as u32 {
+        assert!(unordered.receive(peer, id));
+    }
+    assert!(unordered.receive(peer, 7));
+    unordered.stamp(peer, vec![1]);
+    let acked = unordered.stamp(peer, vec![2]);
+    let Some(MsgType::Unordered { id, .. }) = bitcode::decode(&acked).ok() else {
+        panic!()
+    };
+    unordered.ack(peer, id);
+    let backdate = |u: &mut Unordered| {
+        for (t, _, _) in u.pending.get_mut().unwrap().values_mut() {
+            *t -= RESEND
+        }
+    };
+    for _ in 0..2 {
+        backdate(&mut unordered);
+        let (due, expired) = unordered.due(2);
+        assert_eq!((due.len(), expired.len()), (1, 0));
+    }
+    backdate(&mut unordered);
+    assert_eq!(unordered.due(2), (vec![], vec![peer]));
+    assert!(unordered.pending.get_mut().unwrap().is_empty());
+}
@@ -0,0 +1,119 @@
+use crate::{MsgType, PeerId};
+use bitcode::encode;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+const HISTORY: u32 = 256;
+#[derive(Default)]
+struct Outgoing {
+    next: u32,
+    history: VecDeque<(u32, Vec<u8>)>,
+}
+#[derive(Default)]
+struct Incoming {
+    started: bool,
+    expected: u32,
+    missing: VecDeque<(u32, u32, bool)>,
+}
+#[derive(Default)]
+pub(crate) struct Upgrade {
+    outgoing: Mutex<HashMap<PeerId, Outgoing>>,
+    incoming: HashMap<PeerId, Incoming>,
+}
+impl Upgrade {
+    pub(crate) fn stamp(&self, peer: PeerId, packet: Vec<u8>) -> Vec<u8> {
+        let mut outgoing = self.outgoing.lock().unwrap();
+        let out = outgoing.entry(peer).or_default();
+        let seq = out.next;
+        out.next = out.next.wrapping_add(1);
+        let stamped = encode(&MsgType::Upgradable { seq, packet });
+        if out.history.len() == HISTORY as usize {
+            out.history.pop_front();
+        }
+        out.history.push_back((seq, stamped.clone()));
+        stamped
+    }
+    pub(crate) fn resend(&self, peer: PeerId, seqs: &[u32]) -> Vec<Vec<u8>> {
+        let outgoing = self.outgoing.lock().unwrap();
+        let Some(out) = outgoing.get(&peer) else {
+            return Vec::new();
+        };
+        out.history
+            .iter()
+            .filter(|(seq, _)| seqs.contains(seq))
+            .map(|(_, p)| p.clone())
+            .collect()
+    }
+    pub(crate) fn receive(&mut self, peer: PeerId, seq: u32, after: u32) -> (bool, Vec<u32>) {
+        let inc = self.incoming.entry(peer).or_default();
+        if !inc.started {
+            inc.started = true;
+            inc.expected = seq.wrapping_add(1);
+            return (true, Vec::new());
+        }
+        let ahead = seq.wrapping_sub(inc.expected);
+        if (ahead as i32) < 0 {
+            return match inc.missing.iter().position(|(s, _, _)| *s == seq) {
+                Some(i) => {
+                    inc.missing.remove(i);
+                    (true, Vec::new())
+                }
+                None => (false, Vec::new()),
+            };
+        }
+        let gap = ahead.min(HISTORY);
+        inc.missing
+            .extend((0..gap).map(|i| (seq.wrapping_sub(gap - i), 0, false)));
+        while inc.missing.len() > HISTORY as usize {
+            inc.missing.pop_front();
+        }
+        inc.expected = seq.wrapping_add(1);
+        let mut lost = Vec::new();
+        for (s, seen, nacked) in inc.missing.iter_mut().filter(|(_, _, n)| !*n) {
+            *seen += 1;
+            if *seen >= after.max(1) {
+                *nacked = true;
+                lost.push(*s);
+            }
+        }
+        (true, lost)
+    }
+    pub(crate) fn remove(&mut self, peer: PeerId) {
+        self.outgoing.get_mut().unwrap().remove(&peer);
+        self.incoming.remove(&peer);
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_nack_after_repeated_loss() {
+    let peer = PeerId(1);
+    let mut upgrade = Upgrade::default();
+    assert_eq!(upgrade.receive(peer, 0, 3), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 2, 3), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 3, 3), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 4, 3), (true, vec![1]));
+    assert_eq!(upgrade.receive(peer, 5, 3), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 1, 3), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 1, 3), (false, vec![]));
+    assert_eq!(upgrade.receive(peer, 7, 3), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 6, 3), (true, vec![]));
+}
+#[cfg(test)]
+#[test]
+fn test_sequence_wraps() {
+    let peer = PeerId(1);
+    let mut upgrade = Upgrade::default();
+    upgrade
+        .outgoing
+        .get_mut()
+        .unwrap()
+        .entry(peer)
+        .or_default()
+        .next = u32::MAX;
+    upgrade.stamp(peer, vec![1]);
+    upgrade.stamp(peer, vec![2]);
+    assert_eq!(upgrade.resend(peer, &[u32::MAX, 0]).len(), 2);
+    assert_eq!(upgrade.receive(peer, u32::MAX - 1, 1), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, 1, 1), (true, vec![u32::MAX, 0]));
+    assert_eq!(upgrade.receive(peer, 0, 1), (true, vec![]));
+    assert_eq!(upgrade.receive(peer, u32::MAX - 1, 1), (false, vec![]));
+}
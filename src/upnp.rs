@@ -0,0 +1,168 @@
+use igd_next::{Gateway, PortMappingProtocol, SearchOptions, search_gateway};
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "log")]
+use tracing::warn;
+const PMP_PORT: u16 = 5351;
+const PMP_LIFETIME: u32 = 7200;
+enum Mapped {
+    Upnp(Gateway),
+    Pmp(Ipv4Addr),
+}
+#[derive(Default)]
+struct State {
+    mapped: Option<(Mapped, SocketAddr)>,
+    cancelled: bool,
+}
+pub(crate) struct PortMapping {
+    port: u16,
+    state: Arc<Mutex<State>>,
+}
+impl PortMapping {
+    pub(crate) fn new(port: u16) -> Self {
+        let state: Arc<Mutex<State>> = Default::default();
+        let shared = state.clone();
+        thread::spawn(move || {
+            let mapped = match map_upnp(port) {
+                Ok((gateway, addr)) => (Mapped::Upnp(gateway), addr),
+                Err(_e) => match map_pmp(port) {
+                    Ok((gateway, addr)) => (Mapped::Pmp(gateway), addr),
+                    Err(_f) => {
+                        #[cfg(feature = "log")]
+                        warn!("failed to forward port {port}: upnp: {_e}, nat-pmp: {_f}");
+                        return;
+                    }
+                },
+            };
+            let pmp = match mapped.0 {
+                Mapped::Pmp(gateway) => Some(gateway),
+                Mapped::Upnp(_) => None,
+            };
+            {
+                let mut state = shared.lock().unwrap();
+                if state.cancelled {
+                    unmap(port, &mapped.0);
+                    return;
+                }
+                state.mapped = Some(mapped);
+            }
+            let Some(gateway) = pmp else {
+                return;
+            };
+            let mut renewed = Instant::now();
+            loop {
+                thread::sleep(Duration::from_secs(1));
+                if shared.lock().unwrap().cancelled {
+                    return;
+                }
+                if renewed.elapsed() >= Duration::from_secs(PMP_LIFETIME as u64 / 2) {
+                    let _ = pmp_request(gateway, port, PMP_LIFETIME);
+                    renewed = Instant::now();
+                }
+            }
+        });
+        Self { port, state }
+    }
+    pub(crate) fn external_addr(&self) -> Option<SocketAddr> {
+        self.state.lock().unwrap().mapped.as_ref().map(|(_, a)| *a)
+    }
+}
+impl Drop for PortMapping {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        if let Some((mapped, _)) = state.mapped.take() {
+            unmap(self.port, &mapped)
+        }
+    }
+}
+fn unmap(port: u16, mapped: &Mapped) {
+    match mapped {
+        Mapped::Upnp(gateway) => {
+            let _ = gateway.remove_port(PortMappingProtocol::UDP, port);
+        }
+        Mapped::Pmp(gateway) => {
+            let _ = pmp_request(*gateway, port, 0);
+        }
+    }
+}
+fn local_ip(target: SocketAddr) -> Result<IpAddr, Box<dyn Error>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(target)?;
+    Ok(socket.local_addr()?.ip())
+}
+fn map_upnp(port: u16) -> Result<(Gateway, SocketAddr), Box<dyn Error>> {
+    let gateway = search_gateway(SearchOptions::default())?;
+    let local = SocketAddr::new(local_ip(gateway.addr)?, port);
+    gateway.add_port(PortMappingProtocol::UDP, port, local, 0, "bevy_tangled")?;
+    let ip = gateway.get_external_ip()?;
+    Ok((gateway, SocketAddr::new(ip, port)))
+}
+fn default_gateway() -> Result<Ipv4Addr, Box<dyn Error>> {
+    if let Ok(routes) = std::fs::read_to_string("/proc/net/route") {
+        for line in routes.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(1) == Some(&"00000000")
+                && let Some(gateway) = fields.get(2)
+                && let Ok(raw) = u32::from_str_radix(gateway, 16)
+                && raw != 0
+            {
+                return Ok(Ipv4Addr::from(raw.to_le_bytes()));
+            }
+        }
+    }
+    let IpAddr::V4(local) = local_ip(SocketAddr::new(Ipv4Addr::new(1, 1, 1, 1).into(), 80))? else {
+        return Err("no ipv4 route".into());
+    };
+    let [a, b, c, _] = local.octets();
+    Ok(Ipv4Addr::new(a, b, c, 1))
+}
+fn pmp_exchange(
+    gateway: Ipv4Addr,
+    request: &[u8],
+    response: &mut [u8],
+) -> Result<usize, Box<dyn Error>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(SocketAddr::new(gateway.into(), PMP_PORT))?;
+    let mut timeout = Duration::from_millis(250);
+    for _ in 0..4 {
+        socket.send(request)?;
+        socket.set_read_timeout(Some(timeout))?;
+        match socket.recv(response) {
+            Ok(n) if n >= 4 && response[1] == request[1] | 128 => {
+                let result = u16::from_be_bytes([response[2], response[3]]);
+                if result != 0 {
+                    return Err(format!("nat-pmp result code {result}").into());
+                }
+                return Ok(n);
+            }
+            _ => timeout *= 2,
+        }
+    }
+    Err("nat-pmp gateway did not respond".into())
+}
+fn pmp_request(gateway: Ipv4Addr, port: u16, lifetime: u32) -> Result<u16, Box<dyn Error>> {
+    let mut request = [0u8; 12];
+    request[1] = 1;
+    request[4..6].copy_from_slice(&port.to_be_bytes());
+    request[6..8].copy_from_slice(&if lifetime == 0 { 0 } else { port }.to_be_bytes());
+    request[8..12].copy_from_slice(&lifetime.to_be_bytes());
+    let mut response = [0u8; 16];
+    if pmp_exchange(gateway, &request, &mut response)? < 16 {
+        return Err("short nat-pmp mapping response".into());
+    }
+    Ok(u16::from_be_bytes([response[10], response[11]]))
+}
+fn map_pmp(port: u16) -> Result<(Ipv4Addr, SocketAddr), Box<dyn Error>> {
+    let gateway = default_gateway()?;
+    let mut response = [0u8; 12];
+    if pmp_exchange(gateway, &[0, 0], &mut response)? < 12 {
+        return Err("short nat-pmp address response".into());
+    }
+    let ip = Ipv4Addr::new(response[8], response[9], response[10], response[11]);
+    let external = pmp_request(gateway, port, PMP_LIFETIME)?;
+    Ok((gateway, SocketAddr::new(ip.into(), external)))
+}
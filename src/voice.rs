@@ -0,0 +1,107 @@
+use crate::{Client, ClientTrait, MsgType, PeerId, Reliability};
+use bitcode::encode;
+use std::collections::{HashSet, VecDeque};
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceFrame {
+    pub peer: PeerId,
+    pub data: Vec<u8>,
+    pub sample_rate: Option<u32>,
+}
+#[derive(Default)]
+pub(crate) struct Voice {
+    pub(crate) transmitting: bool,
+    pub(crate) muted: HashSet<PeerId>,
+    pub(crate) frames: VecDeque<VoiceFrame>,
+    pub(crate) sample_rate: Option<u32>,
+}
+impl Client {
+    pub fn set_push_to_talk(&mut self, active: bool) {
+        if self.voice.transmitting == active {
+            return;
+        }
+        let Some(steam) = &self.steam_client else {
+            return;
+        };
+        self.voice.transmitting = active;
+        let user = steam.steamworks.user();
+        if active {
+            user.start_voice_recording()
+        } else {
+            user.stop_voice_recording()
+        }
+    }
+    pub fn is_transmitting(&self) -> bool {
+        self.voice.transmitting
+    }
+    pub fn set_voice_decoding(&mut self, sample_rate: Option<u32>) {
+        self.voice.sample_rate = sample_rate
+    }
+    pub fn mute(&mut self, peer: PeerId) {
+        self.voice.muted.insert(peer);
+        self.voice.frames.retain(|f| f.peer != peer);
+    }
+    pub fn unmute(&mut self, peer: PeerId) {
+        self.voice.muted.remove(&peer);
+    }
+    pub fn is_muted(&self, peer: PeerId) -> bool {
+        self.voice.muted.contains(&peer)
+    }
+    pub fn voice_frames(&mut self) -> impl Iterator<Item = VoiceFrame> + '_ {
+        self.voice.frames.drain(..)
+    }
+    pub(crate) fn pump_voice(&mut self) {
+        if self.is_ip() || !self.voice.transmitting {
+            return;
+        }
+        let Some(steam) = &self.steam_client else {
+            return;
+        };
+        let user = steam.steamworks.user();
+        if !user.get_available_voice().is_ok_and(|n| n > 0) {
+            return;
+        }
+        let Ok(data) = user.get_voice() else {
+            return;
+        };
+        let packet = encode(&MsgType::Voice {
+            speaker: self.my_id(),
+            data,
+        });
+        let _ = self.broadcast_packet(packet, Reliability::Unreliable);
+    }
+    pub(crate) fn receive_voice(&mut self, src: PeerId, speaker: PeerId, data: Vec<u8>) {
+        if self.is_host() {
+            if speaker != src {
+                return;
+            }
+            let packet = encode(&MsgType::Voice {
+                speaker,
+                data: data.clone(),
+            });
+            for peer in self.peers().into_iter().filter(|p| *p != src) {
+                let _ = self.send_packet(peer, packet.clone(), Reliability::Unreliable);
+            }
+        } else if src != self.host_id() && speaker != src {
+            return;
+        }
+        if self.voice.muted.contains(&speaker) || speaker == self.my_id() {
+            return;
+        }
+        let data = match self.voice.sample_rate {
+            Some(rate) => match self
+                .steam_client
+                .as_ref()
+                .map(|steam| steam.steamworks.user().decompress_voice(&data, rate))
+            {
+                Some(Ok(pcm)) => pcm,
+                _ => return,
+            },
+            None => data,
+        };
+        self.voice.frames.push_back(VoiceFrame {
+            peer: speaker,
+            data,
+            sample_rate: self.voice.sample_rate,
+        });
+    }
+}